@@ -0,0 +1,163 @@
+use crate::cloudflare::models::{Record, Zone};
+use crate::error::{Error, Result};
+use crate::util;
+use crate::util::warnings::{self, Warning, WarningKind};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use tracing::{debug, warn};
+
+/// Whether API data is served from the cache instead of Cloudflare.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// How long cached zones are served instead of listing them, in seconds.
+static ZONES_TTL: AtomicU64 = AtomicU64::new(0);
+
+/// API data cached from the latest successful responses.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ApiCache {
+    /// When the cache was last written.
+    pub updated: Option<DateTime<Utc>>,
+    /// The zones from the latest zones response.
+    #[serde(default)]
+    pub zones: Vec<Zone>,
+    /// When the zones were last listed.
+    #[serde(default)]
+    pub zones_listed: Option<DateTime<Utc>>,
+    /// A fingerprint of the token the zones were listed with.
+    #[serde(default)]
+    pub zones_token: Option<u64>,
+    /// The records from the latest records response, per zone ID.
+    #[serde(default)]
+    pub records: BTreeMap<String, Vec<Record>>,
+}
+
+/// Enable or disable serving API data from the cache.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Returns whether API data is served from the cache.
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Set how long cached zones are served, in seconds. 0 disables serving
+/// cached zones while online.
+pub fn set_zones_ttl(ttl: u64) {
+    ZONES_TTL.store(ttl, Ordering::Relaxed);
+}
+
+/// Return a fingerprint of a token, to tell apart data cached for other
+/// tokens without storing them.
+pub fn fingerprint(token: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    token.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Return the cached zones, if they were listed with the token within the
+/// zones TTL.
+pub async fn fresh_zones(token: &str) -> Option<Vec<Zone>> {
+    let ttl = ZONES_TTL.load(Ordering::Relaxed);
+    if ttl == 0 {
+        return None;
+    }
+    let cache = ApiCache::load().await.ok()?;
+    let listed = cache.zones_listed?;
+    let age = Utc::now().signed_duration_since(listed).num_seconds();
+    if cache.zones_token != Some(fingerprint(token))
+        || !(0..ttl as i64).contains(&age)
+    {
+        return None;
+    }
+    debug!(age, "serving zones cached for {ttl} seconds");
+    Some(cache.zones)
+}
+
+/// Fail if offline, as the operation requires Cloudflare.
+pub fn ensure_online(operation: &str) -> Result<()> {
+    if is_offline() {
+        return Err(Error::Offline(operation.to_string()));
+    }
+    Ok(())
+}
+
+/// Return the default cache path, depending on the host OS.
+///
+/// - Linux: $XDG_CACHE_HOME/cddns/api.json or $HOME/.cache/cddns/api.json
+/// - MacOS: $HOME/Library/Caches/cddns/api.json
+/// - Windows: {FOLDERID_LocalAppData}/cddns/api.json
+/// - Else: None, caching is unavailable
+pub fn default_cache_path() -> Option<PathBuf> {
+    if let Some(base_dirs) = directories::BaseDirs::new() {
+        let mut cache_path = base_dirs.cache_dir().to_owned();
+        cache_path.push("cddns");
+        cache_path.push("api.json");
+        Some(cache_path)
+    } else {
+        warnings::emit(Warning::new(
+            WarningKind::Compatibility,
+            "cache.default_path",
+            "no cache directory found for this OS, API data will not be cached",
+        ));
+        None
+    }
+}
+
+impl ApiCache {
+    /// Read the cache, failing if it does not exist.
+    pub async fn load() -> Result<Self> {
+        let path = default_cache_path()
+            .ok_or_else(|| Error::NotFound("cache directory".into()))?;
+        let contents = match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::NotFound(format!(
+                    "cached API data at '{}'",
+                    path.display()
+                )))
+            }
+            Err(err) => return Err(Error::io(path, err)),
+        };
+        let cache: Self = serde_json::from_str(&contents)
+            .map_err(|err| Error::parse("cached API data", err))?;
+        if let Some(updated) = cache.updated {
+            debug!("serving API data cached at {updated}");
+        }
+        Ok(cache)
+    }
+
+    /// Return the cached records of a zone.
+    pub fn zone_records(&self, zone: &Zone) -> Result<&Vec<Record>> {
+        self.records.get(&zone.id).ok_or_else(|| {
+            Error::NotFound(format!("cached records for zone '{}'", zone.name))
+        })
+    }
+
+    /// Modify and save the cache. Failures are logged, as caching is
+    /// best-effort.
+    pub async fn update(modify: impl FnOnce(&mut Self)) {
+        let result: Result<()> = try {
+            let path = default_cache_path()
+                .ok_or_else(|| Error::NotFound("cache directory".into()))?;
+            let mut cache = match tokio::fs::read_to_string(&path).await {
+                Ok(contents) => {
+                    serde_json::from_str(&contents).unwrap_or_default()
+                }
+                Err(_) => Self::default(),
+            };
+            modify(&mut cache);
+            cache.updated.replace(Utc::now());
+            let contents = serde_json::to_string(&cache)
+                .map_err(|err| Error::encode("cached API data", err))?;
+            util::fs::save(path, contents).await?;
+        };
+        if let Err(err) = result {
+            warn!("unable to cache API data: {err}");
+        }
+    }
+}
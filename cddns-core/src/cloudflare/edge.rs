@@ -0,0 +1,42 @@
+use crate::ip::interface::AddressFilter;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// The IPv4 ranges of the Cloudflare edge, which proxied records resolve to.
+/// Learn more: https://www.cloudflare.com/ips/
+const IPV4_RANGES: [(Ipv4Addr, u8); 15] = [
+    (Ipv4Addr::new(173, 245, 48, 0), 20),
+    (Ipv4Addr::new(103, 21, 244, 0), 22),
+    (Ipv4Addr::new(103, 22, 200, 0), 22),
+    (Ipv4Addr::new(103, 31, 4, 0), 22),
+    (Ipv4Addr::new(141, 101, 64, 0), 18),
+    (Ipv4Addr::new(108, 162, 192, 0), 18),
+    (Ipv4Addr::new(190, 93, 240, 0), 20),
+    (Ipv4Addr::new(188, 114, 96, 0), 20),
+    (Ipv4Addr::new(197, 234, 240, 0), 22),
+    (Ipv4Addr::new(198, 41, 128, 0), 17),
+    (Ipv4Addr::new(162, 158, 0, 0), 15),
+    (Ipv4Addr::new(104, 16, 0, 0), 13),
+    (Ipv4Addr::new(104, 24, 0, 0), 14),
+    (Ipv4Addr::new(172, 64, 0, 0), 13),
+    (Ipv4Addr::new(131, 0, 72, 0), 22),
+];
+
+/// The IPv6 ranges of the Cloudflare edge, which proxied records resolve to.
+const IPV6_RANGES: [(Ipv6Addr, u8); 7] = [
+    (Ipv6Addr::new(0x2400, 0xcb00, 0, 0, 0, 0, 0, 0), 32),
+    (Ipv6Addr::new(0x2606, 0x4700, 0, 0, 0, 0, 0, 0), 32),
+    (Ipv6Addr::new(0x2803, 0xf800, 0, 0, 0, 0, 0, 0), 32),
+    (Ipv6Addr::new(0x2405, 0xb500, 0, 0, 0, 0, 0, 0), 32),
+    (Ipv6Addr::new(0x2405, 0x8100, 0, 0, 0, 0, 0, 0), 32),
+    (Ipv6Addr::new(0x2a06, 0x98c0, 0, 0, 0, 0, 0, 0), 29),
+    (Ipv6Addr::new(0x2c0f, 0xf248, 0, 0, 0, 0, 0, 0), 32),
+];
+
+/// Returns whether an address belongs to the Cloudflare edge, as when a
+/// proxied record is resolved in place of its origin value.
+pub fn is_edge(ip: IpAddr) -> bool {
+    let v4 = IPV4_RANGES.iter().map(|(ip, len)| ((*ip).into(), *len));
+    let v6 = IPV6_RANGES.iter().map(|(ip, len)| ((*ip).into(), *len));
+    v4.chain(v6)
+        .any(|(prefix, len)| AddressFilter::Prefix(prefix, len).matches(ip))
+}
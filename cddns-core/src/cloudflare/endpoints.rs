@@ -0,0 +1,439 @@
+use crate::cloudflare::cache::{self, ApiCache};
+use crate::cloudflare::models::{
+    Account, BatchRecordsResponse, CloudflareMessage, CreateRecordResponse,
+    DeleteRecordResponse, DnssecResponse, ListAccountsResponse,
+    ListRecordsResponse, ListZonesResponse, NewRecord, PatchRecordResponse,
+    Record, ResultInfo, VerifyResponse, Zone,
+};
+use crate::cloudflare::{mock, requests};
+use crate::error::{Error, Result};
+use crate::util::progress::{self, Fetch};
+use chrono::Utc;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt::Display;
+use tracing::{debug, warn};
+
+/// The most pages retrieved of a listing, guarding against malformed
+/// pagination which never ends.
+const MAX_PAGES: u32 = 1000;
+
+/// Return a list of login messages if the token is verifiable.
+pub async fn verify(token: &str) -> Result<Vec<CloudflareMessage>> {
+    if mock::is_enabled() {
+        return Ok(vec![]);
+    }
+    cache::ensure_online("verify a token")?;
+    let resp: VerifyResponse =
+        requests::get_with_timeout("/user/tokens/verify", token).await?;
+    Ok(resp.messages)
+}
+
+/// Return all Cloudflare accounts the token can access.
+pub async fn accounts(token: impl Display) -> Result<Vec<Account>> {
+    if let Some(accounts) = mock::with(|mock| mock.accounts.clone()) {
+        return accounts;
+    }
+    cache::ensure_online("list accounts")?;
+    let token = token.to_string();
+
+    let mut accounts = vec![];
+    let mut page = Some(1);
+    while let Some(page_cursor) = page {
+        debug!(page = page_cursor, "retrieving accounts");
+        let endpoint = format!("/accounts?page={page_cursor}");
+        let resp: ListAccountsResponse =
+            requests::get_with_timeout(endpoint, &token).await?;
+        let received = resp.result.len();
+        accounts.extend(resp.result);
+
+        page = next_page("accounts", page_cursor, &resp.result_info, received)?;
+    }
+    debug!("collected {} accounts", accounts.len());
+    Ok(accounts)
+}
+
+/// Return all known Cloudflare zones.
+pub async fn zones(token: impl Display) -> Result<Vec<Zone>> {
+    if let Some(zones) = mock::with(|mock| mock.zones.clone()) {
+        return zones;
+    }
+    if cache::is_offline() {
+        let zones = ApiCache::load().await?.zones;
+        debug!("collected {} cached zones", zones.len());
+        return Ok(zones);
+    }
+    let token = token.to_string();
+    if let Some(zones) = cache::fresh_zones(&token).await {
+        debug!("collected {} cached zones", zones.len());
+        return Ok(zones);
+    }
+
+    let mut zones = vec![];
+    let mut page = Some(1);
+    progress::report(Fetch::Zones { pages: 0 });
+    while let Some(page_cursor) = page {
+        debug!(page = page_cursor, "retrieving zones");
+        let endpoint = format!("/zones?order=name&page={page_cursor}");
+        let resp: ListZonesResponse =
+            requests::get_conditional(endpoint, &token).await?;
+        let received = resp.result.len();
+
+        zones.extend(resp.result.into_iter().filter(|zone| {
+            &zone.status == "active"
+                && zone.permissions.contains(&"#zone:edit".to_string())
+        }));
+
+        progress::report(Fetch::Zones { pages: page_cursor });
+        page = next_page("zones", page_cursor, &resp.result_info, received)?;
+    }
+    progress::report(Fetch::Done);
+    debug!("collected {} zones", zones.len());
+    ApiCache::update(|cache| {
+        cache.zones = zones.clone();
+        cache.zones_listed = Some(Utc::now());
+        cache.zones_token = Some(cache::fingerprint(&token));
+    })
+    .await;
+    Ok(zones)
+}
+
+/// Return the DNSSEC status of a zone, such as `active` or `disabled`, or
+/// `None` if it's unknown because Cloudflare isn't being queried.
+pub async fn dnssec(
+    zone_id: impl Display,
+    token: impl Display,
+) -> Result<Option<String>> {
+    if mock::is_enabled() || cache::is_offline() {
+        return Ok(None);
+    }
+    let endpoint = format!("/zones/{zone_id}/dnssec");
+    let resp: DnssecResponse =
+        requests::get_with_timeout(endpoint, token).await?;
+    Ok(Some(resp.result.status))
+}
+
+/// Return all known Cloudflare records.
+pub async fn records(
+    zones: &Vec<Zone>,
+    token: impl Display,
+) -> Result<Vec<Record>> {
+    if let Some(records) = mock::with(|mock| mock.records(zones)) {
+        return records;
+    }
+    if cache::is_offline() {
+        let cache = ApiCache::load().await?;
+        let mut records = vec![];
+        for zone in zones {
+            records.extend(cache.zone_records(zone)?.iter().cloned());
+        }
+        debug!("collected {} cached records", records.len());
+        return Ok(records);
+    }
+    let records = page_records(zones, token, None, is_managed).await?;
+    debug!("collected {} records", records.len());
+    ApiCache::update(|cache| {
+        for zone in zones {
+            cache.records.insert(
+                zone.id.clone(),
+                records
+                    .iter()
+                    .filter(|r| r.zone_id == zone.id)
+                    .cloned()
+                    .collect(),
+            );
+        }
+    })
+    .await;
+    Ok(records)
+}
+
+/// Return Cloudflare records of every type, such as for inspecting zones.
+/// Only A and AAAA records are cached, so these are never served offline.
+pub async fn all_records(
+    zones: &[Zone],
+    token: impl Display,
+) -> Result<Vec<Record>> {
+    if let Some(records) = mock::with(|mock| mock.records(zones)) {
+        return records;
+    }
+    cache::ensure_online("list records of all types")?;
+    let records = page_records(zones, token, None, |_| true).await?;
+    debug!("collected {} records of all types", records.len());
+    Ok(records)
+}
+
+/// A single page of a listing, instead of every page.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Page {
+    /// The number of the page, starting at 1.
+    pub number: u32,
+    /// The most results per page, or Cloudflare's default of 100.
+    pub per_page: Option<u32>,
+}
+
+impl Page {
+    /// The number of results per page if unspecified.
+    const DEFAULT_PER_PAGE: u32 = 100;
+}
+
+/// Return a single page of the Cloudflare records of each zone, with one
+/// request per zone, such as for browsing zones with many records. Only A
+/// and AAAA records are kept, unless `all_types` is set.
+pub async fn records_page(
+    zones: &[Zone],
+    token: impl Display,
+    page: Page,
+    all_types: bool,
+) -> Result<Vec<Record>> {
+    let filter = |record: &Record| all_types || is_managed(record);
+    if let Some(records) = mock::with(|mock| mock.records(zones)) {
+        let records = records?;
+        let per_page = page.per_page.unwrap_or(Page::DEFAULT_PER_PAGE) as usize;
+        let skip = (page.number.max(1) as usize - 1) * per_page;
+        return Ok(zones
+            .iter()
+            .flat_map(|zone| {
+                records
+                    .iter()
+                    .filter(|r| r.zone_id == zone.id)
+                    .skip(skip)
+                    .take(per_page)
+            })
+            .filter(|r| filter(r))
+            .cloned()
+            .collect());
+    }
+    cache::ensure_online("list a page of records")?;
+    let records = page_records(zones, token, Some(page), filter).await?;
+    debug!(page = page.number, "collected {} records", records.len());
+    Ok(records)
+}
+
+/// Returns whether a record is an A or AAAA record, which cddns manages.
+fn is_managed(record: &Record) -> bool {
+    record.record_type == "A" || record.record_type == "AAAA" && !record.locked
+}
+
+/// Retrieve every page of records of the zones, or only the given page,
+/// keeping those matching a filter.
+async fn page_records(
+    zones: &[Zone],
+    token: impl Display,
+    single: Option<Page>,
+    filter: impl Fn(&Record) -> bool,
+) -> Result<Vec<Record>> {
+    let mut records = vec![];
+    let mut pages = 0;
+    progress::report(Fetch::Records {
+        zones: 0,
+        total_zones: zones.len(),
+        pages,
+    });
+    for (i, zone) in zones.iter().enumerate() {
+        let mut page = Some(single.map_or(1, |single| single.number));
+        let beginning_amt = records.len();
+        let token = token.to_string();
+        let what = format!("records of zone '{}'", zone.name);
+        while let Some(page_cursor) = page {
+            debug!(zone = zone.id, page = page_cursor, "retrieving records");
+            let mut endpoint = format!(
+                "/zones/{}/dns_records?order=name&page={page_cursor}",
+                zone.id,
+            );
+            if let Some(per_page) = single.and_then(|single| single.per_page) {
+                endpoint.push_str(&format!("&per_page={per_page}"));
+            }
+            let resp: ListRecordsResponse =
+                requests::get_conditional(endpoint, &token).await?;
+            let received = resp.result.len();
+
+            records.extend(resp.result.into_iter().filter(&filter));
+
+            pages += 1;
+            progress::report(Fetch::Records {
+                zones: i,
+                total_zones: zones.len(),
+                pages,
+            });
+            page = match single {
+                Some(_) => None,
+                None => {
+                    next_page(&what, page_cursor, &resp.result_info, received)?
+                }
+            };
+        }
+        debug!(
+            zone_id = zone.id,
+            "received {} records",
+            records.len() - beginning_amt,
+        );
+        progress::report(Fetch::Records {
+            zones: i + 1,
+            total_zones: zones.len(),
+            pages,
+        });
+    }
+    progress::report(Fetch::Done);
+    Ok(records)
+}
+
+/// Return the page after a retrieved page of a listing, or `None` if it was
+/// the last page. Without a page count in the metadata, pages are retrieved
+/// until one isn't full.
+fn next_page(
+    what: &str,
+    page: u32,
+    info: &ResultInfo,
+    received: usize,
+) -> Result<Option<u32>> {
+    let pages = info.pages();
+    debug!(
+        page,
+        pages,
+        total_count = info.total_count,
+        "received {received} {what}"
+    );
+    let more = match pages {
+        Some(pages) => page < pages,
+        None => {
+            if page == 1 {
+                warn!("cloudflare sent no page count of {what}, retrieving pages until one isn't full");
+            }
+            match info.per_page.filter(|&per_page| per_page > 0) {
+                Some(per_page) => received >= per_page as usize,
+                None => received > 0,
+            }
+        }
+    };
+    if !more {
+        return Ok(None);
+    }
+    if page >= MAX_PAGES {
+        return Err(Error::Pagination {
+            what: what.to_string(),
+            pages: page,
+        });
+    }
+    Ok(Some(page + 1))
+}
+
+/// Create a Cloudflare record in a zone, returning the created record.
+pub async fn create_record(
+    token: impl Display,
+    zone_id: impl Display,
+    record: &NewRecord,
+) -> Result<Record> {
+    if let Some(result) =
+        mock::with(|mock| mock.create(&zone_id.to_string(), record))
+    {
+        return result?;
+    }
+    cache::ensure_online("create records")?;
+    let endpoint = format!("/zones/{zone_id}/dns_records");
+
+    let resp: CreateRecordResponse =
+        requests::post_with_timeout(endpoint, token, record).await?;
+    Ok(resp.result)
+}
+
+/// Delete a Cloudflare record.
+pub async fn delete_record(
+    token: impl Display,
+    zone_id: impl Display,
+    record_id: impl Display,
+) -> Result<()> {
+    if let Some(result) = mock::with(|mock| {
+        mock.delete(&zone_id.to_string(), &record_id.to_string())
+    }) {
+        return result?;
+    }
+    cache::ensure_online("delete records")?;
+    let endpoint = format!("/zones/{zone_id}/dns_records/{record_id}");
+
+    let resp: DeleteRecordResponse =
+        requests::delete_with_timeout(endpoint, token).await?;
+    debug!(id = resp.result.id, "deleted record");
+    Ok(())
+}
+
+/// Patch the content of a Cloudflare record, and its comment if given,
+/// returning the updated record.
+pub async fn update_record(
+    token: impl Display,
+    zone_id: impl Display,
+    record_id: impl Display,
+    ip: impl Display,
+    comment: Option<&str>,
+) -> Result<Record> {
+    if let Some(result) = mock::with(|mock| {
+        mock.update(
+            &zone_id.to_string(),
+            &record_id.to_string(),
+            &ip.to_string(),
+            comment,
+        )
+    }) {
+        return result?;
+    }
+    cache::ensure_online("update records")?;
+    let endpoint = format!("/zones/{zone_id}/dns_records/{record_id}");
+
+    let mut data = HashMap::new();
+    data.insert("content", ip.to_string());
+    if let Some(comment) = comment {
+        data.insert("comment", comment.to_string());
+    }
+
+    let resp: PatchRecordResponse =
+        requests::patch_with_timeout(endpoint, token, &data).await?;
+    Ok(resp.result)
+}
+
+/// Patch the content of many Cloudflare records in a zone, and their
+/// comments if given, with a single
+/// batch request, returning the updated records. The batch is applied
+/// atomically: either all records are updated, or none are.
+pub async fn update_records(
+    token: impl Display,
+    zone_id: impl Display,
+    patches: &[(impl Display, impl Display, Option<&str>)],
+) -> Result<Vec<Record>> {
+    if let Some(result) = mock::with(|mock| {
+        patches
+            .iter()
+            .map(|(id, ip, comment)| {
+                mock.update(
+                    &zone_id.to_string(),
+                    &id.to_string(),
+                    &ip.to_string(),
+                    *comment,
+                )
+            })
+            .collect::<Result<Vec<Record>>>()
+    }) {
+        return result?;
+    }
+    cache::ensure_online("update records")?;
+    let endpoint = format!("/zones/{zone_id}/dns_records/batch");
+
+    let data = json!({
+        "patches": patches
+            .iter()
+            .map(|(id, ip, comment)| {
+                let mut patch = json!({
+                    "id": id.to_string(),
+                    "content": ip.to_string(),
+                });
+                if let Some(comment) = comment {
+                    patch["comment"] = json!(comment);
+                }
+                patch
+            })
+            .collect::<Vec<_>>(),
+    });
+
+    let resp: BatchRecordsResponse =
+        requests::post_with_timeout(endpoint, token, &data).await?;
+    Ok(resp.result.patches)
+}
@@ -0,0 +1,118 @@
+//! Filters selecting Cloudflare zones and records.
+
+use crate::cloudflare::models::{Record, Zone};
+use crate::cloudflare::query::Query;
+use crate::config::models::ConfigOpts;
+use crate::error::{Error, Result};
+use crate::util::names;
+use regex::Regex;
+use tracing::debug;
+
+/// Find a zone matching the given identifier.
+#[tracing::instrument(level = "trace", skip_all)]
+pub fn find_zone(zones: &Vec<Zone>, id: impl Into<String>) -> Option<Zone> {
+    let id_str = id.into();
+    for z in zones {
+        if id_str == z.id || names::same(&id_str, &z.name) {
+            return Some(z.clone());
+        }
+    }
+    None
+}
+
+/// Retain zones matching the given configuration filters.
+#[tracing::instrument(level = "trace", skip_all)]
+pub fn retain_zones(zones: &mut Vec<Zone>, opts: &ConfigOpts) -> Result<()> {
+    let beginning_amt = zones.len();
+    // Filter zones by configuration options
+    if let Some(include_filters) = opts.list.include_zones.as_ref() {
+        for filter_str in include_filters {
+            debug!("applying include filter: '{}'", filter_str);
+            let pattern = Regex::new(filter_str).map_err(|err| {
+                Error::parse("include_zones regex filter", err)
+            })?;
+            zones.retain(|z| {
+                pattern.is_match(&z.id) || pattern.is_match(&z.name)
+            });
+        }
+    }
+    if let Some(account) = opts.list.account.as_ref() {
+        debug!("applying account filter: '{}'", account);
+        zones.retain(|z| {
+            z.account
+                .as_ref()
+                .is_some_and(|a| &a.id == account || &a.name == account)
+        });
+    }
+    if let Some(ignore_filters) = opts.list.ignore_zones.as_ref() {
+        for filter_str in ignore_filters {
+            debug!("applying ignore filter: '{}'", filter_str);
+            let pattern = Regex::new(filter_str).map_err(|err| {
+                Error::parse("ignore_zones regex filter", err)
+            })?;
+            zones.retain(|z| {
+                !pattern.is_match(&z.id) && !pattern.is_match(&z.name)
+            });
+        }
+    }
+    debug!("filtered out {} zones", beginning_amt - zones.len());
+    Ok(())
+}
+
+/// Find a record matching the given identifier.
+#[tracing::instrument(level = "trace", skip_all)]
+pub fn find_record(
+    records: &Vec<Record>,
+    id: impl Into<String>,
+) -> Option<Record> {
+    let id_str = id.into();
+    for r in records {
+        if id_str == r.id || names::same(&id_str, &r.name) {
+            return Some(r.clone());
+        }
+    }
+    None
+}
+
+/// Retain records matching the given configuration filters.
+#[tracing::instrument(level = "trace", skip_all)]
+pub fn retain_records(
+    records: &mut Vec<Record>,
+    opts: &ConfigOpts,
+) -> Result<()> {
+    let beginning_amt = records.len();
+    // Filter records by configuration options
+    if let Some(include_filters) = opts.list.include_records.as_ref() {
+        for filter_str in include_filters {
+            debug!("applying include filter: '{}'", filter_str);
+            let pattern = Regex::new(filter_str).map_err(|err| {
+                Error::parse("include_records regex filter", err)
+            })?;
+            records.retain(|r| {
+                pattern.is_match(&r.id) || pattern.is_match(&r.name)
+            });
+        }
+    }
+    if let Some(ignore_filters) = opts.list.ignore_records.as_ref() {
+        for filter_str in ignore_filters {
+            debug!("applying ignore filter: '{}'", filter_str);
+            let pattern = Regex::new(filter_str).map_err(|err| {
+                Error::parse("ignore_records regex filter", err)
+            })?;
+            records.retain(|r| {
+                !pattern.is_match(&r.id) && !pattern.is_match(&r.name)
+            });
+        }
+    }
+    if let Some(tag) = opts.list.tag.as_ref() {
+        debug!("applying tag filter: '{}'", tag);
+        records.retain(|r| r.has_tag(tag));
+    }
+    if let Some(query) = opts.list.query.as_ref() {
+        debug!("applying query: '{}'", query);
+        let query = Query::parse(query)?;
+        records.retain(|r| query.matches(r));
+    }
+    debug!("filtered out {} records", beginning_amt - records.len());
+    Ok(())
+}
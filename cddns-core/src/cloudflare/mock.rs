@@ -0,0 +1,125 @@
+use crate::cloudflare::models::{Account, NewRecord, Record, Zone};
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use tracing::debug;
+
+/// The mock provider, if enabled.
+static MOCK: OnceLock<Mutex<MockProvider>> = OnceLock::new();
+
+/// Synthetic zones and records, served instead of Cloudflare.
+#[derive(Debug, Default, Deserialize)]
+pub struct MockProvider {
+    #[serde(default)]
+    pub accounts: Vec<Account>,
+    #[serde(default)]
+    pub zones: Vec<Zone>,
+    #[serde(default)]
+    pub records: Vec<Record>,
+}
+
+/// Enable the mock provider, serving zones and records from a fixture file.
+pub fn init(fixture: impl AsRef<Path>) -> Result<()> {
+    let path = fixture.as_ref();
+    let contents =
+        std::fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+    let provider: MockProvider = serde_yaml::from_str(&contents)
+        .map_err(|err| Error::parse("mock fixture", err))?;
+    debug!(
+        "mocking {} zones and {} records",
+        provider.zones.len(),
+        provider.records.len()
+    );
+    MOCK.set(Mutex::new(provider)).map_err(|_| {
+        Error::Config("mock provider is already initialized".into())
+    })
+}
+
+/// Returns whether the mock provider is enabled.
+pub fn is_enabled() -> bool {
+    MOCK.get().is_some()
+}
+
+/// Access the mock provider, if enabled.
+pub fn with<T>(f: impl FnOnce(&mut MockProvider) -> T) -> Option<Result<T>> {
+    let mock = MOCK.get()?;
+    Some(
+        mock.lock()
+            .map(|mut provider| f(&mut provider))
+            .map_err(|_| Error::Config("mock provider is poisoned".into())),
+    )
+}
+
+impl MockProvider {
+    /// Return the mocked records of the given zones.
+    pub fn records(&self, zones: &[Zone]) -> Vec<Record> {
+        self.records
+            .iter()
+            .filter(|r| zones.iter().any(|z| z.id == r.zone_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Update the content of a mocked record in memory.
+    pub fn update(
+        &mut self,
+        zone_id: &str,
+        record_id: &str,
+        content: &str,
+        comment: Option<&str>,
+    ) -> Result<Record> {
+        let record = self
+            .records
+            .iter_mut()
+            .find(|r| r.zone_id == zone_id && r.id == record_id)
+            .ok_or_else(|| {
+                Error::NotFound(format!("mocked record '{record_id}'"))
+            })?;
+        debug!(id = record_id, content, "updated mocked record");
+        record.content = content.to_string();
+        if let Some(comment) = comment {
+            record.comment = Some(comment.to_string());
+        }
+        Ok(record.clone())
+    }
+
+    /// Create a mocked record in memory.
+    pub fn create(&mut self, zone_id: &str, new: &NewRecord) -> Result<Record> {
+        let zone =
+            self.zones.iter().find(|z| z.id == zone_id).ok_or_else(|| {
+                Error::NotFound(format!("mocked zone '{zone_id}'"))
+            })?;
+        let record = Record {
+            id: format!("mock{:028x}", self.records.len() + 1),
+            zone_id: zone.id.clone(),
+            zone_name: zone.name.clone(),
+            name: new.name.clone(),
+            record_type: new.record_type.clone(),
+            content: new.content.clone(),
+            locked: false,
+            proxied: new.proxied,
+            ttl: new.ttl,
+            comment: new.comment.clone(),
+            tags: vec![],
+            modified_on: None,
+        };
+        debug!(id = record.id, name = record.name, "created mocked record");
+        self.records.push(record.clone());
+        Ok(record)
+    }
+
+    /// Delete a mocked record in memory.
+    pub fn delete(&mut self, zone_id: &str, record_id: &str) -> Result<()> {
+        let idx = self
+            .records
+            .iter()
+            .position(|r| r.zone_id == zone_id && r.id == record_id)
+            .ok_or_else(|| {
+                Error::NotFound(format!("mocked record '{record_id}'"))
+            })?;
+        let record = self.records.remove(idx);
+        debug!(id = record.id, name = record.name, "deleted mocked record");
+        Ok(())
+    }
+}
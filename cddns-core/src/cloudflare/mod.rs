@@ -4,8 +4,13 @@
 //! Learn more: https://api.cloudflare.com
 
 /// The stable base URL for all Version 4 HTTPS endpoints to Cloudflare.
-pub const API_BASE: &str = "https://api.cloudflare.com/client/v4/";
+pub const API_BASE: &str = "https://api.cloudflare.com/client/v4";
 
+pub mod cache;
+pub mod edge;
 pub mod endpoints;
+pub mod filters;
+pub mod mock;
 pub mod models;
+pub mod query;
 pub mod requests;
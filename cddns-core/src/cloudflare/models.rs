@@ -0,0 +1,319 @@
+use crate::util::names;
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+#[derive(Debug, Deserialize)]
+pub struct CloudflareError {
+    pub code: i32,
+    pub message: String,
+    pub error_chain: Option<Vec<CloudflareMessage>>,
+}
+
+impl Display for CloudflareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloudflareMessage {
+    pub code: i32,
+    pub message: String,
+}
+
+impl Display for CloudflareMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloudflareResponse {
+    pub success: bool,
+    pub errors: Vec<CloudflareError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyResponse {
+    pub messages: Vec<CloudflareMessage>,
+}
+
+/// The pagination metadata of a list response, where any field may be
+/// missing in malformed responses.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ResultInfo {
+    /// The number of this page.
+    pub page: Option<u32>,
+    /// The most results per page.
+    pub per_page: Option<u32>,
+    /// The number of results on this page.
+    pub count: Option<u32>,
+    /// The number of results on all pages.
+    pub total_count: Option<u32>,
+    /// The number of pages.
+    pub total_pages: Option<u32>,
+}
+
+impl ResultInfo {
+    /// Return the number of pages, from `total_pages`, or else from
+    /// `total_count` and `per_page`, if known.
+    pub fn pages(&self) -> Option<u32> {
+        match (self.total_pages, self.total_count, self.per_page) {
+            (Some(pages), ..) if pages > 0 => Some(pages),
+            (_, Some(total_count), Some(per_page)) if per_page > 0 => {
+                Some(total_count.div_ceil(per_page))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Zone {
+    pub id: String,
+    pub name: String,
+    pub permissions: Vec<String>,
+    pub status: String,
+    /// The time the zone was last modified.
+    #[serde(default)]
+    pub modified_on: Option<DateTime<Utc>>,
+    /// Whether Cloudflare is paused for the zone, serving DNS only.
+    #[serde(default)]
+    pub paused: bool,
+    /// The account owning the zone.
+    #[serde(default)]
+    pub account: Option<Account>,
+    /// The plan of the zone.
+    #[serde(default)]
+    pub plan: Option<Plan>,
+    /// The Cloudflare nameservers assigned to the zone.
+    #[serde(default)]
+    pub name_servers: Vec<String>,
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", names::to_unicode(&self.name), self.id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Plan {
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub id: String,
+    pub zone_id: String,
+    pub zone_name: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub record_type: String,
+    pub content: String,
+    pub locked: bool,
+    /// Whether traffic is proxied through Cloudflare, in which case the
+    /// record resolves to the Cloudflare edge rather than its content.
+    #[serde(default)]
+    pub proxied: bool,
+    /// The time to live in seconds, where 1 is "Auto".
+    #[serde(default)]
+    pub ttl: u32,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The time the record was last modified.
+    #[serde(default)]
+    pub modified_on: Option<DateTime<Utc>>,
+}
+
+/// A DNS record to create.
+#[derive(Debug, Clone, Serialize)]
+pub struct NewRecord {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub record_type: String,
+    pub content: String,
+    /// The time to live in seconds, where 1 is "Auto".
+    pub ttl: u32,
+    pub proxied: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl Record {
+    /// Returns whether the record carries a tag. Tags with a value, such as
+    /// `ddns:home`, also match by their name.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| {
+            t == tag || t.split_once(':').is_some_and(|(name, _)| name == tag)
+        })
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} => {}",
+            names::to_unicode(&self.name),
+            self.id,
+            self.content
+        )?;
+        if self.proxied {
+            write!(f, " (proxied)")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListZonesResponse {
+    #[serde(default)]
+    pub result_info: ResultInfo,
+    pub result: Vec<Zone>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListAccountsResponse {
+    #[serde(default)]
+    pub result_info: ResultInfo,
+    pub result: Vec<Account>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListRecordsResponse {
+    #[serde(default)]
+    pub result_info: ResultInfo,
+    pub result: Vec<Record>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DnssecResponse {
+    pub result: Dnssec,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Dnssec {
+    /// The DNSSEC status, such as `active`, `pending` or `disabled`.
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PatchRecordResponse {
+    pub result: Record,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRecordResponse {
+    pub result: Record,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteRecordResponse {
+    pub result: DeletedRecord,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeletedRecord {
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchRecordsResponse {
+    pub result: BatchRecordsResult,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchRecordsResult {
+    #[serde(default)]
+    pub patches: Vec<Record>,
+}
+
+/// The API rate limit reported by Cloudflare response headers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The requests remaining in the current window.
+    pub remaining: Option<u64>,
+    /// The requests allowed in each window.
+    pub limit: Option<u64>,
+    /// The time until the current window resets.
+    pub reset: Option<Duration>,
+}
+
+impl RateLimit {
+    /// Parse the rate limit from response headers, supporting both the
+    /// `ratelimit`/`ratelimit-policy` and `x-ratelimit-*` conventions.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| {
+            headers.get(name).and_then(|value| value.to_str().ok())
+        };
+        // Structured fields, e.g. `"default";r=1199;t=300`
+        let param = |value: Option<&str>, key: &str| {
+            value?
+                .split(',')
+                .next()?
+                .split(';')
+                .filter_map(|param| param.trim().split_once('='))
+                .find(|(k, _)| *k == key)
+                .and_then(|(_, v)| v.trim().parse::<u64>().ok())
+        };
+        let (ratelimit, policy) =
+            (header("ratelimit"), header("ratelimit-policy"));
+        let rate_limit = RateLimit {
+            remaining: param(ratelimit, "r").or_else(|| {
+                header("x-ratelimit-remaining").and_then(|v| v.parse().ok())
+            }),
+            limit: param(policy, "q").or_else(|| {
+                header("x-ratelimit-limit").and_then(|v| v.parse().ok())
+            }),
+            reset: param(ratelimit, "t")
+                .or_else(|| {
+                    header("x-ratelimit-reset").and_then(|v| v.parse().ok())
+                })
+                .map(Duration::from_secs),
+        };
+        if rate_limit == RateLimit::default() {
+            None
+        } else {
+            Some(rate_limit)
+        }
+    }
+}
+
+impl Display for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unknown = || "?".to_string();
+        write!(
+            f,
+            "{} of {} requests remaining",
+            self.remaining
+                .map(|r| r.to_string())
+                .unwrap_or_else(unknown),
+            self.limit.map(|l| l.to_string()).unwrap_or_else(unknown),
+        )?;
+        if let Some(reset) = self.reset {
+            write!(f, ", resets in {}", humantime::format_duration(reset))?;
+        }
+        Ok(())
+    }
+}
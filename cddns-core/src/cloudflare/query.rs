@@ -0,0 +1,369 @@
+//! A filter expression language selecting Cloudflare records.
+//!
+//! A query is made of terms comparing a record field to a value, e.g.
+//! `type:A AND name~'^vpn\.' AND proxied:false`.
+//!
+//! - `field:value` matches a field equal to the value. Names, zones and types
+//!   are compared case-insensitively, and names and zones in either their
+//!   Unicode or punycode form.
+//! - `field~pattern` matches a field against a regex pattern.
+//! - Values containing spaces or parentheses are quoted with `'` or `"`.
+//! - Terms are combined with `AND`, `OR` and `NOT`, and grouped with
+//!   parentheses. Adjacent terms are implicitly joined with `AND`.
+//!
+//! The fields are `name`, `zone` (by name or id), `type`, `content`, `id`,
+//! `comment`, `tag` (matching any tag of the record, by name or
+//! `name:value`), `proxied` (`true` or `false`) and `ttl` (seconds, or
+//! `auto`).
+
+use crate::cloudflare::models::Record;
+use crate::error::{Error, Result};
+use crate::util::names;
+use regex::Regex;
+use std::fmt::{self, Display};
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+
+/// A parsed query selecting Cloudflare records.
+#[derive(Clone, Debug)]
+pub struct Query {
+    source: String,
+    expr: Expr,
+}
+
+impl Query {
+    /// Parse a query.
+    pub fn parse(source: &str) -> Result<Self> {
+        let invalid = |reason: String| {
+            Error::Config(format!("invalid query `{source}`: {reason}"))
+        };
+        let tokens = tokenize(source).map_err(invalid)?;
+        let mut parser = Parser {
+            tokens: tokens.into_iter().peekable(),
+        };
+        let expr = parser.or().map_err(invalid)?;
+        if let Some(token) = parser.tokens.next() {
+            return Err(invalid(format!("unexpected {token}")));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    /// Returns whether a record is selected.
+    pub fn matches(&self, record: &Record) -> bool {
+        self.expr.matches(record)
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Self::parse(source)
+    }
+}
+
+impl Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl PartialEq for Query {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Query {}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Term(Term),
+}
+
+impl Expr {
+    fn matches(&self, record: &Record) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(record) && b.matches(record),
+            Expr::Or(a, b) => a.matches(record) || b.matches(record),
+            Expr::Not(expr) => !expr.matches(record),
+            Expr::Term(term) => term.matches(record),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Name,
+    Zone,
+    Type,
+    Content,
+    Id,
+    Comment,
+    Tag,
+    Proxied,
+    Ttl,
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(field: &str) -> Result<Self, String> {
+        Ok(match field {
+            "name" => Field::Name,
+            "zone" => Field::Zone,
+            "type" => Field::Type,
+            "content" => Field::Content,
+            "id" => Field::Id,
+            "comment" => Field::Comment,
+            "tag" => Field::Tag,
+            "proxied" => Field::Proxied,
+            "ttl" => Field::Ttl,
+            _ => return Err(format!("unknown field `{field}`")),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Term {
+    /// A text field equal to a value.
+    Equals(Field, String),
+    /// A text field matching a regex.
+    Matches(Field, Regex),
+    Proxied(bool),
+    Ttl(u32),
+}
+
+impl Term {
+    fn new(field: Field, regex: bool, value: String) -> Result<Self, String> {
+        match (field, regex) {
+            (Field::Proxied | Field::Ttl, true) => Err(format!(
+                "`{}` can't be matched with `~`",
+                if field == Field::Ttl {
+                    "ttl"
+                } else {
+                    "proxied"
+                }
+            )),
+            (Field::Proxied, false) => {
+                value.parse().map(Term::Proxied).map_err(|_| {
+                    format!("expected `true` or `false`, found `{value}`")
+                })
+            }
+            (Field::Ttl, false) if value == "auto" => Ok(Term::Ttl(1)),
+            (Field::Ttl, false) => value.parse().map(Term::Ttl).map_err(|_| {
+                format!("expected seconds or `auto`, found `{value}`")
+            }),
+            (_, true) => Regex::new(&value)
+                .map(|regex| Term::Matches(field, regex))
+                .map_err(|err| err.to_string()),
+            (_, false) => Ok(Term::Equals(field, value)),
+        }
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        match self {
+            Term::Equals(Field::Tag, tag) => record.has_tag(tag),
+            Term::Equals(field, value) => {
+                texts(*field, record).iter().any(|text| match field {
+                    Field::Name | Field::Zone => names::same(text, value),
+                    Field::Type => text.eq_ignore_ascii_case(value),
+                    _ => text == value,
+                })
+            }
+            // Names also match in their Unicode form
+            Term::Matches(field, regex) => {
+                texts(*field, record).iter().any(|text| {
+                    regex.is_match(text)
+                        || matches!(field, Field::Name | Field::Zone)
+                            && regex.is_match(&names::to_unicode(text))
+                })
+            }
+            Term::Proxied(proxied) => record.proxied == *proxied,
+            Term::Ttl(ttl) => record.ttl == *ttl,
+        }
+    }
+}
+
+/// Return the text of a record field, which may have several values.
+fn texts(field: Field, record: &Record) -> Vec<&str> {
+    match field {
+        Field::Name => vec![&record.name],
+        Field::Zone => vec![&record.zone_name, &record.zone_id],
+        Field::Type => vec![&record.record_type],
+        Field::Content => vec![&record.content],
+        Field::Id => vec![&record.id],
+        Field::Comment => record.comment.as_deref().into_iter().collect(),
+        // Tags with a value, such as `ddns:home`, also match by their name
+        Field::Tag => record
+            .tags
+            .iter()
+            .flat_map(|tag| {
+                let name = tag.split_once(':').map(|(name, _)| name);
+                std::iter::once(tag.as_str()).chain(name)
+            })
+            .collect(),
+        Field::Proxied | Field::Ttl => vec![],
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(Field, bool, String),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Open => write!(f, "`(`"),
+            Token::Close => write!(f, "`)`"),
+            Token::And => write!(f, "`AND`"),
+            Token::Or => write!(f, "`OR`"),
+            Token::Not => write!(f, "`NOT`"),
+            Token::Term(..) => write!(f, "term"),
+        }
+    }
+}
+
+/// Split a query into tokens.
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                let word = take_while(&mut chars, source, start, |c| {
+                    c.is_ascii_alphanumeric() || c == '_'
+                });
+                let op = chars.next_if(|&(_, c)| c == ':' || c == '~');
+                tokens.push(match (word, op) {
+                    (word, Some((_, op))) => {
+                        let field = word.parse()?;
+                        let value = value(&mut chars, source)?;
+                        Token::Term(field, op == '~', value)
+                    }
+                    ("AND" | "and", None) => Token::And,
+                    ("OR" | "or", None) => Token::Or,
+                    ("NOT" | "not", None) => Token::Not,
+                    ("", None) => return Err(format!("unexpected `{c}`")),
+                    (word, None) => {
+                        return Err(format!(
+                            "expected `field:value`, found `{word}`"
+                        ))
+                    }
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Consume characters while they match a predicate, returning them.
+fn take_while<'s>(
+    chars: &mut Peekable<CharIndices>,
+    source: &'s str,
+    start: usize,
+    predicate: impl Fn(char) -> bool,
+) -> &'s str {
+    let mut end = start;
+    while let Some((i, c)) = chars.next_if(|&(_, c)| predicate(c)) {
+        end = i + c.len_utf8();
+    }
+    &source[start..end]
+}
+
+/// Consume the value of a term, which may be quoted.
+fn value(
+    chars: &mut Peekable<CharIndices>,
+    source: &str,
+) -> Result<String, String> {
+    match chars.peek().copied() {
+        Some((i, quote @ ('\'' | '"'))) => {
+            chars.next();
+            let value =
+                take_while(chars, source, i + 1, |c| c != quote).to_string();
+            chars
+                .next()
+                .map(|_| value)
+                .ok_or_else(|| format!("unterminated quote `{quote}`"))
+        }
+        Some((i, _)) => Some(take_while(chars, source, i, |c| {
+            !c.is_whitespace() && c != '(' && c != ')'
+        }))
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| "expected a value".to_string()),
+        None => Err("expected a value".to_string()),
+    }
+}
+
+/// A recursive descent parser, where `NOT` binds tighter than `AND`, which
+/// binds tighter than `OR`.
+struct Parser {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.tokens.next_if(|t| matches!(t, Token::Or)).is_some() {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        loop {
+            match self.tokens.peek() {
+                Some(Token::And) => {
+                    self.tokens.next();
+                }
+                // Adjacent terms are implicitly joined
+                Some(Token::Open | Token::Not | Token::Term(..)) => {}
+                _ => return Ok(expr),
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        match self.tokens.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.not()?))),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.tokens.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("expected `)`".to_string()),
+                }
+            }
+            Some(Token::Term(field, regex, value)) => {
+                Term::new(field, regex, value).map(Expr::Term)
+            }
+            Some(token) => Err(format!("unexpected {token}")),
+            None => Err("expected a term".to_string()),
+        }
+    }
+}
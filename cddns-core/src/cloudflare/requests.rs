@@ -0,0 +1,484 @@
+use crate::cloudflare::models::{CloudflareResponse, RateLimit};
+use crate::cloudflare::API_BASE;
+use crate::config::models::ConfigOpts;
+use crate::error::{Error, Result};
+use crate::ip;
+use chrono::{DateTime, Utc};
+use reqwest::header::{
+    HeaderMap, AUTHORIZATION, ETAG, IF_NONE_MATCH, RETRY_AFTER,
+};
+use reqwest::{Client, Method, Request, RequestBuilder, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::{fmt::Display, time::Duration};
+use tracing::{debug, info, trace, warn};
+
+/// The timeout for a single request attempt.
+const TIMEOUT: Duration = Duration::from_millis(10_000);
+
+/// The longest delay between retried requests.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// The response header identifying a request to Cloudflare support.
+const CF_RAY: &str = "cf-ray";
+
+/// The user agent sent with every request.
+const USER_AGENT: &str =
+    concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// The rate limit reported by the latest response.
+static RATE_LIMIT: Mutex<Option<RateLimit>> = Mutex::new(None);
+
+/// A deserialized response to a conditional request, with its entity tag.
+type Entity = (String, Arc<dyn Any + Send + Sync>);
+
+/// The latest entity received for each URL requested conditionally.
+static ENTITIES: Mutex<BTreeMap<String, Entity>> = Mutex::new(BTreeMap::new());
+
+/// The shared state of the request layer.
+static GATEWAY: OnceLock<Gateway> = OnceLock::new();
+
+/// The HTTP client and retry policy shared by all requests.
+struct Gateway {
+    /// The client, pooling connections and TLS sessions between calls.
+    client: Client,
+    /// The maximum attempts for each request.
+    max_attempts: u32,
+    /// The initial delay between retried requests.
+    backoff: Duration,
+    /// Whether to log full requests and responses.
+    trace_http: bool,
+    /// The base URL of all endpoints.
+    base_url: String,
+}
+
+impl Gateway {
+    fn new(opts: &ConfigOpts, trace_http: bool) -> Result<Self> {
+        let defaults = ConfigOpts::default();
+        let user_agent = match opts.api.user_agent {
+            Some(ref suffix) => format!("{USER_AGENT} {suffix}"),
+            None => USER_AGENT.to_string(),
+        };
+        debug!(user_agent);
+        Ok(Self {
+            client: Client::builder()
+                .user_agent(user_agent)
+                .local_address(ip::bind_address(&opts.ip)?)
+                .build()
+                .map_err(Error::Network)?,
+            max_attempts: opts
+                .api
+                .max_attempts
+                .or(defaults.api.max_attempts)
+                .ok_or_else(|| Error::Config("no default max attempts".into()))?
+                .max(1),
+            backoff: Duration::from_millis(
+                opts.api
+                    .retry_backoff
+                    .or(defaults.api.retry_backoff)
+                    .ok_or_else(|| {
+                        Error::Config("no default retry backoff".into())
+                    })?,
+            ),
+            trace_http,
+            base_url: opts
+                .api
+                .base_url
+                .as_deref()
+                .unwrap_or(API_BASE)
+                .trim_end_matches('/')
+                .to_string(),
+        })
+    }
+}
+
+/// Initialize the request layer from configuration options, optionally logging
+/// full requests and responses. Requests made without initialization use the
+/// default configuration.
+pub fn init(opts: &ConfigOpts, trace_http: bool) -> Result<()> {
+    GATEWAY.set(Gateway::new(opts, trace_http)?).map_err(|_| {
+        Error::Config("request layer is already initialized".into())
+    })
+}
+
+/// Return the shared request layer, initializing it on first use.
+fn gateway() -> Result<&'static Gateway> {
+    match GATEWAY.get() {
+        Some(gateway) => Ok(gateway),
+        None => {
+            let gateway = Gateway::new(&ConfigOpts::default(), false)?;
+            Ok(GATEWAY.get_or_init(|| gateway))
+        }
+    }
+}
+
+/// Return the API rate limit reported by the latest response, if any.
+pub fn rate_limit() -> Option<RateLimit> {
+    RATE_LIMIT.lock().ok().and_then(|latest| latest.clone())
+}
+
+/// A response received from Cloudflare.
+struct Payload {
+    status: StatusCode,
+    headers: HeaderMap,
+    ray: String,
+    bytes: Vec<u8>,
+}
+
+impl Payload {
+    /// Deserialize the Cloudflare payload, surfacing any Cloudflare errors.
+    fn deserialize<T>(&self) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let cf_resp: CloudflareResponse =
+            serde_json::from_slice(&self.bytes)
+                .map_err(|err| Error::response("cloudflare metadata", err))?;
+        if cf_resp.success {
+            return serde_json::from_slice(&self.bytes)
+                .map_err(|err| Error::response("cloudflare payload", err));
+        }
+        let message = cf_resp
+            .errors
+            .iter()
+            .map(|err| {
+                let mut message = err.to_string();
+                for chained in err.error_chain.iter().flatten() {
+                    message.push_str(&format!(", {chained}"));
+                }
+                message
+            })
+            .collect::<Vec<String>>()
+            .join("; ");
+        let (status, ray) = (self.status, self.ray.clone());
+        Err(match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Error::Auth {
+                status,
+                ray,
+                message,
+            },
+            _ => Error::Api {
+                status,
+                ray,
+                message,
+            },
+        })
+    }
+}
+
+/// Send a request and deserialize the Cloudflare payload.
+async fn send<T>(request: RequestBuilder) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    send_raw(request).await?.deserialize()
+}
+
+/// Send a request, retrying transient failures with exponential backoff.
+async fn send_raw(request: RequestBuilder) -> Result<Payload> {
+    let gateway = gateway()?;
+    let request = request.build().map_err(Error::Network)?;
+    let mut backoff = gateway.backoff;
+    let mut attempt = 1;
+    loop {
+        let attempted = request.try_clone().ok_or_else(|| {
+            Error::Config("web request body cannot be retried".into())
+        })?;
+        let err =
+            match tokio::time::timeout(TIMEOUT, send_once(attempted)).await {
+                Ok(Ok(payload)) => break Ok(payload),
+                Ok(Err(err)) => err,
+                Err(_) => Error::Timeout,
+            };
+        if !is_retryable(request.method(), &err)
+            || attempt >= gateway.max_attempts
+        {
+            if attempt > 1 {
+                warn!("request failed after {attempt} attempts");
+            }
+            break Err(err);
+        }
+        let delay = match err {
+            Error::RateLimited {
+                retry_after: Some(retry_after),
+                ..
+            } => retry_after,
+            _ => backoff,
+        };
+        // Give up rather than stall for longer than the longest backoff
+        if delay > MAX_BACKOFF {
+            warn!(
+                retry_after_s = delay.as_secs(),
+                "not retrying, cloudflare asked to wait too long"
+            );
+            break Err(err);
+        }
+        warn!(
+            attempt,
+            retry_ms = delay.as_millis(),
+            "transient request failure: {err}"
+        );
+        tokio::time::sleep(delay).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+        attempt += 1;
+    }
+}
+
+/// Returns whether a failed request may be sent again. Requests which
+/// aren't idempotent, such as creating a record, are only retried when
+/// Cloudflare can't have applied them: when rate limited, or when no
+/// connection was made.
+fn is_retryable(method: &Method, err: &Error) -> bool {
+    match err {
+        _ if !err.is_transient() => false,
+        _ if method != Method::POST => true,
+        Error::RateLimited { .. } => true,
+        Error::Network(err) => err.is_connect(),
+        _ => false,
+    }
+}
+
+/// Send a request once.
+async fn send_once(request: Request) -> Result<Payload> {
+    let gateway = gateway()?;
+    if gateway.trace_http {
+        trace_request(&request);
+    }
+    trace!("starting web request");
+    let response = gateway
+        .client
+        .execute(request)
+        .await
+        .map_err(Error::Network)?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let ray = headers
+        .get(CF_RAY)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("none")
+        .to_string();
+    let bytes = response.bytes().await.map_err(Error::Network)?;
+    trace!("received web response");
+    if gateway.trace_http {
+        trace_response(status, &headers, &bytes);
+    }
+    if let Some(rate_limit) = RateLimit::from_headers(&headers) {
+        debug!(
+            remaining = rate_limit.remaining,
+            limit = rate_limit.limit,
+            reset_s = rate_limit.reset.map(|r| r.as_secs()),
+            "api rate limit"
+        );
+        if let Ok(mut latest) = RATE_LIMIT.lock() {
+            latest.replace(rate_limit);
+        }
+    }
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = headers
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        return Err(Error::RateLimited {
+            status,
+            ray,
+            retry_after,
+        });
+    }
+    if status.is_server_error() {
+        return Err(Error::Server { status, ray });
+    }
+    Ok(Payload {
+        status,
+        headers,
+        ray,
+        bytes: bytes.to_vec(),
+    })
+}
+
+/// Format headers for logging, redacting credentials.
+fn format_headers(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if name == AUTHORIZATION {
+                "<redacted>"
+            } else {
+                value.to_str().unwrap_or("<binary>")
+            };
+            format!("{name}: {value}")
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Log a full request, with the token redacted.
+fn trace_request(request: &Request) {
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    info!(
+        method = %request.method(),
+        url = %request.url(),
+        headers = format_headers(request.headers()),
+        body = %body,
+        "http request"
+    );
+}
+
+/// Log a full response.
+fn trace_response(status: StatusCode, headers: &HeaderMap, body: &[u8]) {
+    info!(
+        status = %status,
+        headers = format_headers(headers),
+        body = %String::from_utf8_lossy(body),
+        "http response"
+    );
+}
+
+/// Parse a `Retry-After` header value, given in seconds or as an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    match value.trim().parse::<u64>() {
+        Ok(secs) => Some(Duration::from_secs(secs)),
+        Err(_) => {
+            let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+            (date.with_timezone(&Utc) - Utc::now()).to_std().ok()
+        }
+    }
+}
+
+fn get_request(
+    endpoint: impl Display,
+    token: impl Display,
+) -> Result<RequestBuilder> {
+    let gateway = gateway()?;
+    Ok(gateway
+        .client
+        .get(format!("{}{endpoint}", gateway.base_url))
+        .bearer_auth(token))
+}
+
+pub async fn get_with_timeout<T>(
+    endpoint: impl Display,
+    token: impl Display,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    send(get_request(endpoint, token)?).await
+}
+
+/// Send a conditional GET request, reusing the previous response when
+/// Cloudflare reports the resource is not modified since it was received.
+pub async fn get_conditional<T>(
+    endpoint: impl Display,
+    token: impl Display,
+) -> Result<T>
+where
+    T: DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    let gateway = gateway()?;
+    let url = format!("{}{endpoint}", gateway.base_url);
+    let cached = ENTITIES
+        .lock()
+        .ok()
+        .and_then(|entities| entities.get(&url).cloned());
+    let mut request = gateway.client.get(&url).bearer_auth(token);
+    if let Some((ref etag, _)) = cached {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    let payload = send_raw(request).await?;
+    if payload.status == StatusCode::NOT_MODIFIED {
+        trace!(url, "not modified, reusing previous response");
+        return cached
+            .and_then(|(_, value)| value.downcast_ref::<T>().cloned())
+            .ok_or_else(|| {
+                Error::NotFound(format!("previous response to '{url}'"))
+            });
+    }
+    let value: T = payload.deserialize()?;
+    if let Some(etag) = payload
+        .headers
+        .get(ETAG)
+        .and_then(|value| value.to_str().ok())
+    {
+        if let Ok(mut entities) = ENTITIES.lock() {
+            entities.insert(url, (etag.to_string(), Arc::new(value.clone())));
+        }
+    }
+    Ok(value)
+}
+
+fn patch_request(
+    endpoint: impl Display,
+    token: impl Display,
+    json: &(impl Serialize + ?Sized),
+) -> Result<RequestBuilder> {
+    let gateway = gateway()?;
+    Ok(gateway
+        .client
+        .patch(format!("{}{endpoint}", gateway.base_url))
+        .bearer_auth(token)
+        .header("Content-Type", "application/json")
+        .json(json))
+}
+
+pub async fn patch_with_timeout<T>(
+    endpoint: impl Display,
+    token: impl Display,
+    json: &(impl Serialize + ?Sized),
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    send(patch_request(endpoint, token, json)?).await
+}
+
+fn post_request(
+    endpoint: impl Display,
+    token: impl Display,
+    json: &(impl Serialize + ?Sized),
+) -> Result<RequestBuilder> {
+    let gateway = gateway()?;
+    Ok(gateway
+        .client
+        .post(format!("{}{endpoint}", gateway.base_url))
+        .bearer_auth(token)
+        .header("Content-Type", "application/json")
+        .json(json))
+}
+
+pub async fn post_with_timeout<T>(
+    endpoint: impl Display,
+    token: impl Display,
+    json: &(impl Serialize + ?Sized),
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    send(post_request(endpoint, token, json)?).await
+}
+
+fn delete_request(
+    endpoint: impl Display,
+    token: impl Display,
+) -> Result<RequestBuilder> {
+    let gateway = gateway()?;
+    Ok(gateway
+        .client
+        .delete(format!("{}{endpoint}", gateway.base_url))
+        .bearer_auth(token))
+}
+
+pub async fn delete_with_timeout<T>(
+    endpoint: impl Display,
+    token: impl Display,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    send(delete_request(endpoint, token)?).await
+}
@@ -0,0 +1,722 @@
+use crate::config::models::{
+    ConfigOpts, ConfigOptsApi, ConfigOptsGroup, ConfigOptsInventory,
+    ConfigOptsIp, ConfigOptsList, ConfigOptsTemplate, ConfigOptsUplink,
+    ConfigOptsVerify,
+};
+use crate::inventory::lookup::CheckVia;
+use crate::inventory::policy::UnsupportedTypes;
+use crate::ip::dns::DnsProvider;
+use crate::ip::IpSource;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+/// A builder for configuration options.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConfigBuilder {
+    pub api: Option<ConfigOptsApi>,
+    pub verify: Option<ConfigOptsVerify>,
+    pub list: Option<ConfigOptsList>,
+    pub inventory: Option<ConfigOptsInventory>,
+    pub ip: Option<ConfigOptsIp>,
+    pub templates: Option<BTreeMap<String, ConfigOptsTemplate>>,
+}
+
+impl ConfigBuilder {
+    /// Create a new config opts builder.
+    pub(crate) fn new() -> Self {
+        Self {
+            api: None,
+            verify: None,
+            list: None,
+            inventory: None,
+            ip: None,
+            templates: None,
+        }
+    }
+
+    /// Merge config layers, where the `greater` layer takes precedence.
+    pub fn merge(&mut self, greater: impl Into<Self>) -> &mut Self {
+        let mut greater = greater.into();
+        self.api = match (self.api.take(), greater.api.take()) {
+            (None, None) => None,
+            (Some(val), None) | (None, Some(val)) => Some(val),
+            (Some(l), Some(mut g)) => {
+                g.max_attempts = g.max_attempts.or(l.max_attempts);
+                g.retry_backoff = g.retry_backoff.or(l.retry_backoff);
+                g.user_agent = g.user_agent.or(l.user_agent);
+                g.base_url = g.base_url.or(l.base_url);
+                g.zones_ttl = g.zones_ttl.or(l.zones_ttl);
+                Some(g)
+            }
+        };
+        self.verify = match (self.verify.take(), greater.verify.take()) {
+            (None, None) => None,
+            (Some(val), None) | (None, Some(val)) => Some(val),
+            (Some(l), Some(mut g)) => {
+                g.token = g.token.or(l.token);
+                Some(g)
+            }
+        };
+        self.list = match (self.list.take(), greater.list.take()) {
+            (None, None) => None,
+            (Some(val), None) | (None, Some(val)) => Some(val),
+            (Some(l), Some(mut g)) => {
+                g.include_zones = g.include_zones.or(l.include_zones);
+                g.ignore_zones = g.ignore_zones.or(l.ignore_zones);
+                g.include_records = g.include_records.or(l.include_records);
+                g.ignore_records = g.ignore_records.or(l.ignore_records);
+                g.account = g.account.or(l.account);
+                g.query = g.query.or(l.query);
+                g.tag = g.tag.or(l.tag);
+                Some(g)
+            }
+        };
+        self.inventory = match (self.inventory.take(), greater.inventory.take())
+        {
+            (None, None) => None,
+            (Some(val), None) | (None, Some(val)) => Some(val),
+            (Some(l), Some(mut g)) => {
+                g.path = g.path.or(l.path);
+                g.force_update = g.force_update.or(l.force_update);
+                g.force_prune = g.force_prune.or(l.force_prune);
+                g.watch_interval = g.watch_interval.or(l.watch_interval);
+                g.update_cooldown = g.update_cooldown.or(l.update_cooldown);
+                g.reconcile_interval =
+                    g.reconcile_interval.or(l.reconcile_interval);
+                g.ipv4 = g.ipv4.or(l.ipv4);
+                g.ipv6 = g.ipv6.or(l.ipv6);
+                g.pair_records = g.pair_records.or(l.pair_records);
+                g.check_via = g.check_via.or(l.check_via);
+                g.watch_events = g.watch_events.or(l.watch_events);
+                g.watch_max_failures =
+                    g.watch_max_failures.or(l.watch_max_failures);
+                g.groups = g.groups.or(l.groups);
+                g.watch_immediate = g.watch_immediate.or(l.watch_immediate);
+                g.watch_listen = g.watch_listen.or(l.watch_listen);
+                g.watch_textfile_dir =
+                    g.watch_textfile_dir.or(l.watch_textfile_dir);
+                g.watch_summary = g.watch_summary.or(l.watch_summary);
+                g.comment_template = g.comment_template.or(l.comment_template);
+                g.create_missing = g.create_missing.or(l.create_missing);
+                g.prune_invalid = g.prune_invalid.or(l.prune_invalid);
+                g.unsupported_types =
+                    g.unsupported_types.or(l.unsupported_types);
+                g.notifiers = g.notifiers.or(l.notifiers);
+                Some(g)
+            }
+        };
+        self.ip = match (self.ip.take(), greater.ip.take()) {
+            (None, None) => None,
+            (Some(val), None) | (None, Some(val)) => Some(val),
+            (Some(l), Some(mut g)) => {
+                g.ipv4_urls = g.ipv4_urls.or(l.ipv4_urls);
+                g.ipv6_urls = g.ipv6_urls.or(l.ipv6_urls);
+                g.interface = g.interface.or(l.interface);
+                g.router = g.router.or(l.router);
+                g.dns = g.dns.or(l.dns);
+                g.command = g.command.or(l.command);
+                g.sources = g.sources.or(l.sources);
+                g.consensus = g.consensus.or(l.consensus);
+                g.confirm_delay = g.confirm_delay.or(l.confirm_delay);
+                g.allow_private = g.allow_private.or(l.allow_private);
+                g.ipv6_suffix = g.ipv6_suffix.or(l.ipv6_suffix);
+                g.ipv6_prefix_len = g.ipv6_prefix_len.or(l.ipv6_prefix_len);
+                g.ipv6_filter = g.ipv6_filter.or(l.ipv6_filter);
+                g.uplinks = g.uplinks.or(l.uplinks);
+                g.bind = g.bind.or(l.bind);
+                g.plugins = g.plugins.or(l.plugins);
+                Some(g)
+            }
+        };
+        self.templates = greater.templates.take().or(self.templates.take());
+        self
+    }
+
+    /// Initialize the API configuration options.
+    pub fn api(&mut self, api: Option<ConfigOptsApi>) -> &mut Self {
+        self.api = api;
+        self
+    }
+
+    /// Initialize the maximum attempts for each request.
+    pub fn api_max_attempts(&mut self, attempts: Option<u32>) -> &mut Self {
+        self.api.get_or_insert_default().max_attempts = attempts;
+        self
+    }
+
+    /// Initialize the backoff between retried requests.
+    pub fn api_retry_backoff(&mut self, backoff: Option<u64>) -> &mut Self {
+        self.api.get_or_insert_default().retry_backoff = backoff;
+        self
+    }
+
+    /// Initialize the user agent suffix.
+    pub fn api_user_agent(
+        &mut self,
+        user_agent: Option<impl Into<String>>,
+    ) -> &mut Self {
+        self.api.get_or_insert_default().user_agent =
+            user_agent.map(|v| v.into());
+        self
+    }
+
+    /// Set the base URL of Cloudflare API requests.
+    pub fn api_base_url(
+        &mut self,
+        base_url: Option<impl Into<String>>,
+    ) -> &mut Self {
+        self.api.get_or_insert_default().base_url = base_url.map(|v| v.into());
+        self
+    }
+
+    /// Set how long listed zones are cached.
+    pub fn api_zones_ttl(&mut self, zones_ttl: Option<u64>) -> &mut Self {
+        self.api.get_or_insert_default().zones_ttl = zones_ttl;
+        self
+    }
+
+    /// Initialize the verify configuration options.
+    pub fn verify(&mut self, verify: Option<ConfigOptsVerify>) -> &mut Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Initialize the API token.
+    pub fn verify_token(
+        &mut self,
+        token: Option<impl Into<String>>,
+    ) -> &mut Self {
+        self.verify.get_or_insert_default().token = token.map(|t| t.into());
+        self
+    }
+
+    /// Initialize the list configuration options.
+    pub fn list(&mut self, list: Option<ConfigOptsList>) -> &mut Self {
+        self.list = list;
+        self
+    }
+
+    /// Initialize the include zones.
+    pub fn list_include_zones(
+        &mut self,
+        include_zones: Option<Vec<String>>,
+    ) -> &mut Self {
+        self.list.get_or_insert_default().include_zones = include_zones;
+        self
+    }
+
+    /// Initialize the ignore zones.
+    pub fn list_ignore_zones(
+        &mut self,
+        ignore_zones: Option<Vec<String>>,
+    ) -> &mut Self {
+        self.list.get_or_insert_default().ignore_zones = ignore_zones;
+        self
+    }
+
+    /// Initialize the include records.
+    pub fn list_include_records(
+        &mut self,
+        include_records: Option<Vec<String>>,
+    ) -> &mut Self {
+        self.list.get_or_insert_default().include_records = include_records;
+        self
+    }
+
+    /// Initialize the ignore records.
+    pub fn list_ignore_records(
+        &mut self,
+        ignore_records: Option<Vec<String>>,
+    ) -> &mut Self {
+        self.list.get_or_insert_default().ignore_records = ignore_records;
+        self
+    }
+
+    /// Set the account owning the zones to list.
+    pub fn list_account(
+        &mut self,
+        account: Option<impl Into<String>>,
+    ) -> &mut Self {
+        self.list.get_or_insert_default().account = account.map(|v| v.into());
+        self
+    }
+
+    /// Set the query selecting records to list.
+    pub fn list_query(
+        &mut self,
+        query: Option<impl Into<String>>,
+    ) -> &mut Self {
+        self.list.get_or_insert_default().query = query.map(|v| v.into());
+        self
+    }
+
+    /// Set the tag of the records to list.
+    pub fn list_tag(&mut self, tag: Option<impl Into<String>>) -> &mut Self {
+        self.list.get_or_insert_default().tag = tag.map(|v| v.into());
+        self
+    }
+
+    /// Initialize the inventory configuration options.
+    pub fn inventory(
+        &mut self,
+        inventory: Option<ConfigOptsInventory>,
+    ) -> &mut Self {
+        self.inventory = inventory;
+        self
+    }
+
+    /// Initialize the inventory path.
+    pub fn inventory_path(&mut self, path: Option<PathBuf>) -> &mut Self {
+        self.inventory.get_or_insert_default().path = path;
+        self
+    }
+
+    /// Initialize the inventory force update flag.
+    pub fn inventory_force_update(&mut self, force: Option<bool>) -> &mut Self {
+        self.inventory.get_or_insert_default().force_update = force;
+        self
+    }
+
+    /// Initialize the inventory force prune flag.
+    pub fn inventory_force_prune(&mut self, force: Option<bool>) -> &mut Self {
+        self.inventory.get_or_insert_default().force_prune = force;
+        self
+    }
+
+    /// Initialize the inventory watch interval.
+    pub fn inventory_watch_interval(
+        &mut self,
+        interval: Option<u64>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().watch_interval = interval;
+        self
+    }
+
+    /// Set the minimum milliseconds between updates of the same record.
+    pub fn inventory_update_cooldown(
+        &mut self,
+        update_cooldown: Option<u64>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().update_cooldown =
+            update_cooldown;
+        self
+    }
+
+    /// Set the milliseconds between full reconciliations in watch mode.
+    pub fn inventory_reconcile_interval(
+        &mut self,
+        reconcile_interval: Option<u64>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().reconcile_interval =
+            reconcile_interval;
+        self
+    }
+
+    /// Set whether A records and IPv4 addresses are handled.
+    pub fn inventory_ipv4(&mut self, ipv4: Option<bool>) -> &mut Self {
+        self.inventory.get_or_insert_default().ipv4 = ipv4;
+        self
+    }
+
+    /// Set whether AAAA records and IPv6 addresses are handled.
+    pub fn inventory_ipv6(&mut self, ipv6: Option<bool>) -> &mut Self {
+        self.inventory.get_or_insert_default().ipv6 = ipv6;
+        self
+    }
+
+    /// Set whether A and AAAA records of a name are kept current together.
+    pub fn inventory_pair_records(
+        &mut self,
+        pair_records: Option<bool>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().pair_records = pair_records;
+        self
+    }
+
+    /// Set how records are checked.
+    pub fn inventory_check_via(
+        &mut self,
+        check_via: Option<CheckVia>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().check_via = check_via;
+        self
+    }
+
+    /// Set whether network changes trigger checks in watch mode.
+    pub fn inventory_watch_events(
+        &mut self,
+        watch_events: Option<bool>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().watch_events = watch_events;
+        self
+    }
+
+    /// Set the consecutive failures after which watch exits.
+    pub fn inventory_watch_max_failures(
+        &mut self,
+        watch_max_failures: Option<u32>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().watch_max_failures =
+            watch_max_failures;
+        self
+    }
+
+    /// Set the groups of inventory entries refreshed on their own interval.
+    pub fn inventory_groups(
+        &mut self,
+        groups: Option<BTreeMap<String, ConfigOptsGroup>>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().groups = groups;
+        self
+    }
+
+    /// Set whether watch checks records as soon as it starts.
+    pub fn inventory_watch_immediate(
+        &mut self,
+        watch_immediate: Option<bool>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().watch_immediate =
+            watch_immediate;
+        self
+    }
+
+    /// Set the address to serve health endpoints on during watch.
+    pub fn inventory_watch_listen(
+        &mut self,
+        watch_listen: Option<SocketAddr>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().watch_listen = watch_listen;
+        self
+    }
+
+    /// Set the directory to write Prometheus metrics to during watch.
+    pub fn inventory_watch_textfile_dir(
+        &mut self,
+        watch_textfile_dir: Option<PathBuf>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().watch_textfile_dir =
+            watch_textfile_dir;
+        self
+    }
+
+    /// Set the number of checks summarized by each condensed watch log.
+    pub fn inventory_watch_summary(
+        &mut self,
+        watch_summary: Option<u32>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().watch_summary = watch_summary;
+        self
+    }
+
+    /// Set the template of comments set on updated records.
+    pub fn inventory_comment_template(
+        &mut self,
+        comment_template: Option<impl Into<String>>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().comment_template =
+            comment_template.map(|v| v.into());
+        self
+    }
+
+    /// Set whether missing records are created on update.
+    pub fn inventory_create_missing(
+        &mut self,
+        create_missing: Option<bool>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().create_missing = create_missing;
+        self
+    }
+
+    /// Set whether update prunes invalid inventory records.
+    pub fn inventory_prune_invalid(
+        &mut self,
+        prune_invalid: Option<bool>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().prune_invalid = prune_invalid;
+        self
+    }
+
+    /// Set how records of unsupported types are handled.
+    pub fn inventory_unsupported_types(
+        &mut self,
+        unsupported_types: Option<UnsupportedTypes>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().unsupported_types =
+            unsupported_types;
+        self
+    }
+
+    /// Set the notifier plugins.
+    pub fn inventory_notifiers(
+        &mut self,
+        notifiers: Option<Vec<PathBuf>>,
+    ) -> &mut Self {
+        self.inventory.get_or_insert_default().notifiers = notifiers;
+        self
+    }
+
+    /// Initialize the IP configuration options.
+    pub fn ip(&mut self, ip: Option<ConfigOptsIp>) -> &mut Self {
+        self.ip = ip;
+        self
+    }
+
+    /// Initialize the URLs resolving the public IPv4 address.
+    pub fn ip_ipv4_urls(&mut self, urls: Option<Vec<String>>) -> &mut Self {
+        self.ip.get_or_insert_default().ipv4_urls = urls;
+        self
+    }
+
+    /// Initialize the URLs resolving the public IPv6 address.
+    pub fn ip_ipv6_urls(&mut self, urls: Option<Vec<String>>) -> &mut Self {
+        self.ip.get_or_insert_default().ipv6_urls = urls;
+        self
+    }
+
+    /// Initialize the network interface to read public IPs from.
+    pub fn ip_interface(
+        &mut self,
+        interface: Option<impl Into<String>>,
+    ) -> &mut Self {
+        self.ip.get_or_insert_default().interface = interface.map(|v| v.into());
+        self
+    }
+
+    /// Initialize whether to query public IPv4 addresses from the router.
+    pub fn ip_router(&mut self, router: Option<bool>) -> &mut Self {
+        self.ip.get_or_insert_default().router = router;
+        self
+    }
+
+    /// Initialize the DNS provider to resolve public IPs with.
+    pub fn ip_dns(&mut self, dns: Option<DnsProvider>) -> &mut Self {
+        self.ip.get_or_insert_default().dns = dns;
+        self
+    }
+
+    /// Initialize the shell command printing public IPs.
+    pub fn ip_command(
+        &mut self,
+        command: Option<impl Into<String>>,
+    ) -> &mut Self {
+        self.ip.get_or_insert_default().command = command.map(|v| v.into());
+        self
+    }
+
+    /// Set the ordered chain of IP sources.
+    pub fn ip_sources(&mut self, sources: Option<Vec<IpSource>>) -> &mut Self {
+        self.ip.get_or_insert_default().sources = sources;
+        self
+    }
+
+    /// Set the number of IP sources which must agree.
+    pub fn ip_consensus(&mut self, consensus: Option<u32>) -> &mut Self {
+        self.ip.get_or_insert_default().consensus = consensus;
+        self
+    }
+
+    /// Set the delay before re-resolving public IPs to confirm an update.
+    pub fn ip_confirm_delay(
+        &mut self,
+        confirm_delay: Option<u64>,
+    ) -> &mut Self {
+        self.ip.get_or_insert_default().confirm_delay = confirm_delay;
+        self
+    }
+
+    /// Set whether private and CGNAT addresses may be published.
+    pub fn ip_allow_private(
+        &mut self,
+        allow_private: Option<bool>,
+    ) -> &mut Self {
+        self.ip.get_or_insert_default().allow_private = allow_private;
+        self
+    }
+
+    /// Set the host suffix combined with the resolved IPv6 prefix.
+    pub fn ip_ipv6_suffix(
+        &mut self,
+        ipv6_suffix: Option<impl Into<String>>,
+    ) -> &mut Self {
+        self.ip.get_or_insert_default().ipv6_suffix =
+            ipv6_suffix.map(|v| v.into());
+        self
+    }
+
+    /// Set the length of the IPv6 prefix kept with a host suffix.
+    pub fn ip_ipv6_prefix_len(
+        &mut self,
+        ipv6_prefix_len: Option<u8>,
+    ) -> &mut Self {
+        self.ip.get_or_insert_default().ipv6_prefix_len = ipv6_prefix_len;
+        self
+    }
+
+    /// Set the filter selecting the IPv6 address of the interface.
+    pub fn ip_ipv6_filter(
+        &mut self,
+        ipv6_filter: Option<impl Into<String>>,
+    ) -> &mut Self {
+        self.ip.get_or_insert_default().ipv6_filter =
+            ipv6_filter.map(|v| v.into());
+        self
+    }
+
+    /// Set the uplinks which selected records are published with.
+    pub fn ip_uplinks(
+        &mut self,
+        uplinks: Option<BTreeMap<String, ConfigOptsUplink>>,
+    ) -> &mut Self {
+        self.ip.get_or_insert_default().uplinks = uplinks;
+        self
+    }
+
+    /// Set the local address or interface requests are sent from.
+    pub fn ip_bind(&mut self, bind: Option<impl Into<String>>) -> &mut Self {
+        self.ip.get_or_insert_default().bind = bind.map(|v| v.into());
+        self
+    }
+
+    /// Set the templates of records created together by `record create`.
+    pub fn templates(
+        &mut self,
+        templates: Option<BTreeMap<String, ConfigOptsTemplate>>,
+    ) -> &mut Self {
+        self.templates = templates;
+        self
+    }
+
+    /// Set the IP source plugins.
+    pub fn ip_plugins(&mut self, plugins: Option<Vec<PathBuf>>) -> &mut Self {
+        self.ip.get_or_insert_default().plugins = plugins;
+        self
+    }
+
+    /// Build an configuration options model.
+    pub fn build(&self) -> ConfigOpts {
+        ConfigOpts {
+            api: {
+                let api = self.api.as_ref();
+                ConfigOptsApi {
+                    max_attempts: api.and_then(|o| o.max_attempts),
+                    retry_backoff: api.and_then(|o| o.retry_backoff),
+                    user_agent: api.and_then(|o| o.user_agent.clone()),
+                    base_url: api.and_then(|o| o.base_url.clone()),
+                    zones_ttl: api.and_then(|o| o.zones_ttl),
+                }
+            },
+            verify: {
+                let verify = self.verify.as_ref();
+                ConfigOptsVerify {
+                    token: verify.and_then(|o| o.token.clone()),
+                }
+            },
+            list: {
+                let list = self.list.as_ref();
+                ConfigOptsList {
+                    include_zones: list.and_then(|o| o.include_zones.clone()),
+                    ignore_zones: list.and_then(|o| o.ignore_zones.clone()),
+                    include_records: list
+                        .and_then(|o| o.include_records.clone()),
+                    ignore_records: list.and_then(|o| o.ignore_records.clone()),
+                    account: list.and_then(|o| o.account.clone()),
+                    query: list.and_then(|o| o.query.clone()),
+                    tag: list.and_then(|o| o.tag.clone()),
+                }
+            },
+            inventory: {
+                let inventory = self.inventory.as_ref();
+                ConfigOptsInventory {
+                    path: inventory.and_then(|o| o.path.clone()),
+                    force_update: inventory.and_then(|o| o.force_update),
+                    force_prune: inventory.and_then(|o| o.force_prune),
+                    watch_interval: inventory.and_then(|o| o.watch_interval),
+                    update_cooldown: inventory.and_then(|o| o.update_cooldown),
+                    reconcile_interval: inventory
+                        .and_then(|o| o.reconcile_interval),
+                    ipv4: inventory.and_then(|o| o.ipv4),
+                    ipv6: inventory.and_then(|o| o.ipv6),
+                    pair_records: inventory.and_then(|o| o.pair_records),
+                    check_via: inventory.and_then(|o| o.check_via),
+                    watch_events: inventory.and_then(|o| o.watch_events),
+                    watch_max_failures: inventory
+                        .and_then(|o| o.watch_max_failures),
+                    groups: inventory.and_then(|o| o.groups.clone()),
+                    watch_immediate: inventory.and_then(|o| o.watch_immediate),
+                    watch_listen: inventory.and_then(|o| o.watch_listen),
+                    watch_textfile_dir: inventory
+                        .and_then(|o| o.watch_textfile_dir.clone()),
+                    watch_summary: inventory.and_then(|o| o.watch_summary),
+                    comment_template: inventory
+                        .and_then(|o| o.comment_template.clone()),
+                    create_missing: inventory.and_then(|o| o.create_missing),
+                    prune_invalid: inventory.and_then(|o| o.prune_invalid),
+                    unsupported_types: inventory
+                        .and_then(|o| o.unsupported_types),
+                    notifiers: inventory.and_then(|o| o.notifiers.clone()),
+                }
+            },
+            ip: {
+                let ip = self.ip.as_ref();
+                ConfigOptsIp {
+                    ipv4_urls: ip.and_then(|o| o.ipv4_urls.clone()),
+                    ipv6_urls: ip.and_then(|o| o.ipv6_urls.clone()),
+                    interface: ip.and_then(|o| o.interface.clone()),
+                    router: ip.and_then(|o| o.router),
+                    dns: ip.and_then(|o| o.dns),
+                    command: ip.and_then(|o| o.command.clone()),
+                    sources: ip.and_then(|o| o.sources.clone()),
+                    consensus: ip.and_then(|o| o.consensus),
+                    confirm_delay: ip.and_then(|o| o.confirm_delay),
+                    allow_private: ip.and_then(|o| o.allow_private),
+                    ipv6_suffix: ip.and_then(|o| o.ipv6_suffix.clone()),
+                    ipv6_prefix_len: ip.and_then(|o| o.ipv6_prefix_len),
+                    ipv6_filter: ip.and_then(|o| o.ipv6_filter.clone()),
+                    uplinks: ip.and_then(|o| o.uplinks.clone()),
+                    bind: ip.and_then(|o| o.bind.clone()),
+                    plugins: ip.and_then(|o| o.plugins.clone()),
+                }
+            },
+            templates: self.templates.clone(),
+        }
+    }
+
+    /// Save the config file at the given path, overwriting if necessary.
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let toml = crate::util::encoding::as_toml(&self)?;
+        crate::util::fs::save(path, toml).await?;
+        Ok(())
+    }
+}
+
+impl From<ConfigOpts> for ConfigBuilder {
+    fn from(opts: ConfigOpts) -> Self {
+        Self {
+            api: Some(opts.api),
+            verify: Some(opts.verify),
+            list: Some(opts.list),
+            inventory: Some(opts.inventory),
+            ip: Some(opts.ip),
+            templates: opts.templates,
+        }
+    }
+}
+
+impl From<Option<ConfigOpts>> for ConfigBuilder {
+    fn from(opts: Option<ConfigOpts>) -> Self {
+        match opts {
+            None => Self {
+                api: None,
+                verify: None,
+                list: None,
+                inventory: None,
+                ip: None,
+                templates: None,
+            },
+            Some(o) => o.into(),
+        }
+    }
+}
@@ -4,9 +4,23 @@
 //! The config file is the base, which is then superseded by environment
 //! variables, which are finally superseded by CLI arguments and options.
 
+use crate::util::warnings::{self, Warning, WarningKind};
+
 pub mod builder;
 pub mod models;
 
+/// Deprecated configuration keys as `(section, key, replacement)`.
+///
+/// Deprecated keys are still honored: renamed keys when their replacement is
+/// absent, and the regex filters of `list` along with `list.query`.
+pub const DEPRECATED_KEYS: &[(&str, &str, &str)] = &[
+    ("inventory", "interval", "watch_interval"),
+    ("list", "include_zones", "query"),
+    ("list", "ignore_zones", "query"),
+    ("list", "include_records", "query"),
+    ("list", "ignore_records", "query"),
+];
+
 /// Return the default configuration path, depending on the host OS.
 ///
 /// - Linux: $XDG_CONFIG_HOME/cddns/config.toml or
@@ -21,6 +35,14 @@ pub fn default_config_path() -> std::path::PathBuf {
         config_path.push("config.toml");
         config_path
     } else {
+        warnings::emit(
+            Warning::new(
+                WarningKind::Compatibility,
+                "config.default_path",
+                "no configuration directory found for this OS, falling back to './config.toml'",
+            )
+            .with_help("use `--config` to provide a configuration path"),
+        );
         std::path::PathBuf::from("config.toml")
     }
 }
@@ -0,0 +1,887 @@
+use crate::cloudflare::API_BASE;
+use crate::config::builder::ConfigBuilder;
+use crate::config::{default_config_path, DEPRECATED_KEYS};
+use crate::inventory::default_inventory_path;
+use crate::inventory::lookup::CheckVia;
+use crate::inventory::policy::UnsupportedTypes;
+use crate::ip::dns::DnsProvider;
+use crate::ip::IpSource;
+use crate::util::warnings::{self, Warning, WarningKind};
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::{fmt::Debug, fmt::Display};
+use tracing::debug;
+
+/// The model of all configuration options which can be saved in a config file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConfigOpts {
+    pub api: ConfigOptsApi,
+    pub verify: ConfigOptsVerify,
+    pub list: ConfigOptsList,
+    pub inventory: ConfigOptsInventory,
+    pub ip: ConfigOptsIp,
+    /// Named templates of records created together by `record create`.
+    /// Only configurable by file.
+    pub templates: Option<BTreeMap<String, ConfigOptsTemplate>>,
+}
+
+impl Default for ConfigOpts {
+    /// Static default configuration options.
+    fn default() -> Self {
+        Self {
+            api: ConfigOptsApi {
+                max_attempts: Some(3),
+                retry_backoff: Some(500),
+                user_agent: None,
+                base_url: Some(API_BASE.to_string()),
+                zones_ttl: Some(3600),
+            },
+            verify: ConfigOptsVerify { token: None },
+            list: ConfigOptsList {
+                include_zones: Some(vec![".*".to_string()]),
+                ignore_zones: Some(vec![]),
+                include_records: Some(vec![".*".to_string()]),
+                ignore_records: Some(vec![]),
+                account: None,
+                query: None,
+                tag: None,
+            },
+            inventory: ConfigOptsInventory {
+                path: Some(default_inventory_path()),
+                force_update: Some(false),
+                force_prune: Some(false),
+                watch_interval: Some(30_000),
+                update_cooldown: Some(0),
+                reconcile_interval: Some(3_600_000),
+                ipv4: Some(true),
+                ipv6: Some(true),
+                pair_records: Some(false),
+                check_via: Some(CheckVia::Api),
+                watch_events: Some(false),
+                watch_max_failures: Some(0),
+                groups: None,
+                watch_immediate: Some(true),
+                watch_listen: None,
+                watch_textfile_dir: None,
+                watch_summary: Some(0),
+                comment_template: None,
+                create_missing: Some(false),
+                prune_invalid: Some(false),
+                unsupported_types: Some(UnsupportedTypes::Skip),
+                notifiers: None,
+            },
+            ip: ConfigOptsIp {
+                ipv4_urls: None,
+                ipv6_urls: None,
+                interface: None,
+                router: Some(false),
+                dns: None,
+                command: None,
+                sources: None,
+                consensus: Some(1),
+                confirm_delay: Some(0),
+                allow_private: Some(false),
+                ipv6_suffix: None,
+                ipv6_prefix_len: Some(64),
+                ipv6_filter: None,
+                uplinks: None,
+                bind: None,
+                plugins: None,
+            },
+            templates: None,
+        }
+    }
+}
+
+impl ConfigOpts {
+    /// Return a new configuration builder.
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+
+    /// Read runtime config from a target path.
+    pub fn from_file(path: Option<PathBuf>) -> Result<Option<Self>> {
+        let path = path.unwrap_or(default_config_path());
+        if path.exists() {
+            debug!("configuration file found");
+            debug!("reading configuration path: '{}'", path.display());
+            let cfg_bytes =
+                std::fs::read_to_string(path).context("reading config file")?;
+            Self::from_toml(&cfg_bytes)
+                .context("reading config file contents as TOML data")
+                .map(Some)
+        } else {
+            debug!("configuration file not found");
+            Ok(None)
+        }
+    }
+
+    /// Read runtime config from TOML data, such as a config piped to stdin.
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        let cfg: ConfigBuilder = toml::from_str(toml)?;
+        warn_deprecated_keys(toml);
+        Ok(cfg.build())
+    }
+
+    /// Read runtime config from environment variables.
+    pub fn from_env() -> Result<Self> {
+        Ok(ConfigOpts {
+            api: envy::prefixed("CDDNS_API_")
+                .from_env::<ConfigOptsApi>()
+                .context("reading api env var config")?,
+            verify: envy::prefixed("CDDNS_VERIFY_")
+                .from_env::<ConfigOptsVerify>()
+                .context("reading verify env var config")?,
+            list: envy::prefixed("CDDNS_LIST_")
+                .from_env::<ConfigOptsList>()
+                .context("reading list env var config")?,
+            inventory: envy::prefixed("CDDNS_INVENTORY_")
+                .from_env::<ConfigOptsInventory>()
+                .context("reading inventory env var config")?,
+            ip: envy::prefixed("CDDNS_IP_")
+                .from_env::<ConfigOptsIp>()
+                .context("reading ip env var config")?,
+            templates: None,
+        })
+    }
+}
+
+/// Surface warnings for deprecated keys in a TOML configuration.
+fn warn_deprecated_keys(toml: &str) {
+    let Ok(table) = toml.parse::<toml::Table>() else {
+        return;
+    };
+    for (section, key, replacement) in DEPRECATED_KEYS {
+        let present = table
+            .get(*section)
+            .and_then(|s| s.as_table())
+            .is_some_and(|s| s.contains_key(*key));
+        if present {
+            warnings::emit(
+                Warning::new(
+                    WarningKind::Deprecation,
+                    format!("config.{section}.{key}"),
+                    format!("config key `{section}.{key}` is deprecated"),
+                )
+                .with_help(format!("use `{section}.{replacement}` instead")),
+            );
+        }
+    }
+}
+
+fn __display<T>(opt: Option<&T>) -> String
+where
+    T: Serialize + Debug,
+{
+    if let Some(opt) = opt {
+        match ron::to_string(opt) {
+            Ok(ron) => ron,
+            Err(_) => format!("{opt:?}"),
+        }
+    } else {
+        "None".to_string()
+    }
+}
+
+impl Display for ConfigOpts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        try {
+            // API
+            writeln!(
+                f,
+                "Max request attempts: {}",
+                __display(self.api.max_attempts.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Retry backoff: {}",
+                __display(self.api.retry_backoff.as_ref())
+            )?;
+            writeln!(
+                f,
+                "User agent suffix: {}",
+                __display(self.api.user_agent.as_ref())
+            )?;
+            writeln!(
+                f,
+                "API base URL: {}",
+                __display(self.api.base_url.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Zones TTL: {}",
+                __display(self.api.zones_ttl.as_ref())
+            )?;
+
+            // Verify
+            writeln!(f, "Token: {}", __display(self.verify.token.as_ref()))?;
+
+            // List
+            writeln!(
+                f,
+                "Include zones: {}",
+                __display(self.list.include_zones.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Ignore zones: {}",
+                __display(self.list.ignore_zones.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Include records: {}",
+                __display(self.list.include_records.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Ignore records: {}",
+                __display(self.list.ignore_records.as_ref())
+            )?;
+            writeln!(f, "Account: {}", __display(self.list.account.as_ref()))?;
+            writeln!(f, "Query: {}", __display(self.list.query.as_ref()))?;
+            writeln!(f, "Tag: {}", __display(self.list.tag.as_ref()))?;
+
+            // Inventory
+            writeln!(
+                f,
+                "Inventory path: {}",
+                __display(self.inventory.path.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Force update without user prompt: {}",
+                __display(self.inventory.force_update.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Force prune without user prompt: {}",
+                __display(self.inventory.force_prune.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Watch interval: {}",
+                __display(self.inventory.watch_interval.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Update cooldown: {}",
+                __display(self.inventory.update_cooldown.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Reconcile interval: {}",
+                __display(self.inventory.reconcile_interval.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Inventory IPv4: {}",
+                __display(self.inventory.ipv4.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Inventory IPv6: {}",
+                __display(self.inventory.ipv6.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Inventory pair records: {}",
+                __display(self.inventory.pair_records.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Inventory check via: {}",
+                __display(self.inventory.check_via.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Inventory watch events: {}",
+                __display(self.inventory.watch_events.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Inventory watch max failures: {}",
+                __display(self.inventory.watch_max_failures.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Inventory groups: {}",
+                __display(
+                    self.inventory
+                        .groups
+                        .as_ref()
+                        .map(|g| g.keys().collect::<Vec<_>>())
+                        .as_ref()
+                )
+            )?;
+            writeln!(
+                f,
+                "Inventory watch immediate: {}",
+                __display(self.inventory.watch_immediate.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Inventory watch listen: {}",
+                __display(self.inventory.watch_listen.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Inventory watch textfile directory: {}",
+                __display(self.inventory.watch_textfile_dir.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Inventory watch summary: {}",
+                __display(self.inventory.watch_summary.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Comment template: {}",
+                __display(self.inventory.comment_template.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Create missing: {}",
+                __display(self.inventory.create_missing.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Prune invalid: {}",
+                __display(self.inventory.prune_invalid.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Unsupported types: {}",
+                __display(self.inventory.unsupported_types.as_ref())
+            )?;
+            writeln!(
+                f,
+                "Notifiers: {}",
+                __display(self.inventory.notifiers.as_ref())
+            )?;
+
+            // IP
+            writeln!(
+                f,
+                "IPv4 source URLs: {}",
+                __display(self.ip.ipv4_urls.as_ref())
+            )?;
+            writeln!(
+                f,
+                "IPv6 source URLs: {}",
+                __display(self.ip.ipv6_urls.as_ref())
+            )?;
+            writeln!(
+                f,
+                "IP interface: {}",
+                __display(self.ip.interface.as_ref())
+            )?;
+            writeln!(f, "IP router: {}", __display(self.ip.router.as_ref()))?;
+            writeln!(
+                f,
+                "IP DNS provider: {}",
+                __display(self.ip.dns.as_ref())
+            )?;
+            writeln!(f, "IP command: {}", __display(self.ip.command.as_ref()))?;
+            writeln!(f, "IP sources: {}", __display(self.ip.sources.as_ref()))?;
+            writeln!(
+                f,
+                "IP consensus: {}",
+                __display(self.ip.consensus.as_ref())
+            )?;
+            writeln!(
+                f,
+                "IP confirm delay: {}",
+                __display(self.ip.confirm_delay.as_ref())
+            )?;
+            writeln!(
+                f,
+                "IP allow private: {}",
+                __display(self.ip.allow_private.as_ref())
+            )?;
+            writeln!(
+                f,
+                "IPv6 suffix: {}",
+                __display(self.ip.ipv6_suffix.as_ref())
+            )?;
+            writeln!(
+                f,
+                "IPv6 prefix length: {}",
+                __display(self.ip.ipv6_prefix_len.as_ref())
+            )?;
+            writeln!(
+                f,
+                "IPv6 filter: {}",
+                __display(self.ip.ipv6_filter.as_ref())
+            )?;
+            writeln!(
+                f,
+                "IP uplinks: {}",
+                __display(
+                    self.ip
+                        .uplinks
+                        .as_ref()
+                        .map(|u| u.keys().collect::<Vec<_>>())
+                        .as_ref()
+                )
+            )?;
+            writeln!(f, "IP bind: {}", __display(self.ip.bind.as_ref()))?;
+            writeln!(f, "IP plugins: {}", __display(self.ip.plugins.as_ref()))?;
+
+            // Templates
+            write!(
+                f,
+                "Templates: {}",
+                __display(
+                    self.templates
+                        .as_ref()
+                        .map(|t| t.keys().collect::<Vec<_>>())
+                        .as_ref()
+                )
+            )?;
+        }
+    }
+}
+
+/// Config options for the Cloudflare API.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Args)]
+pub struct ConfigOptsApi {
+    /// The maximum attempts for each Cloudflare request, retrying transient
+    /// failures. [default: 3]
+    #[clap(long, env = "CDDNS_API_MAX_ATTEMPTS", value_name = "number")]
+    pub max_attempts: Option<u32>,
+    /// The initial delay between retried Cloudflare requests in milliseconds,
+    /// doubled after each attempt. [default: 500]
+    #[clap(long, env = "CDDNS_API_RETRY_BACKOFF", value_name = "ms")]
+    pub retry_backoff: Option<u64>,
+    /// A suffix appended to the `cddns/<version>` user agent of Cloudflare
+    /// requests, identifying this host or tool.
+    #[clap(long, env = "CDDNS_API_USER_AGENT", value_name = "suffix")]
+    pub user_agent: Option<String>,
+    /// The base URL of Cloudflare API requests, such as a proxy or mock
+    /// server. [default: https://api.cloudflare.com/client/v4]
+    #[clap(long, env = "CDDNS_API_BASE_URL", value_name = "url")]
+    pub base_url: Option<String>,
+    /// How long the zone list is cached in seconds, since zones change far
+    /// less often than records. 0 lists zones on every run. [default: 3600]
+    #[clap(long, env = "CDDNS_API_ZONES_TTL", value_name = "seconds")]
+    pub zones_ttl: Option<u64>,
+}
+
+/// Config options for the verify system.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Args)]
+pub struct ConfigOptsVerify {
+    // Your Cloudflare API key token.
+    #[clap(short, long, env = "CDDNS_VERIFY_TOKEN", value_name = "token")]
+    pub token: Option<String>,
+}
+
+/// Config options for the list system.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Args)]
+pub struct ConfigOptsList {
+    /// Include cloudflare zones by regex. [default: all]
+    #[clap(
+        long,
+        value_name = "pattern1,pattern2,..",
+        env = "CDDNS_LIST_INCLUDE_ZONES"
+    )]
+    pub include_zones: Option<Vec<String>>,
+    /// Ignore cloudflare zones by regex. [default: none]
+    #[clap(
+        long,
+        value_name = "pattern1,pattern2,..",
+        env = "CDDNS_LIST_IGNORE_ZONES"
+    )]
+    pub ignore_zones: Option<Vec<String>>,
+
+    /// Include cloudflare zone records by regex. [default: all]
+    #[clap(
+        long,
+        value_name = "pattern1,pattern2,..",
+        env = "CDDNS_LIST_INCLUDE_RECORDS"
+    )]
+    pub include_records: Option<Vec<String>>,
+    /// Ignore cloudflare zone records by regex. [default: none]
+    #[clap(
+        long,
+        value_name = "pattern1,pattern2,..",
+        env = "CDDNS_LIST_IGNORE_RECORDS"
+    )]
+    pub ignore_records: Option<Vec<String>>,
+    /// Only include zones owned by an account, by name or id. [default: all]
+    #[clap(long, value_name = "name|id", env = "CDDNS_LIST_ACCOUNT")]
+    pub account: Option<String>,
+    /// Only include records matching a query, e.g.
+    /// `type:A AND name~'^vpn\.'`. [default: all]
+    #[clap(long, value_name = "query", env = "CDDNS_LIST_QUERY")]
+    pub query: Option<String>,
+    /// Only include records carrying a Cloudflare tag, e.g. `ddns`. Tags with
+    /// a value, such as `ddns:home`, also match by their name. [default: all]
+    #[clap(long, value_name = "tag", env = "CDDNS_LIST_TAG")]
+    pub tag: Option<String>,
+}
+
+/// Config options for the inventory system.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Args)]
+pub struct ConfigOptsInventory {
+    /// The path to the inventory file.
+    #[clap(short, long, env = "CDDNS_INVENTORY_PATH", value_name = "file")]
+    pub path: Option<PathBuf>,
+    /// Skip prompts asking to update outdated DNS records.
+    #[clap(long, env = "CDDNS_INVENTORY_FORCE_UPDATE", value_name = "boolean")]
+    pub force_update: Option<bool>,
+    /// Skip prompts asking to prune invalid DNS records.
+    #[clap(long, env = "CDDNS_INVENTORY_FORCE_PRUNE", value_name = "boolean")]
+    pub force_prune: Option<bool>,
+    /// The interval for refreshing inventory records in milliseconds.
+    #[serde(alias = "interval")]
+    #[clap(
+        short,
+        long,
+        value_name = "ms",
+        env = "CDDNS_INVENTORY_WATCH_INTERVAL"
+    )]
+    pub watch_interval: Option<u64>,
+    /// The minimum milliseconds between updates of the same record.
+    #[clap(long, value_name = "ms", env = "CDDNS_INVENTORY_UPDATE_COOLDOWN")]
+    pub update_cooldown: Option<u64>,
+    /// The maximum milliseconds between full reconciliations of records in
+    /// watch mode, which otherwise skips Cloudflare while public IPs are
+    /// unchanged.
+    #[clap(
+        long,
+        value_name = "ms",
+        env = "CDDNS_INVENTORY_RECONCILE_INTERVAL"
+    )]
+    pub reconcile_interval: Option<u64>,
+    /// Handle A records and resolve the public IPv4 address. Disable on hosts
+    /// without IPv4 connectivity. [default: true]
+    #[clap(long, value_name = "boolean", env = "CDDNS_INVENTORY_IPV4")]
+    pub ipv4: Option<bool>,
+    /// Handle AAAA records and resolve the public IPv6 address. Disable on
+    /// hosts without IPv6 connectivity. [default: true]
+    #[clap(long, value_name = "boolean", env = "CDDNS_INVENTORY_IPV6")]
+    pub ipv6: Option<bool>,
+    /// Keep both the A and AAAA records of a name current when either is in
+    /// the inventory, so one entry manages a dual-stack host.
+    /// [default: false]
+    #[clap(long, value_name = "boolean", env = "CDDNS_INVENTORY_PAIR_RECORDS")]
+    pub pair_records: Option<bool>,
+    /// How records are checked: `api` lists records through Cloudflare,
+    /// while `dns` resolves record names and only uses the API when records
+    /// are outdated, conserving API quota. [default: api]
+    #[clap(long, value_name = "api|dns", env = "CDDNS_INVENTORY_CHECK_VIA")]
+    pub check_via: Option<CheckVia>,
+    /// Check records as soon as a local address is added or removed, such
+    /// as when reconnecting, in addition to the watch interval.
+    /// [default: false]
+    #[clap(long, value_name = "boolean", env = "CDDNS_INVENTORY_WATCH_EVENTS")]
+    pub watch_events: Option<bool>,
+    /// The number of consecutive failed checks after which `watch` exits
+    /// with an error, letting a supervisor restart it. 0 never exits.
+    /// [default: 0]
+    #[clap(
+        long,
+        value_name = "count",
+        env = "CDDNS_INVENTORY_WATCH_MAX_FAILURES"
+    )]
+    pub watch_max_failures: Option<u32>,
+    /// Named groups of inventory entries, each refreshed by `watch` on its
+    /// own interval, such as critical records every minute and others
+    /// hourly. Only configurable by file. [default: none]
+    #[clap(skip)]
+    pub groups: Option<BTreeMap<String, ConfigOptsGroup>>,
+    /// Check records as soon as `watch` starts, rather than waiting one
+    /// interval. [default: true]
+    #[clap(
+        long,
+        value_name = "boolean",
+        env = "CDDNS_INVENTORY_WATCH_IMMEDIATE"
+    )]
+    pub watch_immediate: Option<bool>,
+    /// Serve `/livez` and `/readyz` health endpoints on this address during
+    /// `watch`, for probes from Kubernetes or a load balancer.
+    /// [default: none]
+    #[clap(long, value_name = "address", env = "CDDNS_INVENTORY_WATCH_LISTEN")]
+    pub watch_listen: Option<SocketAddr>,
+    /// Write Prometheus metrics to `cddns.prom` in this directory after each
+    /// `watch` iteration, for the node_exporter textfile collector.
+    /// [default: none]
+    #[clap(
+        long,
+        value_name = "directory",
+        env = "CDDNS_INVENTORY_WATCH_TEXTFILE_DIR"
+    )]
+    pub watch_textfile_dir: Option<PathBuf>,
+    /// Condense routine `watch` logs into one summary line every this many
+    /// checks, or 0 to log every check in full. Updates, warnings and
+    /// errors are always logged. [default: 0]
+    #[clap(long, value_name = "checks", env = "CDDNS_INVENTORY_WATCH_SUMMARY")]
+    pub watch_summary: Option<u32>,
+    /// Set the Cloudflare comment of updated records from a template, where
+    /// `{name}`, `{content}` and `{time}` are replaced. [default: unchanged]
+    #[clap(
+        long,
+        value_name = "template",
+        env = "CDDNS_INVENTORY_COMMENT_TEMPLATE"
+    )]
+    pub comment_template: Option<String>,
+    /// Create inventory records missing from Cloudflare with the public IP
+    /// during `update`, rather than reporting them as invalid.
+    /// [default: false]
+    #[clap(
+        long,
+        value_name = "boolean",
+        env = "CDDNS_INVENTORY_CREATE_MISSING"
+    )]
+    pub create_missing: Option<bool>,
+    /// Prune invalid records from the inventory during `update`, checking
+    /// and fixing the inventory with a single fetch of zones and records.
+    /// [default: false]
+    #[clap(
+        long,
+        value_name = "boolean",
+        env = "CDDNS_INVENTORY_PRUNE_INVALID"
+    )]
+    pub prune_invalid: Option<bool>,
+    /// How records of a type cddns can't resolve, such as CNAME records
+    /// selected by a tag, are handled: `skip` warns and skips them, `error`
+    /// fails the check, and `static` treats them as always valid.
+    /// [default: skip]
+    #[clap(
+        long,
+        value_name = "skip|error|static",
+        env = "CDDNS_INVENTORY_UNSUPPORTED_TYPES"
+    )]
+    pub unsupported_types: Option<UnsupportedTypes>,
+    /// Notifier plugin executables, sent alerts when records become outdated
+    /// or invalid and when they recover, before those discovered in the
+    /// plugins directory.
+    #[clap(
+        long,
+        value_name = "path1,path2,..",
+        value_delimiter = ',',
+        env = "CDDNS_INVENTORY_NOTIFIERS"
+    )]
+    pub notifiers: Option<Vec<PathBuf>>,
+}
+
+/// Config options for a template of records created together, such as the
+/// A, AAAA and TXT records of a new host.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ConfigOptsTemplate {
+    /// The records created by this template.
+    #[serde(default)]
+    pub records: Vec<ConfigOptsTemplateRecord>,
+}
+
+/// Config options for a record created by a template.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConfigOptsTemplateRecord {
+    /// The type of the record.
+    #[serde(rename = "type")]
+    pub record_type: String,
+    /// The content of the record, where `{name}` is replaced with the name
+    /// of the record. Required, except for A and AAAA records, which default
+    /// to the public IP.
+    #[serde(default)]
+    pub content: Option<String>,
+    /// The time to live in seconds, where 1 is "Auto". [default: 1]
+    #[serde(default)]
+    pub ttl: Option<u32>,
+    /// Whether traffic is proxied through Cloudflare. [default: false]
+    #[serde(default)]
+    pub proxied: Option<bool>,
+    /// The Cloudflare comment of the record, where `{name}` is replaced with
+    /// the name of the record.
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Config options for a group of inventory entries refreshed on its own
+/// interval.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ConfigOptsGroup {
+    /// Inventory zones whose entries all belong to this group, as written in
+    /// the inventory.
+    #[serde(default)]
+    pub zones: Vec<String>,
+    /// Inventory entries belonging to this group, as written in the
+    /// inventory.
+    #[serde(default)]
+    pub records: Vec<String>,
+    /// The interval for refreshing this group in milliseconds.
+    pub interval: u64,
+}
+
+impl ConfigOptsGroup {
+    /// Returns whether an inventory entry belongs to this group.
+    pub fn contains(&self, zone: &str, record: &str) -> bool {
+        self.zones.iter().any(|z| z == zone)
+            || self.records.iter().any(|r| r == record)
+    }
+}
+
+impl ConfigOptsInventory {
+    /// Return the group an inventory entry belongs to, if any.
+    pub fn group_of(&self, zone: &str, record: &str) -> Option<&str> {
+        self.groups
+            .as_ref()?
+            .iter()
+            .find(|(_, group)| group.contains(zone, record))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+impl ConfigOptsIp {
+    /// Return the IP options of an uplink, layered over these options.
+    pub fn uplink(&self, name: &str) -> Option<ConfigOptsIp> {
+        let uplink = self.uplinks.as_ref()?.get(name)?;
+        let mut base = self.clone();
+        base.uplinks = None;
+        let mut layer = ConfigBuilder::new();
+        layer.ip(Some(uplink.ip.clone()));
+        let mut builder = ConfigOpts::builder();
+        builder.ip(Some(base)).merge(layer);
+        Some(builder.build().ip)
+    }
+}
+
+/// Config options for an uplink, a WAN connection with its own IP sources.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ConfigOptsUplink {
+    /// Inventory entries published with this uplink, such as record names,
+    /// IDs, or `tag:` and `comment:` selectors.
+    #[serde(default)]
+    pub records: Vec<String>,
+    /// IP options of this uplink, layered over the `ip` section.
+    #[serde(flatten)]
+    pub ip: ConfigOptsIp,
+}
+
+/// Config options for public IP resolution.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Args)]
+pub struct ConfigOptsIp {
+    /// URLs resolving the public IPv4 address, tried in order. Responses are
+    /// plain text, unless a URL fragment names a JSON field to read, such as
+    /// `https://api.ipify.org?format=json#ip`. [default: built-in sources]
+    #[clap(
+        long,
+        value_name = "url1,url2,..",
+        value_delimiter = ',',
+        env = "CDDNS_IP_IPV4_URLS"
+    )]
+    pub ipv4_urls: Option<Vec<String>>,
+    /// URLs resolving the public IPv6 address, tried in order. Responses are
+    /// plain text, unless a URL fragment names a JSON field to read, such as
+    /// `https://api64.ipify.org?format=json#ip`. [default: built-in sources]
+    #[clap(
+        long,
+        value_name = "url1,url2,..",
+        value_delimiter = ',',
+        env = "CDDNS_IP_IPV6_URLS"
+    )]
+    pub ipv6_urls: Option<Vec<String>>,
+    /// A network interface to read public IPs from, instead of resolving
+    /// them with external services.
+    #[clap(
+        long = "ip-interface",
+        value_name = "name",
+        env = "CDDNS_IP_INTERFACE"
+    )]
+    pub interface: Option<String>,
+    /// Query the public IPv4 address from the router via UPnP IGD or
+    /// NAT-PMP, instead of external services. [default: false]
+    #[clap(
+        long = "ip-router",
+        value_name = "boolean",
+        env = "CDDNS_IP_ROUTER"
+    )]
+    pub router: Option<bool>,
+    /// A DNS provider to resolve public IPs with, instead of HTTP services.
+    #[clap(long = "ip-dns", value_name = "provider", env = "CDDNS_IP_DNS")]
+    pub dns: Option<DnsProvider>,
+    /// A shell command printing the public IP to publish, run with
+    /// `CDDNS_IP_VERSION` set to `4` or `6`, instead of external services.
+    #[clap(
+        long = "ip-command",
+        value_name = "command",
+        env = "CDDNS_IP_COMMAND"
+    )]
+    pub command: Option<String>,
+    /// The IP sources to try in order, from `command`, `plugin`, `interface`,
+    /// `router`, `dns`, `http` and `builtin`. [default: every configured
+    /// source, or else the built-in sources]
+    #[clap(
+        long = "ip-sources",
+        value_name = "source1,source2,..",
+        value_delimiter = ',',
+        env = "CDDNS_IP_SOURCES"
+    )]
+    pub sources: Option<Vec<IpSource>>,
+    /// The number of IP sources which must resolve the same address before
+    /// it is trusted, trying sources of the chain in order. [default: 1]
+    #[clap(
+        long = "ip-consensus",
+        value_name = "count",
+        env = "CDDNS_IP_CONSENSUS"
+    )]
+    pub consensus: Option<u32>,
+    /// Milliseconds to wait before re-resolving the public IPs to confirm an
+    /// update, skipping it unless both readings match. [default: 0, off]
+    #[clap(
+        long = "ip-confirm-delay",
+        value_name = "ms",
+        env = "CDDNS_IP_CONFIRM_DELAY"
+    )]
+    pub confirm_delay: Option<u64>,
+    /// Publish resolved IPs which are not publicly routable, such as private
+    /// or CGNAT (100.64.0.0/10) addresses, with a warning instead of refusing
+    /// to update. [default: false]
+    #[clap(
+        long = "ip-allow-private",
+        value_name = "boolean",
+        env = "CDDNS_IP_ALLOW_PRIVATE"
+    )]
+    pub allow_private: Option<bool>,
+    /// A host suffix to publish within the prefix of the resolved IPv6
+    /// address, as an interface identifier such as `::1234:5678:9abc:def0`
+    /// or a MAC address expanded to EUI-64. [default: none]
+    #[clap(long, value_name = "suffix", env = "CDDNS_IP_IPV6_SUFFIX")]
+    pub ipv6_suffix: Option<String>,
+    /// The length of the prefix kept from the resolved IPv6 address when
+    /// combining it with `ip.ipv6_suffix`. [default: 64]
+    #[clap(long, value_name = "bits", env = "CDDNS_IP_IPV6_PREFIX_LEN")]
+    pub ipv6_prefix_len: Option<u8>,
+    /// Select the IPv6 address of `ip.interface` to publish by prefix, such as
+    /// `2001:db8:1::/48`, or else by regular expression. Stable addresses are
+    /// preferred over temporary ones. [default: none]
+    #[clap(long, value_name = "prefix|regex", env = "CDDNS_IP_IPV6_FILTER")]
+    pub ipv6_filter: Option<String>,
+    /// Named uplinks with their own IP sources, and the inventory entries
+    /// published with each, for sites with several WAN connections. Only
+    /// configurable by file. [default: none]
+    #[clap(skip)]
+    pub uplinks: Option<BTreeMap<String, ConfigOptsUplink>>,
+    /// A local address, or a network interface whose address is used, to
+    /// send Cloudflare requests and IP detection from, for multi-homed hosts.
+    /// [default: chosen by the OS]
+    #[clap(
+        long = "ip-bind",
+        value_name = "address|interface",
+        env = "CDDNS_IP_BIND"
+    )]
+    pub bind: Option<String>,
+    /// IP source plugin executables, tried in order by the `plugin` source
+    /// before those discovered in the plugins directory.
+    #[clap(
+        long = "ip-plugins",
+        value_name = "path1,path2,..",
+        value_delimiter = ',',
+        env = "CDDNS_IP_PLUGINS"
+    )]
+    pub plugins: Option<Vec<PathBuf>>,
+}
@@ -0,0 +1,196 @@
+//! Typed errors of the Cloudflare client and inventory operations.
+
+use reqwest::StatusCode;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// A result with the error type of this crate.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A boxed error from a parser or encoder.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// An error of the Cloudflare client or inventory operations.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// No Cloudflare API token was provided.
+    #[error("no token was provided, need help? see https://github.com/simbleau/cddns#readme")]
+    MissingToken,
+    /// Cloudflare rejected the token, or the token lacks permission.
+    #[error("cloudflare rejected the API token: {message} (status {status}, cf-ray {ray})")]
+    Auth {
+        status: StatusCode,
+        ray: String,
+        message: String,
+    },
+    /// Cloudflare rate limited the request.
+    #[error("rate limited by cloudflare (status {status}, cf-ray {ray})")]
+    RateLimited {
+        status: StatusCode,
+        ray: String,
+        /// The delay requested by Cloudflare before retrying.
+        retry_after: Option<Duration>,
+    },
+    /// Cloudflare responded with an unsuccessful status.
+    #[error("unsuccessful cloudflare status: {message} (status {status}, cf-ray {ray})")]
+    Api {
+        status: StatusCode,
+        ray: String,
+        message: String,
+    },
+    /// Cloudflare failed to handle the request.
+    #[error("cloudflare responded with server error (status {status}, cf-ray {ray})")]
+    Server { status: StatusCode, ray: String },
+    /// A listing didn't end within the most pages retrieved, such as with
+    /// malformed pagination metadata.
+    #[error("stopped listing {what} after {pages} pages, cloudflare may be sending malformed pagination")]
+    Pagination { what: String, pages: u32 },
+    /// The request could not be sent, or the response could not be read.
+    #[error("error sending web request")]
+    Network(#[source] reqwest::Error),
+    /// The request did not complete in time.
+    #[error("request to cloudflare timed out")]
+    Timeout,
+    /// A zone, record, or file was not found.
+    #[error("{0} not found")]
+    NotFound(String),
+    /// The inventory file was not found.
+    #[error("inventory file '{}' not found", .0.display())]
+    InventoryNotFound(PathBuf),
+    /// Data could not be parsed, such as the config or inventory file.
+    #[error("error parsing {what}")]
+    Parse {
+        what: String,
+        #[source]
+        source: BoxError,
+    },
+    /// A response could not be parsed, such as from Cloudflare, an IP source
+    /// or a plugin.
+    #[error("error parsing {what}")]
+    Response {
+        what: String,
+        #[source]
+        source: BoxError,
+    },
+    /// Data could not be encoded.
+    #[error("error encoding {what}")]
+    Encode {
+        what: String,
+        #[source]
+        source: BoxError,
+    },
+    /// A file could not be read or written.
+    #[error("unable to access '{}'", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    /// A public IP address could not be resolved from a source.
+    #[error("unable to resolve {what}")]
+    Resolve {
+        what: String,
+        #[source]
+        source: BoxError,
+    },
+    /// An external plugin failed or answered with an invalid response.
+    #[error("plugin '{}' failed: {reason}", .path.display())]
+    Plugin { path: PathBuf, reason: String },
+    /// An inventory record is of a type whose content can't be resolved.
+    #[error("unsupported {record_type} record '{name}', only A and AAAA records can be checked")]
+    UnsupportedRecord { name: String, record_type: String },
+    /// The operation requires Cloudflare, but offline mode is enabled.
+    #[error("cannot {0} in offline mode")]
+    Offline(String),
+    /// The configuration or state of the client is invalid.
+    #[error("{0}")]
+    Config(String),
+}
+
+impl Error {
+    /// Return a machine-stable code of the error kind, such as
+    /// `AUTH_FAILED`, for wrappers which can't parse messages.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::MissingToken => "TOKEN_MISSING",
+            Error::Auth { .. } => "AUTH_FAILED",
+            Error::RateLimited { .. } => "RATE_LIMITED",
+            Error::Api { .. } => "API_ERROR",
+            Error::Server { .. } => "SERVER_ERROR",
+            Error::Pagination { .. } => "PAGINATION_FAILED",
+            Error::Network(_) => "NETWORK_ERROR",
+            Error::Timeout => "TIMEOUT",
+            Error::NotFound(_) => "NOT_FOUND",
+            Error::InventoryNotFound(_) => "INVENTORY_NOT_FOUND",
+            Error::Parse { .. } => "PARSE_ERROR",
+            Error::Response { .. } => "RESPONSE_INVALID",
+            Error::Encode { .. } => "ENCODE_ERROR",
+            Error::Io { .. } => "IO_ERROR",
+            Error::Resolve { .. } => "IP_UNRESOLVED",
+            Error::Plugin { .. } => "PLUGIN_FAILED",
+            Error::UnsupportedRecord { .. } => "UNSUPPORTED_RECORD",
+            Error::Offline(_) => "OFFLINE",
+            Error::Config(_) => "CONFIG_INVALID",
+        }
+    }
+
+    /// Create a parse error.
+    pub fn parse(what: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        Self::Parse {
+            what: what.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Create a response parse error.
+    pub fn response(
+        what: impl Into<String>,
+        source: impl Into<BoxError>,
+    ) -> Self {
+        Self::Response {
+            what: what.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Create an encoding error.
+    pub fn encode(
+        what: impl Into<String>,
+        source: impl Into<BoxError>,
+    ) -> Self {
+        Self::Encode {
+            what: what.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Create an IP resolution error.
+    pub fn resolve(
+        what: impl Into<String>,
+        source: impl Into<BoxError>,
+    ) -> Self {
+        Self::Resolve {
+            what: what.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Create a file access error.
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Returns whether the error may not recur if the request is retried.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited { .. }
+                | Self::Server { .. }
+                | Self::Network(_)
+                | Self::Timeout
+        )
+    }
+}
@@ -0,0 +1,110 @@
+use crate::error::{Error, Result};
+use crate::inventory::state::state_dir;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+/// A change to the content of a record made by cddns.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the change was applied.
+    pub time: DateTime<Utc>,
+    pub zone_id: String,
+    pub record_id: String,
+    pub name: String,
+    /// The content of the record before the change.
+    pub from: String,
+    /// The content of the record after the change.
+    pub to: String,
+}
+
+impl AuditEntry {
+    /// Create an audit entry for a change applied now.
+    pub fn new(
+        zone_id: impl Into<String>,
+        record_id: impl Into<String>,
+        name: impl Into<String>,
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        Self {
+            time: Utc::now(),
+            zone_id: zone_id.into(),
+            record_id: record_id.into(),
+            name: name.into(),
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+}
+
+/// Return the default audit log path, in the state directory.
+///
+/// - Linux: $XDG_STATE_HOME/cddns/audit.jsonl or
+///   $HOME/.local/state/cddns/audit.jsonl
+/// - MacOS: $HOME/Library/Application Support/cddns/audit.jsonl
+/// - Windows: {FOLDERID_LocalAppData}/cddns/audit.jsonl
+/// - Else: None, auditing is unavailable
+pub fn default_audit_path() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("audit.jsonl"))
+}
+
+/// Read the audit log, oldest entries first.
+pub async fn read() -> Result<Vec<AuditEntry>> {
+    let path = default_audit_path()
+        .ok_or_else(|| Error::NotFound("state directory".into()))?;
+    let contents = match tokio::fs::read_to_string(&path).await {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(vec![]);
+        }
+        Err(err) => return Err(Error::io(path, err)),
+    };
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|err| Error::parse("audit log", err))
+        })
+        .collect()
+}
+
+/// Append entries to the audit log. Failures are logged, as auditing is
+/// best-effort.
+pub async fn append(entries: &[AuditEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    let result: Result<()> = try {
+        let path = default_audit_path()
+            .ok_or_else(|| Error::NotFound("state directory".into()))?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|err| Error::io(parent, err))?;
+        }
+        let mut contents = String::new();
+        for entry in entries {
+            contents.push_str(
+                &serde_json::to_string(entry)
+                    .map_err(|err| Error::encode("audit entry", err))?,
+            );
+            contents.push('\n');
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .map_err(|err| Error::io(&path, err))?;
+        file.write_all(contents.as_bytes())
+            .await
+            .map_err(|err| Error::io(&path, err))?;
+    };
+    if let Err(err) = result {
+        warn!("unable to write audit log: {err}");
+    }
+}
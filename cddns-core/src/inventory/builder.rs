@@ -0,0 +1,88 @@
+use crate::error::{Error, Result};
+use crate::inventory::models::Inventory;
+use crate::inventory::models::InventoryData;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+/// A builder for an inventory.
+#[derive(Default)]
+pub struct InventoryBuilder {
+    path: Option<PathBuf>,
+    data: Option<InventoryData>,
+}
+
+impl InventoryBuilder {
+    /// Create a new inventory builder.
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            data: None,
+        }
+    }
+
+    /// Initialize the inventory's path.
+    pub fn path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path.replace(path.as_ref().to_owned());
+        self
+    }
+
+    /// Initialize inventory with data.
+    pub fn with_data(mut self, data: InventoryData) -> Self {
+        self.data.replace(data);
+        self
+    }
+
+    /// Initialize inventory data from bytes.
+    pub fn with_bytes<'a>(
+        mut self,
+        bytes: impl Into<&'a [u8]>,
+    ) -> Result<Self> {
+        let bytes = bytes.into();
+        let contents = match std::str::from_utf8(bytes) {
+            Ok(contents) => quote_wildcards(contents),
+            Err(_) => String::from_utf8_lossy(bytes),
+        };
+        self.data.replace(
+            serde_yaml::from_str(&contents)
+                .map_err(|err| Error::parse("inventory", err))?,
+        );
+        Ok(self)
+    }
+
+    /// Build an inventory model.
+    pub fn build(self) -> Result<Inventory> {
+        Ok(Inventory {
+            path: self
+                .path
+                .ok_or_else(|| Error::Config("uninitalized path".into()))?,
+            data: self.data.ok_or_else(|| {
+                Error::Config("uninitialized inventory data".into())
+            })?,
+        })
+    }
+}
+
+/// Quote wildcard list items, so `- *.example.com` is read as the name
+/// `*.example.com` rather than a YAML alias.
+fn quote_wildcards(contents: &str) -> Cow<'_, str> {
+    if !contents.contains('*') {
+        return Cow::Borrowed(contents);
+    }
+    let lines: Vec<Cow<str>> = contents
+        .lines()
+        .map(|line| {
+            let Some(item) = line.trim_start().strip_prefix('-') else {
+                return Cow::Borrowed(line);
+            };
+            let value = item.trim_start();
+            if !value.starts_with('*') || value.len() == item.len() {
+                return Cow::Borrowed(line);
+            }
+            let end = value.find(char::is_whitespace).unwrap_or(value.len());
+            let (name, rest) = value.split_at(end);
+            let indent = &line[..line.len() - value.len()];
+            Cow::Owned(format!("{indent}'{name}'{rest}"))
+        })
+        .collect();
+    Cow::Owned(lines.join("\n"))
+}
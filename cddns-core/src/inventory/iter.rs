@@ -40,9 +40,12 @@ impl IntoIterator for InventoryData {
                 }
             }
         }
-        InventoryIter {
-            items: Vec::from_iter(items),
-            curr: 0,
+        // Sort for deterministic iteration
+        let mut items = Vec::from_iter(items);
+        for (_, records) in items.iter_mut() {
+            records.sort();
         }
+        items.sort();
+        InventoryIter { items, curr: 0 }
     }
 }
@@ -0,0 +1,75 @@
+use crate::error::Result;
+use crate::ip::dns;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// How inventory records are checked against the public IP.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckVia {
+    /// List records through the Cloudflare API.
+    Api,
+    /// Resolve record names through DNS, using the API only when records
+    /// are outdated or cannot be resolved.
+    Dns,
+}
+
+impl Display for CheckVia {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckVia::Api => write!(f, "api"),
+            CheckVia::Dns => write!(f, "dns"),
+        }
+    }
+}
+
+impl FromStr for CheckVia {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+    }
+}
+
+/// The addresses published for a name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Published {
+    pub ipv4: Vec<Ipv4Addr>,
+    pub ipv6: Vec<Ipv6Addr>,
+}
+
+impl Published {
+    /// Returns whether no address is published.
+    pub fn is_empty(&self) -> bool {
+        self.ipv4.is_empty() && self.ipv6.is_empty()
+    }
+}
+
+/// Returns whether an inventory entry is a hostname which can be resolved,
+/// rather than a Cloudflare ID or a tag or comment selector.
+pub fn is_hostname(entry: &str) -> bool {
+    entry.contains('.') && !entry.contains(':')
+}
+
+/// Look up the addresses published for a name, of the enabled families,
+/// from a local address.
+pub async fn lookup(
+    name: &str,
+    ipv4: bool,
+    ipv6: bool,
+    local: IpAddr,
+) -> Result<Published> {
+    let mut published = Published::default();
+    if ipv4 {
+        published.ipv4 = dns::lookup(name, local).await?;
+    }
+    if ipv6 {
+        published.ipv6 = dns::lookup(name, local).await?;
+    }
+    Ok(published)
+}
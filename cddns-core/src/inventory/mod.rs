@@ -9,11 +9,25 @@
 //! # Or Cloudflare names
 //! imbleau.com:
 //!   - *.imbleau.com
+//! # Or all records with a Cloudflare tag or comment marker
+//! imbleau.dev:
+//!   - tag:ddns
+//!   - comment:managed-by-cddns
 //! ```
 
+use crate::util::warnings::{self, Warning, WarningKind};
+
+pub mod audit;
 pub mod builder;
 pub mod iter;
+pub mod lookup;
 pub mod models;
+pub mod policy;
+pub mod queue;
+pub mod scope;
+pub mod selector;
+pub mod state;
+pub mod ttl;
 
 /// Return the default inventory path, depending on the host OS.
 ///
@@ -29,6 +43,14 @@ pub fn default_inventory_path() -> std::path::PathBuf {
         config_path.push("inventory.yml");
         config_path
     } else {
+        warnings::emit(
+            Warning::new(
+                WarningKind::Compatibility,
+                "inventory.default_path",
+                "no configuration directory found for this OS, falling back to './inventory.yml'",
+            )
+            .with_help("use `--path` to provide an inventory path"),
+        );
         std::path::PathBuf::from("inventory.yml")
     }
 }
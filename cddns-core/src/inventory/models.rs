@@ -1,9 +1,9 @@
 use crate::config::models::ConfigOpts;
+use crate::error::{Error, Result};
 use crate::inventory::builder::InventoryBuilder;
 use crate::util::postprocessors::{
     InventoryAliasCommentPostProcessor, PostProcessor, TimestampPostProcessor,
 };
-use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
@@ -26,19 +26,14 @@ impl Inventory {
         let path = path.as_ref();
         debug!("reading inventory path: '{}'", path.display());
         if !path.exists() {
-            bail!("inventory file not found, need help? see https://github.com/simbleau/cddns#readme");
+            return Err(Error::InventoryNotFound(path.to_owned()));
         } else {
             debug!("inventory file found");
         }
-        let path = path.canonicalize().with_context(|| {
-            format!(
-                "getting canonical path to inventory file '{}'",
-                path.display()
-            )
-        })?;
+        let path = path.canonicalize().map_err(|err| Error::io(path, err))?;
         let contents = tokio::fs::read_to_string(&path)
             .await
-            .context("reading inventory file")?;
+            .map_err(|err| Error::io(&path, err))?;
         Inventory::builder()
             .path(path)
             .with_bytes(contents.as_bytes())?
@@ -150,6 +145,21 @@ impl InventoryData {
         Ok(removed)
     }
 
+    /// Retain only the records for which a predicate, given the zone and
+    /// record as written in the inventory, returns true.
+    pub fn retain(&mut self, mut f: impl FnMut(&str, &str) -> bool) {
+        if let Some(map) = self.0.as_mut() {
+            for (zone_id, zone) in map.iter_mut() {
+                if let Some(records) = zone.0.as_mut() {
+                    records.retain(|record| f(zone_id, &record.0));
+                }
+            }
+            map.retain(|_, zone| {
+                zone.0.as_ref().is_some_and(|r| !r.is_empty())
+            });
+        }
+    }
+
     /// Returns whether the inventory data has no records
     pub fn is_empty(&self) -> bool {
         // Magic that checks whether there are records
@@ -0,0 +1,37 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// How inventory records of a type without a resolvable content, such as
+/// CNAME or TXT records, are handled. Only A and AAAA records are resolved.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum UnsupportedTypes {
+    /// Skip the record with a warning.
+    Skip,
+    /// Fail the check, and any update or watch tick running it.
+    Error,
+    /// Treat the record as static content, which is always valid.
+    Static,
+}
+
+impl Display for UnsupportedTypes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnsupportedTypes::Skip => write!(f, "skip"),
+            UnsupportedTypes::Error => write!(f, "error"),
+            UnsupportedTypes::Static => write!(f, "static"),
+        }
+    }
+}
+
+impl FromStr for UnsupportedTypes {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+    }
+}
@@ -0,0 +1,223 @@
+use crate::cloudflare::endpoints::{update_record, update_records};
+use crate::error::Error;
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+
+/// The most update requests in flight at once.
+pub const MAX_CONCURRENT_UPDATES: usize = 6;
+
+/// The fewest pending updates in a zone which are applied as a batch.
+pub const BATCH_THRESHOLD: usize = 2;
+
+/// Render a template of a record comment, replacing `{name}` and `{content}`
+/// with those of the record, and `{time}` with the current time, e.g.
+/// `managed by cddns, updated {time}`.
+pub fn render_comment(template: &str, name: &str, content: &str) -> String {
+    let time = Utc::now();
+    template
+        .replace("{name}", name)
+        .replace("{content}", content)
+        .replace("{time}", &time.format("%Y-%m-%dT%H:%MZ").to_string())
+}
+
+/// A pending patch to the content of a DNS record.
+#[derive(Clone, Debug)]
+pub struct QueuedUpdate {
+    pub zone_id: String,
+    pub record_id: String,
+    pub name: String,
+    pub content: String,
+    /// The comment set on the record, if any.
+    pub comment: Option<String>,
+}
+
+/// The outcome of flushing an update queue.
+#[derive(Debug, Default)]
+pub struct UpdateReport {
+    /// Updates which were applied.
+    pub updated: Vec<QueuedUpdate>,
+    /// Updates which failed, with the error, which is shared by the updates
+    /// of a batch failing transiently.
+    pub failed: Vec<(QueuedUpdate, Arc<Error>)>,
+}
+
+/// A queue of DNS record updates, grouped per zone.
+///
+/// Zones are flushed concurrently, while the updates of each zone are applied
+/// and reported in the order they were queued, so a later update never
+/// overtakes an earlier one in the same zone. Zones with several pending
+/// updates are flushed with a single batch request, falling back to
+/// individual updates if the batch is rejected. Failed requests are only
+/// retried by the request layer, per `api.max_attempts`. Queueing an update
+/// for a record which is already pending replaces the pending content in
+/// place.
+#[derive(Debug, Default)]
+pub struct UpdateQueue {
+    zones: BTreeMap<String, Vec<QueuedUpdate>>,
+    comment_template: Option<String>,
+}
+
+impl UpdateQueue {
+    /// Create a new, empty update queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the comment of updated records from a template, see
+    /// [`render_comment`].
+    pub fn comment_template(mut self, template: Option<String>) -> Self {
+        self.comment_template = template;
+        self
+    }
+
+    /// Queue an update, returning whether it replaced a pending update to the
+    /// same record.
+    pub fn push(
+        &mut self,
+        zone_id: impl Into<String>,
+        record_id: impl Into<String>,
+        name: impl Into<String>,
+        content: impl Into<String>,
+    ) -> bool {
+        let zone_id = zone_id.into();
+        let record_id = record_id.into();
+        let name = name.into();
+        let content = content.into();
+        let comment = self
+            .comment_template
+            .as_ref()
+            .map(|template| render_comment(template, &name, &content));
+        let updates = self.zones.entry(zone_id.clone()).or_default();
+        match updates.iter_mut().find(|u| u.record_id == record_id) {
+            Some(pending) => {
+                debug!(id = record_id, "deduplicated queued update");
+                pending.content = content;
+                pending.comment = comment;
+                true
+            }
+            None => {
+                updates.push(QueuedUpdate {
+                    zone_id,
+                    record_id,
+                    name,
+                    content,
+                    comment,
+                });
+                false
+            }
+        }
+    }
+
+    /// Returns the number of pending updates.
+    pub fn len(&self) -> usize {
+        self.zones.values().map(Vec::len).sum()
+    }
+
+    /// Returns whether there are no pending updates.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Apply all pending updates, leaving the queue empty.
+    ///
+    /// Zones are flushed concurrently, with at most
+    /// [`MAX_CONCURRENT_UPDATES`] requests in flight at once, and reported in
+    /// order of their ID.
+    pub async fn flush(&mut self, token: &str) -> UpdateReport {
+        let mut report = UpdateReport::default();
+        let token: Arc<str> = Arc::from(token);
+        let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_UPDATES));
+        let mut tasks = JoinSet::new();
+        for (zone_id, updates) in std::mem::take(&mut self.zones) {
+            debug!(zone = zone_id, "flushing {} updates", updates.len());
+            let (token, permits) = (token.clone(), permits.clone());
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                let flushed = Self::flush_zone(&token, &zone_id, updates).await;
+                (zone_id, flushed)
+            });
+        }
+
+        // Report in a stable order, regardless of completion order
+        let mut zones = BTreeMap::new();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((zone_id, flushed)) => {
+                    zones.insert(zone_id, flushed);
+                }
+                Err(err) => warn!("zone update task failed: {err}"),
+            }
+        }
+        for flushed in zones.into_values() {
+            report.updated.extend(flushed.updated);
+            report.failed.extend(flushed.failed);
+        }
+        report
+    }
+
+    /// Apply the pending updates of a zone in order, with a batch request if
+    /// there are several.
+    async fn flush_zone(
+        token: &str,
+        zone_id: &str,
+        updates: Vec<QueuedUpdate>,
+    ) -> UpdateReport {
+        let mut report = UpdateReport::default();
+        if updates.len() >= BATCH_THRESHOLD {
+            let patches = updates
+                .iter()
+                .map(|u| (&u.record_id, &u.content, u.comment.as_deref()))
+                .collect::<Vec<_>>();
+            match update_records(token, zone_id, &patches).await {
+                Ok(_) => {
+                    report.updated = updates;
+                    return report;
+                }
+                // Requests failing transiently were already retried, and
+                // updating records individually would only fail likewise
+                Err(err) if err.is_transient() => {
+                    let err = Arc::new(err);
+                    report.failed = updates
+                        .into_iter()
+                        .map(|update| (update, err.clone()))
+                        .collect();
+                    return report;
+                }
+                Err(err) => warn!(
+                    zone = zone_id,
+                    "batch update failed, updating records individually: {err:#}"
+                ),
+            }
+        }
+        for update in updates {
+            match Self::apply(token, update).await {
+                Ok(update) => report.updated.push(update),
+                Err(failure) => report.failed.push(failure),
+            }
+        }
+        report
+    }
+
+    /// Apply a single update.
+    async fn apply(
+        token: &str,
+        update: QueuedUpdate,
+    ) -> Result<QueuedUpdate, (QueuedUpdate, Arc<Error>)> {
+        let result = update_record(
+            token,
+            &update.zone_id,
+            &update.record_id,
+            &update.content,
+            update.comment.as_deref(),
+        )
+        .await;
+        match result {
+            Ok(_) => Ok(update),
+            Err(err) => Err((update, Arc::new(err))),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::cloudflare::models::Record;
+use crate::config::models::ConfigOptsInventory;
+use crate::util::names;
+use regex::Regex;
+use std::fmt::{self, Display};
+
+/// The inventory entries a run applies to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Scope {
+    /// All entries.
+    #[default]
+    All,
+    /// The entries of a group in `inventory.groups`.
+    Group(String),
+    /// The entries which belong to no group.
+    Ungrouped,
+    /// The records matching any of the filters. Entries are kept as written,
+    /// since an ID or tag may select a matching record.
+    Records(Vec<RecordFilter>),
+}
+
+impl Scope {
+    /// Return the scope of the given record filters, or all entries if there
+    /// are none.
+    pub fn records(filters: &[String]) -> Self {
+        if filters.is_empty() {
+            Scope::All
+        } else {
+            Scope::Records(
+                filters.iter().map(|f| RecordFilter::new(f)).collect(),
+            )
+        }
+    }
+
+    /// Returns whether an inventory entry, as written in the inventory, is in
+    /// scope.
+    pub fn contains(
+        &self,
+        opts: &ConfigOptsInventory,
+        zone: &str,
+        record: &str,
+    ) -> bool {
+        match self {
+            Scope::All | Scope::Records(_) => true,
+            Scope::Group(name) => {
+                opts.group_of(zone, record) == Some(name.as_str())
+            }
+            Scope::Ungrouped => opts.group_of(zone, record).is_none(),
+        }
+    }
+
+    /// Returns whether a Cloudflare record selected by an entry in scope is
+    /// in scope.
+    pub fn selects(&self, record: &Record) -> bool {
+        match self {
+            Scope::Records(filters) => {
+                filters.iter().any(|filter| filter.matches(record))
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns whether an inventory entry names a record in scope, as
+    /// written in the inventory.
+    pub fn names(&self, record: &str) -> bool {
+        match self {
+            Scope::Records(filters) => {
+                filters.iter().any(|filter| filter.matches_text(record))
+            }
+            _ => true,
+        }
+    }
+}
+
+/// A filter selecting records by name, ID, or a regex matching the whole
+/// name. Filters which are not a valid regex, such as wildcard names, only
+/// match exactly.
+#[derive(Clone, Debug)]
+pub struct RecordFilter {
+    source: String,
+    regex: Option<Regex>,
+}
+
+impl RecordFilter {
+    /// Create a record filter.
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            regex: Regex::new(&format!("^(?:{source})$")).ok(),
+        }
+    }
+
+    /// Returns whether a record matches the filter.
+    pub fn matches(&self, record: &Record) -> bool {
+        self.matches_text(&record.id) || self.matches_text(&record.name)
+    }
+
+    /// Returns whether a record name or ID matches the filter.
+    fn matches_text(&self, text: &str) -> bool {
+        names::same(text, &self.source)
+            || self.regex.as_ref().is_some_and(|r| r.is_match(text))
+    }
+}
+
+impl Display for RecordFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl PartialEq for RecordFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for RecordFilter {}
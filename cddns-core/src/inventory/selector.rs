@@ -0,0 +1,134 @@
+use crate::cloudflare::models::Record;
+use crate::cloudflare::query::Query;
+use crate::error::Result;
+use crate::util::names;
+
+/// The prefix of inventory entries selecting records by Cloudflare tag.
+pub const TAG_PREFIX: &str = "tag:";
+/// The prefix of inventory entries selecting records by Cloudflare comment.
+pub const COMMENT_PREFIX: &str = "comment:";
+/// The prefix of inventory entries selecting records by query.
+pub const QUERY_PREFIX: &str = "query:";
+
+/// A selector for the Cloudflare records an inventory entry refers to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RecordSelector<'a> {
+    /// A single record, by Cloudflare ID or name. Names match literally, so
+    /// `*.example.com` selects the wildcard record of that name, and a
+    /// concrete name never selects the wildcard record covering it.
+    Record(&'a str),
+    /// All records carrying a tag, e.g. `tag:ddns`. Tags with a value, such
+    /// as `ddns:home`, are also matched by their name.
+    Tag(&'a str),
+    /// All records whose comment contains a marker, e.g.
+    /// `comment:managed-by-cddns`.
+    Comment(&'a str),
+    /// All records matching a query, e.g. `query:type:A AND name~'^vpn\.'`.
+    Query(Query),
+}
+
+impl<'a> RecordSelector<'a> {
+    /// Parse an inventory record entry into a selector, which fails if a
+    /// query is invalid.
+    pub fn parse(entry: &'a str) -> Result<Self> {
+        Ok(if let Some(tag) = entry.strip_prefix(TAG_PREFIX) {
+            Self::Tag(tag)
+        } else if let Some(marker) = entry.strip_prefix(COMMENT_PREFIX) {
+            Self::Comment(marker)
+        } else if let Some(query) = entry.strip_prefix(QUERY_PREFIX) {
+            Self::Query(Query::parse(query)?)
+        } else {
+            Self::Record(entry)
+        })
+    }
+
+    /// Returns whether this selector may match more than one record.
+    pub fn is_multiple(&self) -> bool {
+        !matches!(self, Self::Record(_))
+    }
+
+    /// Returns whether a record is selected.
+    pub fn matches(&self, record: &Record) -> bool {
+        match self {
+            Self::Record(id) => {
+                record.id == *id || names::same(&record.name, id)
+            }
+            Self::Tag(tag) => record.has_tag(tag),
+            Self::Comment(marker) => record
+                .comment
+                .as_ref()
+                .is_some_and(|comment| comment.contains(marker)),
+            Self::Query(query) => query.matches(record),
+        }
+    }
+
+    /// Return the records selected within a zone, given by Cloudflare ID or
+    /// name.
+    pub fn select<'r>(
+        &self,
+        zone: &str,
+        records: &'r [Record],
+    ) -> Vec<&'r Record> {
+        let mut in_zone = records
+            .iter()
+            .filter(|r| r.zone_id == zone || names::same(&r.zone_name, zone))
+            .filter(|r| self.matches(r));
+        if self.is_multiple() {
+            in_zone.collect()
+        } else {
+            in_zone.next().into_iter().collect()
+        }
+    }
+
+    /// Return the wildcard record of a zone covering a name selected by this
+    /// selector, such as `*.example.com` for `vpn.example.com`. A name is
+    /// covered by the closest wildcard of its ancestors.
+    pub fn covering_wildcard<'r>(
+        &self,
+        zone: &str,
+        records: &'r [Record],
+    ) -> Option<&'r Record> {
+        let Self::Record(name) = self else {
+            return None;
+        };
+        let labels: Vec<&str> = name.split('.').collect();
+        (1..labels.len()).find_map(|i| {
+            let wildcard = format!("*.{}", labels[i..].join("."));
+            records.iter().find(|r| {
+                (r.zone_id == zone || names::same(&r.zone_name, zone))
+                    && names::same(&r.name, &wildcard)
+            })
+        })
+    }
+
+    /// Return the records selected within a zone, pairing each A or AAAA
+    /// record with the record of the other type sharing its name.
+    pub fn select_paired<'r>(
+        &self,
+        zone: &str,
+        records: &'r [Record],
+    ) -> Vec<&'r Record> {
+        let mut selected = self.select(zone, records);
+        let pairs: Vec<&'r Record> = selected
+            .iter()
+            .filter_map(|record| {
+                let pair = match record.record_type.as_str() {
+                    "A" => "AAAA",
+                    "AAAA" => "A",
+                    _ => return None,
+                };
+                records.iter().find(|r| {
+                    r.zone_id == record.zone_id
+                        && r.name == record.name
+                        && r.record_type == pair
+                })
+            })
+            .collect();
+        for pair in pairs {
+            if !selected.iter().any(|r| r.id == pair.id) {
+                selected.push(pair);
+            }
+        }
+        selected
+    }
+}
@@ -0,0 +1,209 @@
+use crate::error::{Error, Result};
+use crate::inventory::queue::QueuedUpdate;
+use crate::ip::uplink::Resolved;
+use crate::util;
+use crate::util::warnings::{self, Warning, WarningKind};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::warn;
+
+/// State maintained by `update` and `watch` between runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    /// When the state was last written.
+    pub updated: Option<DateTime<Utc>>,
+    /// The last observed public IPv4 address.
+    pub ipv4: Option<Ipv4Addr>,
+    /// The last observed public IPv6 address.
+    pub ipv6: Option<Ipv6Addr>,
+    /// When the public IP addresses were last observed.
+    pub observed: Option<DateTime<Utc>>,
+    /// The last observed public IP addresses of each uplink.
+    #[serde(default)]
+    pub uplinks: BTreeMap<String, Resolved>,
+    /// The last successful update of each record, per record ID.
+    #[serde(default)]
+    pub records: BTreeMap<String, RecordState>,
+    /// The alerts raised for records which are outdated or invalid, per
+    /// record name, so each is raised once until the record recovers.
+    #[serde(default)]
+    pub alerts: BTreeMap<String, Alert>,
+}
+
+/// The last successful update of a record.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordState {
+    pub zone_id: String,
+    pub name: String,
+    pub content: String,
+    pub updated: DateTime<Utc>,
+}
+
+impl RecordState {
+    /// Return the time elapsed since the update.
+    pub fn elapsed(&self) -> Duration {
+        (Utc::now() - self.updated).to_std().unwrap_or_default()
+    }
+}
+
+/// An alert raised for a record.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub since: DateTime<Utc>,
+}
+
+/// The problem of an alerted record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertKind {
+    /// The record remains outdated, such as after a failed update.
+    Outdated,
+    /// The inventory entry matches no Cloudflare record.
+    Invalid,
+}
+
+impl Display for AlertKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertKind::Outdated => write!(f, "outdated"),
+            AlertKind::Invalid => write!(f, "invalid"),
+        }
+    }
+}
+
+/// Return the default state path, depending on the host OS.
+///
+/// - Linux: $XDG_STATE_HOME/cddns/state.json or
+///   $HOME/.local/state/cddns/state.json
+/// - MacOS: $HOME/Library/Application Support/cddns/state.json
+/// - Windows: {FOLDERID_LocalAppData}/cddns/state.json
+/// - Else: None, state is unavailable
+pub fn default_state_path() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("state.json"))
+}
+
+/// Return the cddns state directory, if this OS has one.
+pub(crate) fn state_dir() -> Option<PathBuf> {
+    if let Some(base_dirs) = directories::BaseDirs::new() {
+        let mut state_dir = base_dirs
+            .state_dir()
+            .unwrap_or_else(|| base_dirs.data_local_dir())
+            .to_owned();
+        state_dir.push("cddns");
+        Some(state_dir)
+    } else {
+        warnings::emit(Warning::new(
+            WarningKind::Compatibility,
+            "state.default_path",
+            "no state directory found for this OS, state will not be saved",
+        ));
+        None
+    }
+}
+
+impl State {
+    /// Read the state, or the default state if none was saved.
+    pub async fn load() -> Result<Self> {
+        let Some(path) = default_state_path() else {
+            return Ok(Self::default());
+        };
+        match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|err| Error::parse("state", err)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            Err(err) => Err(Error::io(path, err)),
+        }
+    }
+
+    /// Record observed public IP addresses.
+    pub fn observe(&mut self, ipv4: Option<Ipv4Addr>, ipv6: Option<Ipv6Addr>) {
+        if ipv4.is_some() {
+            self.ipv4 = ipv4;
+        }
+        if ipv6.is_some() {
+            self.ipv6 = ipv6;
+        }
+        if ipv4.is_some() || ipv6.is_some() {
+            self.observed.replace(Utc::now());
+        }
+    }
+
+    /// Record the public IP addresses resolved for each uplink.
+    pub fn observe_resolved(
+        &mut self,
+        resolved: &BTreeMap<Option<String>, Resolved>,
+    ) {
+        for (uplink, ips) in resolved {
+            match uplink {
+                Some(name) => {
+                    let last = self.uplinks.entry(name.clone()).or_default();
+                    last.ipv4 = ips.ipv4.or(last.ipv4);
+                    last.ipv6 = ips.ipv6.or(last.ipv6);
+                    self.observed.replace(Utc::now());
+                }
+                None => self.observe(ips.ipv4, ips.ipv6),
+            }
+        }
+    }
+
+    /// Record a successful update.
+    pub fn record_update(&mut self, update: &QueuedUpdate) {
+        self.records.insert(
+            update.record_id.clone(),
+            RecordState {
+                zone_id: update.zone_id.clone(),
+                name: update.name.clone(),
+                content: update.content.clone(),
+                updated: Utc::now(),
+            },
+        );
+    }
+
+    /// Raise an alert for a record, returning it unless it was already
+    /// raised for the same problem.
+    pub fn alert(&mut self, name: &str, kind: AlertKind) -> Option<Alert> {
+        match self.alerts.get(name) {
+            Some(alert) if alert.kind == kind => None,
+            _ => {
+                let alert = Alert {
+                    kind,
+                    since: Utc::now(),
+                };
+                self.alerts.insert(name.to_string(), alert.clone());
+                Some(alert)
+            }
+        }
+    }
+
+    /// Clear the alert of a recovered record, returning it if one was
+    /// raised.
+    pub fn recover(&mut self, name: &str) -> Option<Alert> {
+        self.alerts.remove(name)
+    }
+
+    /// Modify and save the state. Failures are logged, as state is
+    /// best-effort.
+    pub async fn update(modify: impl FnOnce(&mut Self)) {
+        let result: Result<()> = try {
+            let path = default_state_path()
+                .ok_or_else(|| Error::NotFound("state directory".into()))?;
+            let mut state = Self::load().await.unwrap_or_default();
+            modify(&mut state);
+            state.updated.replace(Utc::now());
+            let contents = serde_json::to_string_pretty(&state)
+                .map_err(|err| Error::encode("state", err))?;
+            util::fs::save(path, contents).await?;
+        };
+        if let Err(err) = result {
+            warn!("unable to save state: {err}");
+        }
+    }
+}
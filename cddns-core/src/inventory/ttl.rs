@@ -0,0 +1,50 @@
+use crate::cloudflare::models::Record;
+use crate::util::warnings::{self, Warning, WarningKind};
+
+/// The TTL Cloudflare uses for "Auto".
+pub const AUTO: u32 = 1;
+
+/// The longest TTL suitable for dynamic DNS, in seconds.
+pub const MAX: u32 = 900;
+
+/// The TTL suggested for dynamic DNS records, in seconds.
+pub const SUGGESTED: u32 = 300;
+
+/// Return a warning if the TTL of a record undermines dynamic DNS, keeping
+/// stale addresses cached by resolvers after the public IP changes.
+pub fn lint(record: &Record) -> Option<Warning> {
+    // Proxied records always use "Auto", and their edge addresses are stable
+    if record.proxied {
+        return None;
+    }
+    let message = match record.ttl {
+        AUTO => format!(
+            "'{}' uses \"Auto\" TTL while unproxied, which leaves the TTL \
+            to Cloudflare",
+            record.name
+        ),
+        ttl if ttl > MAX => format!(
+            "'{}' has a TTL of {ttl} seconds, so resolvers may cache a stale \
+            address that long after the public IP changes",
+            record.name
+        ),
+        _ => return None,
+    };
+    Some(
+        Warning::new(
+            WarningKind::Record,
+            format!("record.ttl.{}", record.id),
+            message,
+        )
+        .with_help(format!(
+            "set an explicit TTL of {SUGGESTED} seconds or less"
+        )),
+    )
+}
+
+/// Surface a warning if the TTL of a record undermines dynamic DNS.
+pub fn check(record: &Record) {
+    if let Some(warning) = lint(record) {
+        warnings::emit(warning);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::error::{Error, Result};
+use crate::ip::Address;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time;
+
+/// The longest time to wait for a command to exit.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Resolve an IP address from the output of a shell command.
+///
+/// The command is run with `CDDNS_IP_VERSION` set to `4` or `6`, and the
+/// first line of its standard output which parses as an address of the
+/// requested family is used.
+pub async fn resolve<T: Address>(command: &str) -> Result<T> {
+    let version = if T::UNSPECIFIED.is_ipv6() { "6" } else { "4" };
+    let what = format!("address with command '{command}'");
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let output = time::timeout(
+        TIMEOUT,
+        shell
+            .arg(command)
+            .env("CDDNS_IP_VERSION", version)
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| Error::resolve(&what, "command timed out"))?
+    .map_err(|err| Error::resolve(&what, err))?;
+    if !output.status.success() {
+        return Err(Error::resolve(
+            &what,
+            format!(
+                "{}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.trim().parse().ok())
+        .ok_or_else(|| Error::NotFound(format!("IPv{version} {what}")))
+}
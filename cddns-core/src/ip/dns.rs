@@ -0,0 +1,359 @@
+use crate::error::{Error, Result};
+use crate::ip::Address;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time;
+use tracing::debug;
+
+/// The longest time to wait for a DNS response.
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The DNS record types queried for addresses.
+const TYPE_A: u16 = 1;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+
+/// The DNS classes queried for addresses.
+const CLASS_IN: u16 = 1;
+const CLASS_CH: u16 = 3;
+
+/// A DNS service which answers with the address of the client.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DnsProvider {
+    /// `myip.opendns.com` on OpenDNS resolvers.
+    Opendns,
+    /// `whoami.cloudflare` on Cloudflare resolvers.
+    Cloudflare,
+    /// `o-o.myaddr.l.google.com` on Google nameservers.
+    Google,
+}
+
+/// A DNS query.
+struct Query<'a> {
+    name: &'a str,
+    record_type: u16,
+    class: u16,
+    server: IpAddr,
+}
+
+impl DnsProvider {
+    /// The query resolving the client address of a family.
+    fn query(self, ipv6: bool) -> Query<'static> {
+        let (name, record_type, class, v4, v6) = match self {
+            DnsProvider::Opendns => (
+                "myip.opendns.com",
+                if ipv6 { TYPE_AAAA } else { TYPE_A },
+                CLASS_IN,
+                Ipv4Addr::new(208, 67, 222, 222),
+                Ipv6Addr::new(0x2620, 0, 0xccc, 0, 0, 0, 0, 2),
+            ),
+            DnsProvider::Cloudflare => (
+                "whoami.cloudflare",
+                TYPE_TXT,
+                CLASS_CH,
+                Ipv4Addr::new(1, 1, 1, 1),
+                Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111),
+            ),
+            DnsProvider::Google => (
+                "o-o.myaddr.l.google.com",
+                TYPE_TXT,
+                CLASS_IN,
+                Ipv4Addr::new(216, 239, 32, 10),
+                Ipv6Addr::new(0x2001, 0x4860, 0x4802, 0x32, 0, 0, 0, 0xa),
+            ),
+        };
+        Query {
+            name,
+            record_type,
+            class,
+            server: if ipv6 { v6.into() } else { v4.into() },
+        }
+    }
+}
+
+impl Display for DnsProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnsProvider::Opendns => write!(f, "opendns"),
+            DnsProvider::Cloudflare => write!(f, "cloudflare"),
+            DnsProvider::Google => write!(f, "google"),
+        }
+    }
+}
+
+impl FromStr for DnsProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+    }
+}
+
+/// Resolve an IP address by querying a DNS provider from a local address of
+/// the requested family.
+pub async fn resolve<T: Address>(
+    provider: DnsProvider,
+    local: IpAddr,
+) -> Result<T> {
+    let query = provider.query(T::UNSPECIFIED.is_ipv6());
+    let what = format!("address with {provider} DNS");
+    let answer = exchange(&query, local, &what)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::NotFound(what.clone()))?;
+    answer.parse().map_err(|err| {
+        Error::response(format!("'{answer}' from {provider} DNS"), err)
+    })
+}
+
+/// Look up the addresses of a family published for a name from a local
+/// address, by querying Cloudflare's public resolver of its family. The
+/// resolver caches answers, so a record changed recently may resolve to its
+/// previous content until its TTL expires.
+pub async fn lookup<T: Address>(name: &str, local: IpAddr) -> Result<Vec<T>> {
+    let query = Query {
+        name: name.trim_end_matches('.'),
+        record_type: if T::UNSPECIFIED.is_ipv6() {
+            TYPE_AAAA
+        } else {
+            TYPE_A
+        },
+        class: CLASS_IN,
+        server: match local {
+            IpAddr::V4(_) => Ipv4Addr::new(1, 1, 1, 1).into(),
+            IpAddr::V6(_) => {
+                Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111).into()
+            }
+        },
+    };
+    let what = format!("{} addresses of '{name}'", T::FAMILY);
+    exchange(&query, local, &what)
+        .await?
+        .into_iter()
+        .map(|answer| {
+            answer
+                .parse()
+                .map_err(|err| Error::response(format!("'{answer}'"), err))
+        })
+        .collect()
+}
+
+/// Send a query from a local address and return the answers of its server,
+/// as text. Responses to other queries, such as spoofed ones, are ignored.
+async fn exchange(
+    query: &Query<'_>,
+    local: IpAddr,
+    what: &str,
+) -> Result<Vec<String>> {
+    let id = rand::random();
+    let socket = UdpSocket::bind(SocketAddr::from((local, 0)))
+        .await
+        .map_err(|err| Error::resolve(what, err))?;
+    // Only receive datagrams from the server
+    socket
+        .connect((query.server, 53))
+        .await
+        .map_err(|err| Error::resolve(what, err))?;
+    socket
+        .send(&encode(query, id))
+        .await
+        .map_err(|err| Error::resolve(what, err))?;
+    let deadline = time::Instant::now() + TIMEOUT;
+    let mut response = [0u8; 512];
+    loop {
+        let len = time::timeout_at(deadline, socket.recv(&mut response))
+            .await
+            .map_err(|_| Error::resolve(what, "no response"))?
+            .map_err(|err| Error::resolve(what, err))?;
+        match __decode(&response[..len], query, id) {
+            Response::Answers(answers) => return Ok(answers),
+            Response::Unrelated => {
+                debug!("ignoring a DNS response to another query")
+            }
+            Response::Failed(reason) => {
+                return Err(Error::resolve(what, reason))
+            }
+        }
+    }
+}
+
+/// A DNS response, as decoded for a query.
+#[derive(Debug, PartialEq, Eq)]
+enum Response {
+    /// The answers of the queried type, as text.
+    Answers(Vec<String>),
+    /// A response to another query, or not a response at all.
+    Unrelated,
+    /// A response to the query which failed or can't be read.
+    Failed(&'static str),
+}
+
+/// Encode a DNS query message with an ID.
+fn encode(query: &Query, id: u16) -> Vec<u8> {
+    // ID, recursion desired, one question
+    let mut message = id.to_be_bytes().to_vec();
+    message.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in query.name.split('.') {
+        message.push(label.len() as u8);
+        message.extend_from_slice(label.as_bytes());
+    }
+    message.push(0);
+    message.extend_from_slice(&query.record_type.to_be_bytes());
+    message.extend_from_slice(&query.class.to_be_bytes());
+    message
+}
+
+/// Decode a DNS response to the query with an ID.
+fn __decode(message: &[u8], query: &Query, id: u16) -> Response {
+    let question = &encode(query, id)[12..];
+    let Some(header) = message.get(..12) else {
+        return Response::Unrelated;
+    };
+    let (flags, questions) =
+        (header[2], u16::from_be_bytes([header[4], header[5]]));
+    // The ID, the response bit and the question must match the query
+    if header[..2] != id.to_be_bytes()
+        || flags & 0x80 == 0
+        || questions != 1
+        || !message
+            .get(12..12 + question.len())
+            .is_some_and(|q| q.eq_ignore_ascii_case(question))
+    {
+        return Response::Unrelated;
+    }
+    if flags & 0x02 != 0 {
+        return Response::Failed("truncated response");
+    }
+    match header[3] & 0x0f {
+        0 => {}
+        3 => return Response::Failed("name does not exist"),
+        _ => return Response::Failed("server failure"),
+    }
+    match answers(message, 12 + question.len(), query.record_type) {
+        Some(answers) => Response::Answers(answers),
+        None => Response::Failed("malformed response"),
+    }
+}
+
+/// Decode the answers of a type following the question of a DNS response,
+/// or `None` if they are malformed.
+fn answers(
+    message: &[u8],
+    mut i: usize,
+    record_type: u16,
+) -> Option<Vec<String>> {
+    let u16_at = |i: usize| -> Option<u16> {
+        Some(u16::from_be_bytes([*message.get(i)?, *message.get(i + 1)?]))
+    };
+    // Skip a possibly compressed name, returning the following position
+    let skip_name = |mut i: usize| -> Option<usize> {
+        loop {
+            let len = *message.get(i)? as usize;
+            match len {
+                0 => return Some(i + 1),
+                l if l & 0xc0 == 0xc0 => return Some(i + 2),
+                l => i += l + 1,
+            }
+        }
+    };
+    let mut decoded = vec![];
+    for _ in 0..u16_at(6)? {
+        i = skip_name(i)?;
+        let (rtype, len) = (u16_at(i)?, u16_at(i + 8)? as usize);
+        let data = message.get(i + 10..i + 10 + len)?;
+        i += 10 + len;
+        if rtype != record_type {
+            continue;
+        }
+        decoded.push(match rtype {
+            TYPE_A => {
+                let octets: [u8; 4] = data.try_into().ok()?;
+                Ipv4Addr::from(octets).to_string()
+            }
+            TYPE_AAAA => {
+                let octets: [u8; 16] = data.try_into().ok()?;
+                Ipv6Addr::from(octets).to_string()
+            }
+            _ => {
+                // The first character-string of the TXT record
+                let len = *data.first()? as usize;
+                String::from_utf8(data.get(1..1 + len)?.to_vec()).ok()?
+            }
+        });
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUERY: Query = Query {
+        name: "a.example.com",
+        record_type: TYPE_A,
+        class: CLASS_IN,
+        server: IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)),
+    };
+
+    /// A response to the query with an ID, flags and an answer whose name
+    /// is compressed to point at the question.
+    fn response(id: u16, flags: [u8; 2]) -> Vec<u8> {
+        let mut message = encode(&QUERY, id);
+        message[2..4].copy_from_slice(&flags);
+        message[7] = 1;
+        message.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1, 0, 0, 1, 0, 0, 4]);
+        message.extend_from_slice(&[203, 0, 113, 7]);
+        message
+    }
+
+    #[test]
+    fn decodes_compressed_answers() {
+        assert_eq!(
+            __decode(&response(7, [0x81, 0x80]), &QUERY, 7),
+            Response::Answers(vec!["203.0.113.7".to_string()])
+        );
+    }
+
+    #[test]
+    fn ignores_responses_to_other_queries() {
+        let message = response(7, [0x81, 0x80]);
+        assert_eq!(__decode(&message, &QUERY, 8), Response::Unrelated);
+        let other = Query {
+            name: "b.example.com",
+            ..QUERY
+        };
+        assert_eq!(__decode(&message, &other, 7), Response::Unrelated);
+        // A query rather than a response
+        let message = response(7, [0x01, 0x80]);
+        assert_eq!(__decode(&message, &QUERY, 7), Response::Unrelated);
+        assert_eq!(__decode(&message[..8], &QUERY, 7), Response::Unrelated);
+    }
+
+    #[test]
+    fn rejects_failed_responses() {
+        let message = response(7, [0x83, 0x80]);
+        assert_eq!(
+            __decode(&message, &QUERY, 7),
+            Response::Failed("truncated response")
+        );
+        let message = response(7, [0x81, 0x83]);
+        assert_eq!(
+            __decode(&message, &QUERY, 7),
+            Response::Failed("name does not exist")
+        );
+        let message = response(7, [0x81, 0x80]);
+        assert_eq!(
+            __decode(&message[..message.len() - 2], &QUERY, 7),
+            Response::Failed("malformed response")
+        );
+    }
+}
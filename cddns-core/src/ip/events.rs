@@ -0,0 +1,70 @@
+use crate::error::{Error, Result};
+use futures::StreamExt;
+use if_watch::tokio::IfWatcher;
+use if_watch::IfEvent;
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::time;
+use tracing::{debug, warn};
+
+/// How long to wait for the network to settle after a change, so a burst
+/// of events triggers a single check.
+const SETTLE: Duration = Duration::from_secs(2);
+
+/// A subscription to network changes, via netlink on Linux, the System
+/// Configuration framework on macOS, and the IP Helper API on Windows.
+pub struct NetworkEvents {
+    watcher: IfWatcher,
+    known: HashSet<IpAddr>,
+}
+
+impl NetworkEvents {
+    /// Subscribe to changes of local addresses.
+    pub fn new() -> Result<Self> {
+        let watcher = IfWatcher::new()
+            .map_err(|err| Error::resolve("network change events", err))?;
+        // Existing addresses are reported as added when first polled
+        let known = local_ip_address::list_afinet_netifas()
+            .map(|interfaces| {
+                interfaces.into_iter().map(|(_, ip)| ip).collect()
+            })
+            .unwrap_or_default();
+        Ok(Self { watcher, known })
+    }
+
+    /// Wait until a local address is added or removed, and the network has
+    /// settled.
+    pub async fn changed(&mut self) {
+        loop {
+            if self.next_change().await {
+                break;
+            }
+        }
+        // Coalesce the events of a reconnect
+        while time::timeout(SETTLE, self.next_change()).await.is_ok() {}
+    }
+
+    /// Wait for the next event, returning whether it changed the addresses.
+    async fn next_change(&mut self) -> bool {
+        match self.watcher.next().await {
+            Some(Ok(IfEvent::Up(net))) => {
+                let changed = self.known.insert(net.addr());
+                if changed {
+                    debug!(address = %net.addr(), "local address added");
+                }
+                changed
+            }
+            Some(Ok(IfEvent::Down(net))) => {
+                debug!(address = %net.addr(), "local address removed");
+                self.known.remove(&net.addr());
+                true
+            }
+            Some(Err(err)) => {
+                warn!("unable to read network change events: {err}");
+                std::future::pending().await
+            }
+            None => std::future::pending().await,
+        }
+    }
+}
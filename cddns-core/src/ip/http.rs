@@ -0,0 +1,55 @@
+use crate::error::{Error, Result};
+use crate::ip::Address;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// The longest time to wait for an IP source to respond.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Resolve an IP address from a URL.
+///
+/// The response is read as plain text, unless the URL has a fragment naming
+/// a JSON field, such as `https://api.ipify.org?format=json#ip`. Nested
+/// fields are separated by dots, such as `#data.ip`. The request is sent
+/// from a local address of the requested family.
+pub async fn resolve<T: Address>(url: &str, local: IpAddr) -> Result<T> {
+    let (url, field) = match url.split_once('#') {
+        Some((url, field)) => (url, Some(field)),
+        None => (url, None),
+    };
+    let body = reqwest::Client::builder()
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .timeout(TIMEOUT)
+        .local_address(local)
+        .build()
+        .map_err(Error::Network)?
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(Error::Network)?
+        .text()
+        .await
+        .map_err(Error::Network)?;
+    let text = match field {
+        Some(field) => {
+            let json: serde_json::Value = serde_json::from_str(&body)
+                .map_err(|err| Error::response(format!("'{url}'"), err))?;
+            let pointer = format!("/{}", field.replace('.', "/"));
+            json.pointer(&pointer)
+                .and_then(|value| value.as_str())
+                .ok_or_else(|| {
+                    Error::NotFound(format!("field '{field}' of '{url}'"))
+                })?
+                .to_string()
+        }
+        None => body,
+    };
+    text.trim()
+        .parse()
+        .map_err(|err| Error::response(format!("IP address from '{url}'"), err))
+}
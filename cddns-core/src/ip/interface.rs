@@ -0,0 +1,138 @@
+use crate::error::{Error, Result};
+use crate::ip::{is_public, Address};
+use regex::Regex;
+use std::net::IpAddr;
+
+/// Return the addresses configured on a network interface.
+pub fn addresses(name: &str) -> Result<Vec<IpAddr>> {
+    let interfaces = local_ip_address::list_afinet_netifas()
+        .map_err(|err| Error::resolve("network interfaces", err))?;
+    let addresses: Vec<IpAddr> = interfaces
+        .into_iter()
+        .filter(|(interface, _)| interface == name)
+        .map(|(_, ip)| ip)
+        .collect();
+    if addresses.is_empty() {
+        return Err(Error::NotFound(format!("network interface '{name}'")));
+    }
+    Ok(addresses)
+}
+
+/// Return the first public address of a family configured on a network
+/// interface, preferring stable addresses over temporary ones and keeping
+/// only those matching a filter, if any.
+pub fn resolve<T: Address>(name: &str, filter: Option<&str>) -> Result<T> {
+    let filter = filter.map(AddressFilter::parse).transpose()?;
+    let mut candidates: Vec<IpAddr> = addresses(name)?
+        .into_iter()
+        .filter(|ip| is_public(*ip))
+        .filter(|ip| filter.as_ref().is_none_or(|f| f.matches(*ip)))
+        .collect();
+    candidates.sort_by_key(|ip| is_temporary(*ip));
+    candidates.into_iter().find_map(T::from_ip).ok_or_else(|| {
+        Error::NotFound(format!("public {} address on '{name}'", T::FAMILY))
+    })
+}
+
+/// Return the first address of a family configured on a network interface
+/// which requests can be sent from, excluding loopback and link-local
+/// addresses.
+pub fn local<T: Address>(name: &str) -> Result<T> {
+    addresses(name)?
+        .into_iter()
+        .filter(|ip| !ip.is_loopback() && !is_link_local(*ip))
+        .find_map(T::from_ip)
+        .ok_or_else(|| {
+            Error::NotFound(format!("{} address on '{name}'", T::FAMILY))
+        })
+}
+
+/// Returns whether an address is link-local.
+fn is_link_local(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_link_local(),
+        IpAddr::V6(ip) => ip.segments()[0] & 0xffc0 == 0xfe80,
+    }
+}
+
+/// A filter selecting addresses by prefix, such as `2001:db8:1::/48`, or
+/// else by regular expression.
+pub enum AddressFilter {
+    Prefix(IpAddr, u8),
+    Pattern(Regex),
+}
+
+impl AddressFilter {
+    /// Parse a filter from a prefix in CIDR notation, or a regular
+    /// expression.
+    pub fn parse(filter: &str) -> Result<Self> {
+        if let Some((ip, len)) = filter.split_once('/') {
+            if let (Ok(ip), Ok(len)) = (ip.parse::<IpAddr>(), len.parse()) {
+                let max = if ip.is_ipv4() { 32 } else { 128 };
+                if len > max {
+                    return Err(Error::Config(format!(
+                        "prefix length of '{filter}' is greater than {max}"
+                    )));
+                }
+                return Ok(Self::Prefix(ip, len));
+            }
+        }
+        Regex::new(filter).map(Self::Pattern).map_err(|err| {
+            Error::parse(format!("address filter '{filter}'"), err)
+        })
+    }
+
+    /// Returns whether an address matches the filter.
+    pub fn matches(&self, ip: IpAddr) -> bool {
+        match self {
+            Self::Prefix(IpAddr::V4(prefix), len) => match ip {
+                IpAddr::V4(ip) => {
+                    let mask =
+                        u32::MAX.checked_shl(32 - *len as u32).unwrap_or(0);
+                    u32::from(ip) & mask == u32::from(*prefix) & mask
+                }
+                IpAddr::V6(_) => false,
+            },
+            Self::Prefix(IpAddr::V6(prefix), len) => match ip {
+                IpAddr::V6(ip) => {
+                    let mask =
+                        u128::MAX.checked_shl(128 - *len as u32).unwrap_or(0);
+                    u128::from(ip) & mask == u128::from(*prefix) & mask
+                }
+                IpAddr::V4(_) => false,
+            },
+            Self::Pattern(pattern) => pattern.is_match(&ip.to_string()),
+        }
+    }
+}
+
+/// Returns whether an IPv6 address is temporary or deprecated, such as those
+/// of privacy extensions, from the kernel address table.
+#[cfg(target_os = "linux")]
+fn is_temporary(ip: IpAddr) -> bool {
+    // IFA_F_TEMPORARY and IFA_F_DEPRECATED
+    const FLAGS: u8 = 0x01 | 0x20;
+    let IpAddr::V6(ip) = ip else {
+        return false;
+    };
+    let Ok(table) = std::fs::read_to_string("/proc/net/if_inet6") else {
+        return false;
+    };
+    let address = format!("{:032x}", u128::from(ip));
+    table.lines().any(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [a, _, _, _, flags, ..] if *a == address => {
+                u8::from_str_radix(flags, 16).is_ok_and(|f| f & FLAGS != 0)
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Returns whether an IPv6 address is temporary, which is unknown on this
+/// OS.
+#[cfg(not(target_os = "linux"))]
+fn is_temporary(_ip: IpAddr) -> bool {
+    false
+}
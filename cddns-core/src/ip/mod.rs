@@ -0,0 +1,438 @@
+//! Public IP address resolution.
+//!
+//! Public IP addresses are resolved by trying an ordered chain of sources,
+//! configured by `ip.sources`. By default, the chain is every configured
+//! source in the order of [`IpSource`], or else the built-in sources of the
+//! `public-ip` crate. With `ip.consensus`, several sources must agree on an
+//! address before it is trusted.
+
+use crate::config::models::ConfigOptsIp;
+use crate::error::{Error, Result};
+use crate::plugin::{self, PluginKind};
+use crate::util::progress;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::net::{AddrParseError, IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+use tracing::{debug, info, trace, warn};
+
+pub mod command;
+pub mod dns;
+pub mod events;
+pub mod http;
+pub mod interface;
+pub mod prefix;
+pub mod router;
+pub mod uplink;
+
+/// A source of public IP addresses.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum IpSource {
+    /// The output of `ip.command`.
+    Command,
+    /// The IP source plugins of `ip.plugins`, or discovered in the plugins
+    /// directory.
+    Plugin,
+    /// An address configured on `ip.interface`.
+    Interface,
+    /// The router, via UPnP IGD or NAT-PMP (IPv4 only).
+    Router,
+    /// The DNS provider of `ip.dns`.
+    Dns,
+    /// The URLs of `ip.ipv4_urls` and `ip.ipv6_urls`.
+    Http,
+    /// The built-in sources of the `public-ip` crate.
+    Builtin,
+}
+
+impl Display for IpSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpSource::Command => write!(f, "command"),
+            IpSource::Plugin => write!(f, "plugin"),
+            IpSource::Interface => write!(f, "interface"),
+            IpSource::Router => write!(f, "router"),
+            IpSource::Dns => write!(f, "dns"),
+            IpSource::Http => write!(f, "http"),
+            IpSource::Builtin => write!(f, "builtin"),
+        }
+    }
+}
+
+impl FromStr for IpSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+    }
+}
+
+/// An IP address family.
+pub trait Address:
+    FromStr<Err = AddrParseError> + Display + Copy + PartialEq + Into<IpAddr>
+{
+    /// The name of the family.
+    const FAMILY: &'static str;
+    /// The unspecified address of the family, binding requests to it.
+    const UNSPECIFIED: IpAddr;
+
+    /// Return the address if it belongs to the family.
+    fn from_ip(ip: IpAddr) -> Option<Self>;
+
+    /// Return the configured URLs resolving addresses of the family.
+    fn urls(opts: &ConfigOptsIp) -> Option<&Vec<String>>;
+
+    /// Return the address to publish for this host, given a resolved address.
+    fn host(self, _opts: &ConfigOptsIp) -> Result<Self> {
+        Ok(self)
+    }
+}
+
+impl Address for Ipv4Addr {
+    const FAMILY: &'static str = "IPv4";
+    const UNSPECIFIED: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+    fn from_ip(ip: IpAddr) -> Option<Self> {
+        match ip {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(_) => None,
+        }
+    }
+
+    fn urls(opts: &ConfigOptsIp) -> Option<&Vec<String>> {
+        opts.ipv4_urls.as_ref()
+    }
+}
+
+impl Address for Ipv6Addr {
+    const FAMILY: &'static str = "IPv6";
+    const UNSPECIFIED: IpAddr = IpAddr::V6(Ipv6Addr::UNSPECIFIED);
+
+    fn from_ip(ip: IpAddr) -> Option<Self> {
+        match ip {
+            IpAddr::V4(_) => None,
+            IpAddr::V6(ip) => Some(ip),
+        }
+    }
+
+    fn urls(opts: &ConfigOptsIp) -> Option<&Vec<String>> {
+        opts.ipv6_urls.as_ref()
+    }
+
+    fn host(self, opts: &ConfigOptsIp) -> Result<Self> {
+        match opts.ipv6_suffix {
+            Some(ref suffix) => prefix::combine(
+                self,
+                opts.ipv6_prefix_len.unwrap_or(64),
+                suffix,
+            ),
+            None => Ok(self),
+        }
+    }
+}
+
+/// Resolve the public IPv4 address.
+pub async fn ipv4(opts: &ConfigOptsIp) -> Result<Ipv4Addr> {
+    resolve(opts).await.map(|(ip, _)| ip)
+}
+
+/// Resolve the public IPv6 address.
+pub async fn ipv6(opts: &ConfigOptsIp) -> Result<Ipv6Addr> {
+    resolve(opts).await.map(|(ip, _)| ip)
+}
+
+/// Resolve a public IP address with the first successful source of the
+/// chain, returning the address and its source.
+///
+/// If `ip.consensus` is greater than 1, sources are tried until that many
+/// resolve the same address, returning the source which settled it.
+pub async fn resolve<T: Address>(opts: &ConfigOptsIp) -> Result<(T, IpSource)> {
+    trace!("resolving {}...", T::FAMILY);
+    let consensus = opts.consensus.unwrap_or(1).max(1) as usize;
+    let mut readings: Vec<(T, IpSource)> = vec![];
+    for source in chain(opts) {
+        match resolve_with::<T>(source, opts).await {
+            Ok(Some(ip)) => {
+                info!(
+                    target: progress::TARGET,
+                    %source,
+                    %ip,
+                    "resolved public {}",
+                    T::FAMILY
+                );
+                if readings.iter().any(|(r, _)| *r != ip) {
+                    warn!(%source, %ip, "IP sources disagree on public {}", T::FAMILY);
+                }
+                readings.push((ip, source));
+                let agreeing =
+                    readings.iter().filter(|(r, _)| *r == ip).count();
+                if agreeing >= consensus {
+                    let host = ip.host(opts)?;
+                    if host != ip {
+                        debug!(%ip, %host, "combined prefix with host suffix");
+                    }
+                    return routable(opts, host).map(|ip| (ip, source));
+                }
+            }
+            Ok(None) => trace!(%source, "source is not configured"),
+            Err(err) => {
+                warn!(%source, "unable to resolve public {}: {err}", T::FAMILY)
+            }
+        }
+    }
+    if readings.is_empty() {
+        return Err(Error::NotFound(format!("public {} address", T::FAMILY)));
+    }
+    let readings = readings
+        .iter()
+        .map(|(ip, source)| format!("{ip} ({source})"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(Error::resolve(
+        format!("public {} address", T::FAMILY),
+        format!("fewer than {consensus} sources agree: {readings}"),
+    ))
+}
+
+/// Refuse an address which is not publicly routable, such as a private or
+/// CGNAT address, unless `ip.allow_private` is set.
+fn routable<T: Address>(opts: &ConfigOptsIp, ip: T) -> Result<T> {
+    if is_public(ip.into()) {
+        return Ok(ip);
+    }
+    if opts.allow_private == Some(true) {
+        warn!(%ip, "public {} is not publicly routable", T::FAMILY);
+        return Ok(ip);
+    }
+    warn!(
+        %ip,
+        "public {} is a private or CGNAT address, which breaks inbound \
+        connectivity. Set `ip.allow_private` to publish it anyway",
+        T::FAMILY
+    );
+    Err(Error::resolve(
+        format!("public {} address", T::FAMILY),
+        format!("{ip} is not publicly routable"),
+    ))
+}
+
+/// Re-resolve a public IP address, returning whether it still matches the
+/// address given, if any.
+pub async fn matches<T: Address>(opts: &ConfigOptsIp, ip: Option<T>) -> bool {
+    let Some(ip) = ip else {
+        return true;
+    };
+    match resolve::<T>(opts).await {
+        Ok((confirmed, _)) if confirmed == ip => true,
+        Ok((confirmed, _)) => {
+            warn!(%ip, %confirmed, "public {} changed", T::FAMILY);
+            false
+        }
+        Err(err) => {
+            warn!("unable to confirm public {}: {err}", T::FAMILY);
+            false
+        }
+    }
+}
+
+/// Return the local address of a family which IP detection is sent from,
+/// per `ip.bind`, or else the unspecified address.
+pub fn local_address<T: Address>(opts: &ConfigOptsIp) -> Result<IpAddr> {
+    let Some(ref bind) = opts.bind else {
+        return Ok(T::UNSPECIFIED);
+    };
+    match bind.parse::<IpAddr>() {
+        Ok(ip) => T::from_ip(ip).map(Into::into).ok_or_else(|| {
+            Error::Config(format!(
+                "bind address {ip} cannot resolve public {} addresses",
+                T::FAMILY
+            ))
+        }),
+        Err(_) => interface::local::<T>(bind).map(Into::into),
+    }
+}
+
+/// Return the local address Cloudflare requests are sent from, per
+/// `ip.bind`, preferring IPv4 addresses of an interface.
+pub fn bind_address(opts: &ConfigOptsIp) -> Result<Option<IpAddr>> {
+    let Some(ref bind) = opts.bind else {
+        return Ok(None);
+    };
+    if let Ok(ip) = bind.parse::<IpAddr>() {
+        return Ok(Some(ip));
+    }
+    interface::local::<Ipv4Addr>(bind)
+        .map(IpAddr::V4)
+        .or_else(|_| interface::local::<Ipv6Addr>(bind).map(IpAddr::V6))
+        .map(Some)
+}
+
+/// Return the chain of sources, in order.
+pub fn chain(opts: &ConfigOptsIp) -> Vec<IpSource> {
+    match opts.sources {
+        Some(ref sources) if !sources.is_empty() => sources.clone(),
+        _ => {
+            let configured: Vec<IpSource> = [
+                (IpSource::Command, opts.command.is_some()),
+                (
+                    IpSource::Plugin,
+                    !plugin::discover(PluginKind::Ip, opts.plugins.as_ref())
+                        .is_empty(),
+                ),
+                (IpSource::Interface, opts.interface.is_some()),
+                (IpSource::Router, opts.router == Some(true)),
+                (IpSource::Dns, opts.dns.is_some()),
+                (
+                    IpSource::Http,
+                    opts.ipv4_urls.is_some() || opts.ipv6_urls.is_some(),
+                ),
+            ]
+            .into_iter()
+            .filter_map(|(source, configured)| configured.then_some(source))
+            .collect();
+            if configured.is_empty() {
+                vec![IpSource::Builtin]
+            } else {
+                configured
+            }
+        }
+    }
+}
+
+/// Resolve a public IP address with a source, or `None` if the source is
+/// not configured for the family.
+async fn resolve_with<T: Address>(
+    source: IpSource,
+    opts: &ConfigOptsIp,
+) -> Result<Option<T>> {
+    match source {
+        IpSource::Command => match opts.command {
+            Some(ref command) => command::resolve(command).await.map(Some),
+            None => Ok(None),
+        },
+        IpSource::Plugin => {
+            let plugins =
+                plugin::discover(PluginKind::Ip, opts.plugins.as_ref());
+            let mut last = None;
+            for path in plugins.iter() {
+                match plugin::resolve(path).await {
+                    Ok(ip) => return Ok(Some(ip)),
+                    Err(err) => {
+                        debug!("{err}");
+                        last = Some(err);
+                    }
+                }
+            }
+            last.map_or(Ok(None), Err)
+        }
+        IpSource::Interface => match opts.interface {
+            Some(ref name) => {
+                let filter = opts
+                    .ipv6_filter
+                    .as_deref()
+                    .filter(|_| T::UNSPECIFIED.is_ipv6());
+                interface::resolve(name, filter).map(Some)
+            }
+            None => Ok(None),
+        },
+        IpSource::Router if T::UNSPECIFIED.is_ipv4() => {
+            let local = local_address::<T>(opts)?;
+            router::ipv4(local)
+                .await
+                .map(|ip| T::from_ip(IpAddr::V4(ip)))
+        }
+        IpSource::Router => Ok(None),
+        IpSource::Dns => match opts.dns {
+            Some(provider) => {
+                let local = local_address::<T>(opts)?;
+                dns::resolve(provider, local).await.map(Some)
+            }
+            None => Ok(None),
+        },
+        IpSource::Http => match T::urls(opts) {
+            Some(urls) if !urls.is_empty() => {
+                let local = local_address::<T>(opts)?;
+                first_of(urls, local).await.map(Some)
+            }
+            _ => Ok(None),
+        },
+        IpSource::Builtin => {
+            let version = match T::UNSPECIFIED {
+                IpAddr::V4(_) => public_ip::Version::V4,
+                IpAddr::V6(_) => public_ip::Version::V6,
+            };
+            public_ip::addr_with(public_ip::ALL, version)
+                .await
+                .and_then(T::from_ip)
+                .map(Some)
+                .ok_or_else(|| {
+                    Error::NotFound(format!(
+                        "public {} address with built-in sources",
+                        T::FAMILY
+                    ))
+                })
+        }
+    }
+}
+
+/// Return the first address resolved by a list of URLs.
+async fn first_of<T: Address>(urls: &[String], local: IpAddr) -> Result<T> {
+    for url in urls {
+        match http::resolve(url, local).await {
+            Ok(ip) => return Ok(ip),
+            Err(err) => {
+                debug!(url, "unable to resolve public {}: {err}", T::FAMILY)
+            }
+        }
+    }
+    Err(Error::NotFound(format!("public {} address", T::FAMILY)))
+}
+
+/// Returns whether an address is publicly routable, excluding loopback,
+/// private, shared (CGNAT), link-local, and unique local addresses.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let shared = ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64;
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || shared)
+        }
+        IpAddr::V6(ip) => {
+            let segment = ip.segments()[0];
+            let unique_local = segment & 0xfe00 == 0xfc00;
+            let link_local = segment & 0xffc0 == 0xfe80;
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || unique_local
+                || link_local)
+        }
+    }
+}
+
+/// Returns whether an address should never be published, being
+/// non-public, multicast, broadcast, or reserved for documentation, as when
+/// a bad IP source poisoned a record.
+pub fn is_bogon(ip: IpAddr) -> bool {
+    if !is_public(ip) || ip.is_multicast() {
+        return true;
+    }
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            ip.is_broadcast()
+                || a == 0
+                || matches!(
+                    (a, b, c),
+                    (192, 0, 2) | (198, 51, 100) | (203, 0, 113)
+                )
+        }
+        IpAddr::V6(ip) => ip.segments()[..2] == [0x2001, 0xdb8],
+    }
+}
@@ -0,0 +1,56 @@
+use crate::error::{Error, Result};
+use std::net::Ipv6Addr;
+
+/// Combine the network prefix of an address with a host suffix, returning a
+/// stable address for the host within a delegated prefix.
+///
+/// The suffix is an interface identifier in IPv6 notation, such as
+/// `::1234:5678:9abc:def0`, or a MAC address, such as `00:11:22:33:44:55`,
+/// which is expanded to a modified EUI-64 interface identifier.
+pub fn combine(
+    address: Ipv6Addr,
+    prefix_len: u8,
+    suffix: &str,
+) -> Result<Ipv6Addr> {
+    if prefix_len > 128 {
+        return Err(Error::Config(format!(
+            "IPv6 prefix length {prefix_len} is greater than 128"
+        )));
+    }
+    let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+    let suffix = interface_id(suffix)?;
+    Ok(Ipv6Addr::from(
+        (u128::from(address) & mask) | (suffix & !mask),
+    ))
+}
+
+/// Parse a host suffix into an interface identifier.
+fn interface_id(suffix: &str) -> Result<u128> {
+    if let Ok(id) = suffix.parse::<Ipv6Addr>() {
+        return Ok(u128::from(id));
+    }
+    let octets = suffix
+        .split([':', '-'])
+        .map(|octet| u8::from_str_radix(octet, 16))
+        .collect::<std::result::Result<Vec<u8>, _>>()
+        .ok()
+        .and_then(|octets| <[u8; 6]>::try_from(octets).ok())
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "IPv6 suffix '{suffix}' is not an IPv6 interface identifier \
+                or MAC address"
+            ))
+        })?;
+    // Modified EUI-64: insert ff:fe and flip the universal/local bit
+    let eui64 = [
+        octets[0] ^ 0x02,
+        octets[1],
+        octets[2],
+        0xff,
+        0xfe,
+        octets[3],
+        octets[4],
+        octets[5],
+    ];
+    Ok(u64::from_be_bytes(eui64) as u128)
+}
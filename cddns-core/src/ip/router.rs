@@ -0,0 +1,108 @@
+use crate::error::{Error, Result};
+use igd_next::SearchOptions;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time;
+use tracing::debug;
+
+/// The longest time to wait for the router to respond.
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The port of NAT-PMP servers.
+const NAT_PMP_PORT: u16 = 5351;
+
+/// Query the router for its external IPv4 address, via UPnP IGD or else
+/// NAT-PMP, from a local address.
+pub async fn ipv4(local: IpAddr) -> Result<Ipv4Addr> {
+    match upnp(local).await {
+        Ok(ip) => return Ok(ip),
+        Err(err) => debug!("unable to query the router via UPnP: {err}"),
+    }
+    nat_pmp(local).await
+}
+
+/// Query the external IPv4 address of the router via UPnP IGD.
+pub async fn upnp(local: IpAddr) -> Result<Ipv4Addr> {
+    let gateway = igd_next::aio::tokio::search_gateway(SearchOptions {
+        bind_addr: SocketAddr::from((local, 0)),
+        timeout: Some(TIMEOUT),
+        ..Default::default()
+    })
+    .await
+    .map_err(|err| Error::resolve("UPnP gateway", err))?;
+    debug!(gateway = %gateway.addr, "found UPnP gateway");
+    match gateway.get_external_ip().await {
+        Ok(IpAddr::V4(ip)) => Ok(ip),
+        Ok(IpAddr::V6(_)) => Err(Error::NotFound(
+            "external IPv4 address of the UPnP gateway".into(),
+        )),
+        Err(err) => Err(Error::resolve("UPnP external address", err)),
+    }
+}
+
+/// Query the external IPv4 address of the default gateway via NAT-PMP.
+pub async fn nat_pmp(local: IpAddr) -> Result<Ipv4Addr> {
+    let gateway = default_gateway()?;
+    let socket = UdpSocket::bind((local, 0))
+        .await
+        .map_err(|err| Error::resolve("NAT-PMP address", err))?;
+    // Only receive datagrams from the gateway, which others on the network
+    // could otherwise answer for
+    socket
+        .connect((gateway, NAT_PMP_PORT))
+        .await
+        .map_err(|err| Error::resolve("NAT-PMP address", err))?;
+    // Version 0, opcode 0: external address request
+    socket
+        .send(&[0, 0])
+        .await
+        .map_err(|err| Error::resolve("NAT-PMP address", err))?;
+    let mut response = [0u8; 12];
+    let len = time::timeout(TIMEOUT, socket.recv(&mut response))
+        .await
+        .map_err(|_| Error::resolve("NAT-PMP address", "no response"))?
+        .map_err(|err| Error::resolve("NAT-PMP address", err))?;
+    // Version 0, opcode 128, result code 0 (success)
+    if len < 12 || response[..4] != [0, 128, 0, 0] {
+        return Err(Error::NotFound(format!(
+            "NAT-PMP external address from {gateway}"
+        )));
+    }
+    Ok(Ipv4Addr::new(
+        response[8],
+        response[9],
+        response[10],
+        response[11],
+    ))
+}
+
+/// Return the IPv4 default gateway, from the kernel routing table.
+#[cfg(target_os = "linux")]
+fn default_gateway() -> Result<Ipv4Addr> {
+    let path = "/proc/net/route";
+    let routes =
+        std::fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+    routes
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [_, "00000000", gateway, ..] => {
+                    u32::from_str_radix(gateway, 16).ok()
+                }
+                _ => None,
+            }
+        })
+        .find(|gateway| *gateway != 0)
+        // The address is printed as a number in host byte order
+        .map(|gateway| Ipv4Addr::from(gateway.to_ne_bytes()))
+        .ok_or_else(|| Error::NotFound("default gateway".into()))
+}
+
+/// Return the IPv4 default gateway, which is unsupported on this OS.
+#[cfg(not(target_os = "linux"))]
+fn default_gateway() -> Result<Ipv4Addr> {
+    Err(Error::NotFound("default gateway on this OS".into()))
+}
@@ -0,0 +1,162 @@
+use crate::cloudflare::models::Record;
+use crate::config::models::ConfigOptsIp;
+use crate::error::{Error, Result};
+use crate::inventory::selector::RecordSelector;
+use crate::ip;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+use tracing::debug;
+
+/// The public IP addresses resolved for an uplink.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct Resolved {
+    pub ipv4: Option<Ipv4Addr>,
+    pub ipv6: Option<Ipv6Addr>,
+}
+
+/// Resolves the public IP addresses records are published with, once per
+/// uplink. Records not assigned to an uplink use the `ip` section.
+pub struct Resolver<'a> {
+    opts: &'a ConfigOptsIp,
+    fixed: Resolved,
+    resolved: BTreeMap<Option<String>, Resolved>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Create a resolver from IP options.
+    pub fn new(opts: &'a ConfigOptsIp) -> Self {
+        Self {
+            opts,
+            fixed: Resolved::default(),
+            resolved: BTreeMap::new(),
+        }
+    }
+
+    /// Use fixed addresses for all records instead of resolving them, such
+    /// as those given on the command line.
+    pub fn fixed(mut self, fixed: Resolved) -> Self {
+        self.fixed = fixed;
+        self
+    }
+
+    /// Return the uplink a record is published with, if any.
+    pub fn uplink(&self, record: &Record) -> Option<&'a str> {
+        self.opts
+            .uplinks
+            .as_ref()?
+            .iter()
+            .find_map(|(name, uplink)| {
+                uplink
+                    .records
+                    .iter()
+                    .any(|entry| {
+                        RecordSelector::parse(entry)
+                            .is_ok_and(|selector| selector.matches(record))
+                    })
+                    .then_some(name.as_str())
+            })
+    }
+
+    /// Return the uplink a record name is published with, or `None` if it
+    /// is unknown without listing records, because an uplink selects
+    /// records by ID, tag, comment or query.
+    pub fn uplink_of(&self, name: &str) -> Option<Option<&'a str>> {
+        let Some(uplinks) = self.opts.uplinks.as_ref() else {
+            return Some(None);
+        };
+        for (uplink, opts) in uplinks.iter() {
+            for entry in opts.records.iter() {
+                match RecordSelector::parse(entry) {
+                    Ok(RecordSelector::Record(n)) if n == name => {
+                        return Some(Some(uplink.as_str()))
+                    }
+                    Ok(RecordSelector::Record(n)) if n.contains('.') => {}
+                    _ => return None,
+                }
+            }
+        }
+        Some(None)
+    }
+
+    /// Return the IP options of an uplink, or of the `ip` section.
+    pub fn opts(&self, uplink: Option<&str>) -> Result<ConfigOptsIp> {
+        match uplink {
+            Some(name) => self
+                .opts
+                .uplink(name)
+                .ok_or_else(|| Error::NotFound(format!("uplink '{name}'"))),
+            None => Ok(self.opts.clone()),
+        }
+    }
+
+    /// Resolve the public IPv4 address a record is published with.
+    pub async fn ipv4(&mut self, record: &Record) -> Result<Ipv4Addr> {
+        self.ipv4_on(self.uplink(record)).await
+    }
+
+    /// Resolve the public IPv4 address of an uplink, or of the `ip` section.
+    pub async fn ipv4_on(&mut self, uplink: Option<&str>) -> Result<Ipv4Addr> {
+        if let Some(ip) = self.fixed.ipv4 {
+            return Ok(ip);
+        }
+        let key = uplink.map(str::to_string);
+        if let Some(ip) = self.resolved.get(&key).and_then(|r| r.ipv4) {
+            return Ok(ip);
+        }
+        let ip = ip::ipv4(&self.opts(uplink)?).await?;
+        self.resolved.entry(key).or_default().ipv4.replace(ip);
+        Ok(ip)
+    }
+
+    /// Resolve the public IPv6 address a record is published with.
+    pub async fn ipv6(&mut self, record: &Record) -> Result<Ipv6Addr> {
+        self.ipv6_on(self.uplink(record)).await
+    }
+
+    /// Resolve the public IPv6 address of an uplink, or of the `ip` section.
+    pub async fn ipv6_on(&mut self, uplink: Option<&str>) -> Result<Ipv6Addr> {
+        if let Some(ip) = self.fixed.ipv6 {
+            return Ok(ip);
+        }
+        let key = uplink.map(str::to_string);
+        if let Some(ip) = self.resolved.get(&key).and_then(|r| r.ipv6) {
+            return Ok(ip);
+        }
+        let ip = ip::ipv6(&self.opts(uplink)?).await?;
+        self.resolved.entry(key).or_default().ipv6.replace(ip);
+        Ok(ip)
+    }
+
+    /// Return the addresses resolved so far, per uplink, excluding fixed
+    /// addresses.
+    pub fn resolved(&self) -> &BTreeMap<Option<String>, Resolved> {
+        &self.resolved
+    }
+
+    /// Re-resolve the addresses resolved so far after `ip.confirm_delay`,
+    /// returning whether they all still match, to avoid publishing a
+    /// transient address seen during a reconnect.
+    pub async fn confirm(&self) -> bool {
+        let delay = self.opts.confirm_delay.unwrap_or(0);
+        if delay == 0 || self.resolved.is_empty() {
+            return true;
+        }
+        debug!(delay, "confirming public IPs...");
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+        for (uplink, resolved) in self.resolved.iter() {
+            let Ok(opts) = self.opts(uplink.as_deref()) else {
+                return false;
+            };
+            if !(ip::matches(&opts, resolved.ipv4).await
+                && ip::matches(&opts, resolved.ipv6).await)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,38 @@
+//! The reusable logic behind cddns, a Cloudflare DDNS command line utility.
+//!
+//! This crate never prompts for input or exits the process, so it can be
+//! embedded in other programs. It is organized into:
+//! - [`cloudflare`]: the Cloudflare API gateway, with retries, caching and a
+//!   mock provider.
+//! - [`config`]: layered configuration options and their builder.
+//! - [`inventory`]: inventory files, selectors and the record update queue.
+//! - [`ip`]: public IP address resolution.
+//! - [`plugin`]: external executables extending IP sources and notifiers.
+//!
+//! Below is an example of listing every record the token can edit:
+//! ```no_run
+//! use cddns_core::cloudflare::endpoints;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let token = "<YOUR_CLOUDFLARE_TOKEN>";
+//! let zones = endpoints::zones(token).await?;
+//! for record in endpoints::records(&zones, token).await? {
+//!     println!("{record}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+// Clippy
+#![deny(clippy::unwrap_used)] // use context/with_context
+#![deny(clippy::expect_used)] // use context/with_context
+// Features
+#![feature(try_blocks)]
+
+pub mod cloudflare;
+pub mod config;
+pub mod error;
+pub mod inventory;
+pub mod ip;
+pub mod plugin;
+pub mod util;
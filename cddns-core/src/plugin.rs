@@ -0,0 +1,197 @@
+//! External executable plugins, extending cddns without forking it.
+//!
+//! A plugin is an executable which is sent a single JSON request on stdin,
+//! and answers with a JSON response on stdout before exiting successfully.
+//! Plugins are listed in the config, or discovered in the `plugins`
+//! directory next to the default config file, by kind:
+//! - IP sources (`ip.plugins`, or `plugins/ip/`) are sent
+//!   `{"kind":"ip","version":4}` and answer `{"ip":"203.0.113.7"}`. They are
+//!   tried in order by the `plugin` source.
+//! - Notifiers (`inventory.notifiers`, or `plugins/notify/`) are sent
+//!   `{"kind":"notify","event":"outdated","name":"vpn.example.com",
+//!   "since":"2024-01-01T00:00:00Z"}` when a record becomes `outdated` or
+//!   `invalid`, or has `recovered`. Their output is ignored.
+//!
+//! Configured plugins are tried before discovered ones, which are tried in
+//! file name order.
+
+use crate::error::{Error, Result};
+use crate::ip::Address;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time;
+use tracing::{debug, warn};
+
+/// The longest time to wait for a plugin to exit.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The kind of a plugin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PluginKind {
+    /// A source of public IP addresses.
+    Ip,
+    /// A notifier of record alerts.
+    Notify,
+}
+
+impl PluginKind {
+    /// The name of the directory plugins of this kind are discovered in.
+    fn dir_name(self) -> &'static str {
+        match self {
+            PluginKind::Ip => "ip",
+            PluginKind::Notify => "notify",
+        }
+    }
+}
+
+/// A request sent to a plugin.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Request<'a> {
+    Ip { version: u8 },
+    Notify(&'a Notification),
+}
+
+/// The response of an IP source plugin.
+#[derive(Debug, Deserialize)]
+struct IpResponse {
+    ip: String,
+}
+
+/// A change in the alerts of a record, sent to notifiers.
+#[derive(Clone, Debug, Serialize)]
+pub struct Notification {
+    pub event: Event,
+    pub name: String,
+    /// When the alert was first raised.
+    pub since: DateTime<Utc>,
+}
+
+/// The event of a notification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    /// The record became outdated, such as after a failed update.
+    Outdated,
+    /// The inventory entry no longer matches a Cloudflare record.
+    Invalid,
+    /// The record is no longer outdated or invalid.
+    Recovered,
+}
+
+/// Return the directory plugins of a kind are discovered in, next to the
+/// default config file, if this OS has a configuration directory.
+pub fn dir(kind: PluginKind) -> Option<PathBuf> {
+    directories::BaseDirs::new().map(|base_dirs| {
+        base_dirs
+            .config_dir()
+            .join("cddns")
+            .join("plugins")
+            .join(kind.dir_name())
+    })
+}
+
+/// Return the plugins of a kind, those configured followed by the
+/// executables discovered in its directory.
+pub fn discover(
+    kind: PluginKind,
+    configured: Option<&Vec<PathBuf>>,
+) -> Vec<PathBuf> {
+    let mut plugins = configured.cloned().unwrap_or_default();
+    if let Some(entries) = dir(kind).and_then(|dir| std::fs::read_dir(dir).ok())
+    {
+        let mut discovered: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| is_executable(path))
+            .collect();
+        discovered.sort();
+        plugins.extend(discovered);
+    }
+    plugins
+}
+
+/// Returns whether a path is an executable file.
+fn is_executable(path: &Path) -> bool {
+    let Ok(metadata) = path.metadata() else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        metadata.is_file()
+    }
+}
+
+/// Resolve an IP address of a family with an IP source plugin.
+pub async fn resolve<T: Address>(path: &Path) -> Result<T> {
+    let version = if T::UNSPECIFIED.is_ipv6() { 6 } else { 4 };
+    let output = call(path, &Request::Ip { version }).await?;
+    let response: IpResponse =
+        serde_json::from_str(&output).map_err(|err| {
+            Error::response(
+                format!("plugin '{}' response", path.display()),
+                err,
+            )
+        })?;
+    response.ip.trim().parse().map_err(|_| Error::Plugin {
+        path: path.to_owned(),
+        reason: format!("'{}' is not an IPv{version} address", response.ip),
+    })
+}
+
+/// Send a notification to every notifier. Failures are logged, as
+/// notifications are best-effort.
+pub async fn notify(notifiers: &[PathBuf], notification: &Notification) {
+    for notifier in notifiers.iter() {
+        match call(notifier, &Request::Notify(notification)).await {
+            Ok(_) => debug!(
+                notifier = %notifier.display(),
+                name = notification.name,
+                "sent notification"
+            ),
+            Err(err) => warn!("unable to send notification: {err}"),
+        }
+    }
+}
+
+/// Run a plugin with a request on stdin, returning its standard output.
+async fn call(path: &Path, request: &Request<'_>) -> Result<String> {
+    let failed = |reason: String| Error::Plugin {
+        path: path.to_owned(),
+        reason,
+    };
+    let request = serde_json::to_vec(request)
+        .map_err(|err| Error::encode("plugin request", err))?;
+    let mut child = Command::new(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| failed(err.to_string()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Plugins may exit without reading their request
+        let _ = stdin.write_all(&request).await;
+    }
+    let output = time::timeout(TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| failed("timed out".to_string()))?
+        .map_err(|err| failed(err.to_string()))?;
+    if !output.status.success() {
+        return Err(failed(format!(
+            "{}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -1,11 +1,11 @@
-use anyhow::{Context, Result};
+use crate::error::{Error, Result};
 
 /// Serialize an object to TOML.
 pub fn as_toml<T>(contents: &T) -> Result<String>
 where
     T: ?Sized + serde::Serialize,
 {
-    toml::to_string(&contents).context("encoding as TOML")
+    toml::to_string(&contents).map_err(|err| Error::encode("TOML", err))
 }
 
 /// Serialize an object to YAML.
@@ -13,5 +13,5 @@ pub fn as_yaml<T>(contents: &T) -> Result<String>
 where
     T: ?Sized + serde::Serialize,
 {
-    serde_yaml::to_string(&contents).context("encoding as YAML")
+    serde_yaml::to_string(&contents).map_err(|err| Error::encode("YAML", err))
 }
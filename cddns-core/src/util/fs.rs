@@ -0,0 +1,37 @@
+use crate::error::{Error, Result};
+use std::path::Path;
+use tracing::debug;
+
+/// If a file exists, remove it by force without user interaction.
+pub async fn remove_force(path: impl AsRef<Path>) -> Result<()> {
+    if path.as_ref().exists() {
+        tokio::fs::remove_file(path.as_ref())
+            .await
+            .map_err(|err| Error::io(path.as_ref(), err))?;
+        debug!("removed: '{}'", path.as_ref().display());
+    }
+    Ok(())
+}
+
+/// Save the desired contents, overwriting and creating directories if
+/// necessary.
+pub async fn save(
+    path: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|err| Error::io(parent, err))?;
+    }
+    if path.exists() {
+        debug!("overwriting '{}'...", path.display());
+        remove_force(path).await?;
+    }
+    tokio::fs::write(path, contents)
+        .await
+        .map_err(|err| Error::io(path, err))?;
+    debug!("wrote: '{}'", path.display());
+    Ok(())
+}
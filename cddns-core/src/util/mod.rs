@@ -0,0 +1,9 @@
+//! cddns utility and helper functions.
+
+pub mod encoding;
+pub mod fs;
+pub mod names;
+pub mod postprocessors;
+pub mod progress;
+pub mod release;
+pub mod warnings;
@@ -0,0 +1,51 @@
+//! Internationalized domain names.
+//!
+//! Cloudflare stores names in their ASCII (punycode) form, such as
+//! `xn--mnchen-3ya.example.de`, while users may write them in Unicode, such
+//! as `münchen.example.de`. Names are compared in ASCII form and displayed in
+//! Unicode form.
+
+use std::borrow::Cow;
+
+/// Return the ASCII form of a name, converting each Unicode label through
+/// IDNA. Labels which cannot be converted, and ASCII labels such as `*` or
+/// `_acme-challenge`, are kept as given.
+pub fn to_ascii(name: &str) -> Cow<'_, str> {
+    if name.is_ascii() {
+        return Cow::Borrowed(name);
+    }
+    let labels: Vec<String> = name
+        .split('.')
+        .map(|label| {
+            if label.is_ascii() {
+                label.to_string()
+            } else {
+                idna::domain_to_ascii(label)
+                    .unwrap_or_else(|_| label.to_string())
+            }
+        })
+        .collect();
+    Cow::Owned(labels.join("."))
+}
+
+/// Return the Unicode form of a name, for display. Names without punycode
+/// labels, or with invalid ones, are kept as given.
+pub fn to_unicode(name: &str) -> Cow<'_, str> {
+    let punycode = name.split('.').any(|label| {
+        label
+            .get(..4)
+            .is_some_and(|p| p.eq_ignore_ascii_case("xn--"))
+    });
+    if !punycode {
+        return Cow::Borrowed(name);
+    }
+    match idna::domain_to_unicode(name) {
+        (unicode, Ok(())) => Cow::Owned(unicode),
+        (_, Err(_)) => Cow::Borrowed(name),
+    }
+}
+
+/// Returns whether two names are the same, in either form.
+pub fn same(a: &str, b: &str) -> bool {
+    a == b || to_ascii(a).eq_ignore_ascii_case(&to_ascii(b))
+}
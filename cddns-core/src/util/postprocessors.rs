@@ -1,8 +1,10 @@
 use crate::cloudflare;
 use crate::cloudflare::models::{Record, Zone};
 use crate::config::models::ConfigOpts;
+use crate::error::{Error, Result};
 use crate::inventory::models::InventoryData;
-use anyhow::{Context, Result};
+use crate::inventory::selector::RecordSelector;
+use crate::util::names;
 use chrono::Local;
 use tracing::{trace, warn};
 
@@ -40,9 +42,7 @@ impl InventoryAliasCommentPostProcessor {
     /// Initialize the inventory alias post-processor.
     pub async fn try_init(opts: &ConfigOpts) -> Result<Self> {
         trace!("starting data retrieval for cloudflare post-processing");
-        let token = opts
-                    .verify.token.as_ref()
-                    .context("no token was provided, need help? see https://github.com/simbleau/cddns#readme")?;
+        let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
         let zones = cloudflare::endpoints::zones(&token).await?;
         let records = cloudflare::endpoints::records(&zones, &token).await?;
         trace!("finished retrieval of cloudflare post-processing resources");
@@ -58,21 +58,22 @@ impl PostProcessor for InventoryAliasCommentPostProcessor {
     fn post_process(&self, yaml: &mut String) -> Result<()> {
         trace!("starting post-processing: inventory aliases");
         let data = serde_yaml::from_slice::<InventoryData>(yaml.as_bytes())
-            .context("deserializing inventory from bytes")?;
+            .map_err(|err| Error::parse("inventory", err))?;
 
         for (zone_id, record_ids) in data.into_iter() {
             // Post-process zone
             if let Some(zone) =
-                crate::cmd::list::find_zone(&self.zones, &zone_id)
+                crate::cloudflare::filters::find_zone(&self.zones, &zone_id)
             {
-                let z_idx =
-                    yaml.find(&zone_id).context("zone not found in yaml")?;
+                let z_idx = yaml.find(&zone_id).ok_or_else(|| {
+                    Error::NotFound(format!("zone '{zone_id}' in yaml"))
+                })?;
                 yaml.insert_str(
                     z_idx + zone_id.len() + ":".len(),
                     &format!(
                         " # '{}'",
                         if zone_id == zone.id {
-                            zone.name
+                            names::to_unicode(&zone.name).into_owned()
                         } else {
                             zone.id
                         }
@@ -87,22 +88,30 @@ impl PostProcessor for InventoryAliasCommentPostProcessor {
 
             // Post-process records
             for record_id in record_ids {
-                if let Some(record) =
-                    crate::cmd::list::find_record(&self.records, &record_id)
-                {
-                    let r_idx = yaml
-                        .find(&record_id)
-                        .context("record not found in yaml")?;
+                let selector = RecordSelector::parse(&record_id)?;
+                let alias = if selector.is_multiple() {
+                    let selected = selector.select(&zone_id, &self.records);
+                    Some(format!("{} records", selected.len()))
+                } else {
+                    crate::cloudflare::filters::find_record(
+                        &self.records,
+                        &record_id,
+                    )
+                    .map(|record| {
+                        if record_id == record.id {
+                            names::to_unicode(&record.name).into_owned()
+                        } else {
+                            record.id
+                        }
+                    })
+                };
+                if let Some(alias) = alias {
+                    let r_idx = yaml.find(&record_id).ok_or_else(|| {
+                        Error::NotFound(format!("record '{record_id}' in yaml"))
+                    })?;
                     yaml.insert_str(
                         r_idx + record_id.len(),
-                        &format!(
-                            " # '{}'",
-                            if record_id == record.id {
-                                record.name
-                            } else {
-                                record.id
-                            }
-                        ),
+                        &format!(" # '{alias}'"),
                     );
                 } else {
                     warn!(
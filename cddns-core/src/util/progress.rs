@@ -0,0 +1,83 @@
+use std::fmt::{self, Display};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// The target of routine progress logs, such as those repeated on every
+/// `watch` check, which frontends may hide.
+pub const TARGET: &str = "cddns::progress";
+
+/// Whether routine progress logs are hidden.
+static HIDDEN: AtomicBool = AtomicBool::new(false);
+
+/// Hide or show routine progress logs.
+pub fn set_hidden(hidden: bool) {
+    HIDDEN.store(hidden, Ordering::Relaxed);
+}
+
+/// Returns whether routine progress logs are hidden.
+pub fn is_hidden() -> bool {
+    HIDDEN.load(Ordering::Relaxed)
+}
+
+/// The progress of a paged fetch of Cloudflare resources.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fetch {
+    /// Zones are being retrieved, with the pages retrieved so far.
+    Zones { pages: u32 },
+    /// Records are being retrieved, with the zones and pages retrieved so
+    /// far.
+    Records {
+        zones: usize,
+        total_zones: usize,
+        pages: u32,
+    },
+    /// The fetch is complete.
+    Done,
+}
+
+impl Display for Fetch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fetch::Zones { pages } => {
+                write!(f, "retrieving zones ({})", Pages(*pages))
+            }
+            Fetch::Records {
+                zones,
+                total_zones,
+                pages,
+            } => write!(
+                f,
+                "retrieving records ({zones}/{total_zones} zones, {})",
+                Pages(*pages)
+            ),
+            Fetch::Done => write!(f, "retrieved"),
+        }
+    }
+}
+
+/// A count of pages retrieved.
+struct Pages(u32);
+
+impl Display for Pages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            1 => write!(f, "1 page"),
+            pages => write!(f, "{pages} pages"),
+        }
+    }
+}
+
+/// The frontend reporting fetch progress, such as with a progress bar.
+static REPORTER: OnceLock<Box<dyn Fn(Fetch) + Send + Sync>> = OnceLock::new();
+
+/// Report fetch progress to a frontend. Only the first reporter is kept.
+pub fn set_reporter(reporter: impl Fn(Fetch) + Send + Sync + 'static) {
+    let _ = REPORTER.set(Box::new(reporter));
+}
+
+/// Report fetch progress, if a frontend is listening.
+pub fn report(fetch: Fetch) {
+    if let Some(reporter) = REPORTER.get() {
+        reporter(fetch);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// The latest release of cddns on GitHub.
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/simbleau/cddns/releases/latest";
+
+/// The shortest interval between release checks.
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Whether release checks are enabled.
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// When releases were last checked.
+static LAST_CHECK: Mutex<Option<Instant>> = Mutex::new(None);
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+}
+
+/// Enable or disable release checks.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Return the latest release version, if newer than the current version.
+pub async fn newer_release(current: &str) -> Result<Option<String>> {
+    let release: Release = reqwest::Client::builder()
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(Error::Network)?
+        .get(LATEST_RELEASE_URL)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(Error::Network)?
+        .json()
+        .await
+        .map_err(Error::Network)?;
+    let latest = release.tag_name.trim_start_matches('v').to_string();
+    Ok(match (parse_version(&latest), parse_version(current)) {
+        (Some(l), Some(c)) if l > c => Some(latest),
+        _ => None,
+    })
+}
+
+/// Log when a newer release is available, checking at most once a day.
+/// Failures are logged, as release checks are best-effort.
+pub async fn check_daily(current: &str) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Ok(mut last_check) = LAST_CHECK.lock() {
+        if last_check.is_some_and(|last| last.elapsed() < CHECK_INTERVAL) {
+            return;
+        }
+        last_check.replace(Instant::now());
+    }
+    match newer_release(current).await {
+        Ok(Some(latest)) => info!(
+            current,
+            latest, "a new release of cddns is available: https://github.com/simbleau/cddns/releases"
+        ),
+        Ok(None) => debug!(current, "cddns is up to date"),
+        Err(err) => debug!("unable to check for releases: {err}"),
+    }
+}
+
+/// Parse a `major.minor.patch` version, ignoring pre-release suffixes.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version
+        .split(['-', '+'])
+        .next()?
+        .split('.')
+        .map(|part| part.parse::<u64>().ok());
+    Some((parts.next()??, parts.next()??, parts.next()??))
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+use std::fmt::{self, Display};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Whether warnings are logged when surfaced.
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// The warnings surfaced during this run.
+static WARNINGS: Mutex<Vec<Warning>> = Mutex::new(Vec::new());
+
+/// The category of a warning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    /// A configuration key or behavior which will be removed.
+    Deprecation,
+    /// A note on compatibility with the environment or older releases.
+    Compatibility,
+    /// A record setting which undermines dynamic DNS.
+    Record,
+}
+
+impl Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningKind::Deprecation => write!(f, "deprecation"),
+            WarningKind::Compatibility => write!(f, "compatibility"),
+            WarningKind::Record => write!(f, "record"),
+        }
+    }
+}
+
+/// A warning surfaced to the user at most once per run.
+#[derive(Clone, Debug, Serialize)]
+pub struct Warning {
+    pub kind: WarningKind,
+    /// A stable identifier for this warning.
+    pub code: String,
+    pub message: String,
+    /// An optional hint to resolve this warning.
+    pub help: Option<String>,
+}
+
+impl Warning {
+    /// Create a new warning.
+    pub fn new(
+        kind: WarningKind,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            code: code.into(),
+            message: message.into(),
+            help: None,
+        }
+    }
+
+    /// Attach a hint to resolve this warning.
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.replace(help.into());
+        self
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)?;
+        if let Some(ref help) = self.help {
+            write!(f, " ({help})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Enable or disable logging warnings when they are surfaced.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Surface a warning, logging it unless it was already surfaced this run or
+/// warnings are disabled.
+pub fn emit(warning: Warning) {
+    let Ok(mut warnings) = WARNINGS.lock() else {
+        return;
+    };
+    if warnings.iter().any(|w| w.code == warning.code) {
+        return;
+    }
+    if ENABLED.load(Ordering::Relaxed) {
+        warn!(code = warning.code, "{warning}");
+    }
+    warnings.push(warning);
+}
+
+/// Return all warnings surfaced during this run.
+pub fn surfaced() -> Vec<Warning> {
+    WARNINGS
+        .lock()
+        .map(|warnings| warnings.clone())
+        .unwrap_or_default()
+}
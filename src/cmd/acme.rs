@@ -0,0 +1,171 @@
+use crate::cmd::record::zone_of;
+use anyhow::Result;
+use cddns_core::cloudflare;
+use cddns_core::cloudflare::models::NewRecord;
+use cddns_core::config::models::{ConfigOpts, ConfigOptsVerify};
+use cddns_core::error::Error;
+use cddns_core::util::names;
+use clap::{Args, Subcommand};
+use tracing::{info, warn};
+
+/// Manage ACME DNS-01 challenge records, as a DNS hook for certbot or lego.
+#[derive(Debug, Args)]
+#[clap(name = "acme")]
+pub struct AcmeCmd {
+    #[clap(subcommand)]
+    action: AcmeSubcommands,
+    #[clap(flatten)]
+    pub cfg: ConfigOptsVerify,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum AcmeSubcommands {
+    /// Create a challenge TXT record.
+    SetTxt(TxtOpts),
+    /// Delete challenge TXT records.
+    ClearTxt(ClearOpts),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct TxtOpts {
+    /// The name of the challenge record, e.g.
+    /// `_acme-challenge.example.com`.
+    #[clap(long, value_name = "name")]
+    pub name: String,
+    /// The challenge token.
+    #[clap(long, value_name = "token")]
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ClearOpts {
+    /// The name of the challenge record, e.g.
+    /// `_acme-challenge.example.com`.
+    #[clap(long, value_name = "name")]
+    pub name: String,
+    /// Only delete the record with this challenge token. [default: all]
+    #[clap(long, value_name = "token")]
+    pub value: Option<String>,
+}
+
+impl AcmeCmd {
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub async fn run(self, opts: ConfigOpts) -> Result<()> {
+        // Apply CLI configuration layering
+        let cli_opts = ConfigOpts::builder().verify(Some(self.cfg)).build();
+        let opts = ConfigOpts::builder().merge(opts).merge(cli_opts).build();
+
+        // Run
+        match self.action {
+            AcmeSubcommands::SetTxt(txt_opts) => {
+                set_txt(&opts, &txt_opts).await
+            }
+            AcmeSubcommands::ClearTxt(clear_opts) => {
+                clear_txt(&opts, &clear_opts).await
+            }
+        }
+    }
+}
+
+/// Create a challenge TXT record, unless it already exists. Other tokens of
+/// the same name are kept, such as for a wildcard and its apex.
+#[tracing::instrument(level = "trace", skip_all)]
+async fn set_txt(opts: &ConfigOpts, cli_opts: &TxtOpts) -> Result<()> {
+    cloudflare::cache::ensure_online("create records")?;
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
+    let name = names::to_ascii(cli_opts.name.trim_end_matches('.'));
+    let name = name.as_ref();
+
+    // Find the zone
+    let zones = cloudflare::endpoints::zones(token.to_string()).await?;
+    let zone = zone_of(&zones, name)
+        .ok_or_else(|| Error::NotFound(format!("zone containing '{name}'")))?;
+
+    // Skip existing challenges
+    let records = cloudflare::endpoints::all_records(
+        std::slice::from_ref(&zone),
+        token.to_string(),
+    )
+    .await?;
+    if records.iter().any(|r| {
+        r.record_type == "TXT"
+            && r.name == name
+            && unquote(&r.content) == cli_opts.value
+    }) {
+        info!(name, "challenge record already exists");
+        return Ok(());
+    }
+
+    // Create the challenge
+    let record = cloudflare::endpoints::create_record(
+        token,
+        &zone.id,
+        &NewRecord {
+            name: name.to_string(),
+            record_type: "TXT".to_string(),
+            content: cli_opts.value.clone(),
+            ttl: 1,
+            proxied: false,
+            comment: None,
+        },
+    )
+    .await?;
+    info!(
+        id = record.id,
+        name = record.name,
+        "created challenge record"
+    );
+    Ok(())
+}
+
+/// Delete the challenge TXT records of a name.
+#[tracing::instrument(level = "trace", skip_all)]
+async fn clear_txt(opts: &ConfigOpts, cli_opts: &ClearOpts) -> Result<()> {
+    cloudflare::cache::ensure_online("delete records")?;
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
+    let name = names::to_ascii(cli_opts.name.trim_end_matches('.'));
+    let name = name.as_ref();
+
+    // Find the challenges
+    let zones = cloudflare::endpoints::zones(token.to_string()).await?;
+    let zone = zone_of(&zones, name)
+        .ok_or_else(|| Error::NotFound(format!("zone containing '{name}'")))?;
+    let mut records =
+        cloudflare::endpoints::all_records(&[zone], token.to_string()).await?;
+    records.retain(|r| {
+        r.record_type == "TXT"
+            && r.name == name
+            && cli_opts
+                .value
+                .as_ref()
+                .is_none_or(|value| unquote(&r.content) == value)
+    });
+    if records.is_empty() {
+        warn!(name, "no challenge records to delete");
+        return Ok(());
+    }
+
+    // Delete the challenges
+    for record in records {
+        cloudflare::endpoints::delete_record(
+            token,
+            &record.zone_id,
+            &record.id,
+        )
+        .await?;
+        info!(
+            id = record.id,
+            name = record.name,
+            "deleted challenge record"
+        );
+    }
+    Ok(())
+}
+
+/// Strip the quotes Cloudflare may return around TXT content.
+fn unquote(content: &str) -> &str {
+    content
+        .strip_prefix('"')
+        .and_then(|c| c.strip_suffix('"'))
+        .unwrap_or(content)
+}
@@ -1,8 +1,9 @@
-use crate::config::{default_config_path, models::ConfigOpts};
-use crate::inventory::default_inventory_path;
 use crate::util;
 use crate::util::scanner::{prompt, prompt_ron, prompt_t, prompt_yes_or_no};
-use anyhow::Result;
+use anyhow::{Context, Result};
+use cddns_core::cloudflare::API_BASE;
+use cddns_core::config::{default_config_path, models::ConfigOpts};
+use cddns_core::inventory::default_inventory_path;
 use clap::{Args, Subcommand};
 use std::path::PathBuf;
 
@@ -20,6 +21,15 @@ enum ConfigSubcommands {
     Build,
     /// Show the current configuration.
     Show,
+    /// Show warnings about the current configuration.
+    Warnings(WarningsOpts),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct WarningsOpts {
+    /// Print warnings as JSON.
+    #[clap(long)]
+    pub json: bool,
 }
 
 impl ConfigCmd {
@@ -28,6 +38,9 @@ impl ConfigCmd {
         match self.action {
             ConfigSubcommands::Build => build().await,
             ConfigSubcommands::Show => show(&opts).await,
+            ConfigSubcommands::Warnings(warnings_opts) => {
+                warnings(&warnings_opts).await
+            }
         }
     }
 }
@@ -49,50 +62,27 @@ async fn build() -> Result<()> {
             println!(r#" > default: none"#);
             prompt("token", "string")?
         })
-        .list_include_zones({
+        .list_query({
             println!();
-            println!(r#"Next, if you want filtered ZONE output in the CLI, provide regex filters in RON notation which will INCLUDE output in `cddns inventory build` and `cddns list`."#);
-            println!(r#" > what is RON? https://github.com/ron-rs/ron/wiki/Specification"#);
-            println!(r#" > what are zones? https://www.cloudflare.com/learning/dns/glossary/dns-zone/"#);
-            println!(r#" > examples: [], [".*.(com|dev)"], ["example.com", "example.dev"]"#);
-            println!(r#" > default: [".*"] (all)"#);
-            prompt_ron(
-                "include zone filters",
-                "list[string]",
-            )?
+            println!(r#"Next, if you want filtered RECORD output in the CLI, provide a query selecting the records to include in `cddns inventory build` and `cddns list`."#);
+            println!(r#" > what is a query? https://github.com/simbleau/cddns#323-list"#);
+            println!(r#" > examples: type:A, zone:example.com AND NOT name~'^beta\.', tag:ddns OR comment~cddns"#);
+            println!(r#" > default: none (all)"#);
+            prompt("query", "string")?
         })
-        .list_ignore_zones({
+        .list_account({
             println!();
-            println!(r#"Next, if you want filtered ZONE output in the CLI, provide regex filters in RON notation which will IGNORE output in `cddns inventory build` and `cddns list`."#);
-            println!(r#" > what is RON? https://github.com/ron-rs/ron/wiki/Specification"#);
-            println!(r#" > what are zones? https://www.cloudflare.com/learning/dns/glossary/dns-zone/"#);
-            println!(r#" > examples: [], [".*.(com|dev)"], ["example.com", "example.dev"]"#);
-            println!(r#" > default: [] (none)"#);
-            prompt_ron(
-                "ignore zone filters",
-                "list[string]",
-            )?
+            println!(r#"Next, if your token can access many accounts, provide the name or id of the account whose zones to use in `cddns inventory build` and `cddns list`."#);
+            println!(r#" > help? `cddns list accounts`"#);
+            println!(r#" > default: none (all)"#);
+            prompt("account", "string")?
         })
-        .list_include_records({
+        .list_tag({
             println!();
-            println!(r#"Next, if you want filtered RECORD output in the CLI, provide regex filters in RON notation which will INCLUDE output in `cddns inventory build` and `cddns list`."#);
-            println!(r#" > what is RON? https://github.com/ron-rs/ron/wiki/Specification"#);
-            println!(r#" > what are records? https://www.cloudflare.com/learning/dns/dns-records/"#);
-            println!(r#" > examples: [], [".*.example.com"], ["beta.example.com", "gamma.example.com"]"#);
-            println!(r#" > default: [".*"] (all)"#);
-            prompt_ron(
-                "include record filters",
-                "list[string]",
-            )?
-        })
-        .list_ignore_records({
-            println!();
-            println!(r#"Next, if you want filtered RECORD output in the CLI, provide regex filters in RON notation which will IGNORE output in `cddns inventory build` and `cddns list`."#);
-            println!(r#" > what is RON? https://github.com/ron-rs/ron/wiki/Specification"#);
-            println!(r#" > what are records? https://www.cloudflare.com/learning/dns/dns-records/"#);
-            println!(r#" > examples: [], [".*.example.com"], ["beta.example.com", "gamma.example.com"]"#);
-            println!(r#" > default: [] (none)"#);
-            prompt_ron("ignore record filters", "list[string]")?
+            println!(r#"Next, if you tag your dynamic records in the Cloudflare dashboard, provide the tag of the records to use in `cddns inventory build` and `cddns list`."#);
+            println!(r#" > example: ddns"#);
+            println!(r#" > default: none (all)"#);
+            prompt("tag", "string")?
         })
         .inventory_path({
             println!();
@@ -121,6 +111,260 @@ async fn build() -> Result<()> {
                 "interval for `inventory watch`?",
                 "number",
             )?
+        })
+        .inventory_update_cooldown({
+            println!();
+            println!(r#"Next, specify the minimum interval (in milliseconds) between updates of the same record, to protect the Cloudflare API from flapping IPs."#);
+            println!(r#" > examples: 0 (no cooldown), 300000 (5 minutes)"#);
+            println!(r#" > default: 0"#);
+            prompt_t("update cooldown", "number")?
+        })
+        .inventory_reconcile_interval({
+            println!();
+            println!(r#"Next, specify the maximum interval (in milliseconds) between full reconciliations when using `inventory watch`. Cloudflare is not contacted while your public IPs are unchanged between reconciliations."#);
+            println!(r#" > examples: 0 (always reconcile), 86400000 (1 day)"#);
+            println!(r#" > default: 3600000"#);
+            prompt_t("reconcile interval", "number")?
+        })
+        .inventory_watch_immediate({
+            println!();
+            println!(r#"Next, would you like `inventory watch` to check records as soon as it starts, rather than waiting one interval?"#);
+            println!(r#" > default: yes"#);
+            prompt_yes_or_no("check immediately?", "Y/n")?
+        })
+        .inventory_watch_events({
+            println!();
+            println!(r#"Next, would you like `inventory watch` to check records as soon as your network changes, such as when reconnecting, in addition to the watch interval?"#);
+            println!(r#" > default: no"#);
+            prompt_yes_or_no("watch network changes?", "y/N")?
+        })
+        .inventory_watch_max_failures({
+            println!();
+            println!(r#"Next, specify the number of consecutive failed checks after which `inventory watch` exits with an error, letting a supervisor such as systemd or Kubernetes restart it."#);
+            println!(r#" > examples: 0 (never exit), 5"#);
+            println!(r#" > default: 0"#);
+            prompt_t("max consecutive failures", "number")?
+        })
+        .inventory_watch_listen({
+            println!();
+            println!(r#"Next, if `inventory watch` runs under Kubernetes or a load balancer, provide an address to serve `/livez` and `/readyz` health endpoints on. `/readyz` succeeds once records are first reconciled."#);
+            println!(r#" > examples: 0.0.0.0:8080, 127.0.0.1:9000"#);
+            println!(r#" > default: none"#);
+            prompt_t("listen address", "address")?
+        })
+        .inventory_watch_textfile_dir({
+            println!();
+            println!(r#"Next, if you collect metrics with the Prometheus node_exporter textfile collector, provide its directory to write `cddns.prom` to after each `inventory watch` check."#);
+            println!(r#" > examples: /var/lib/node_exporter/textfile_collector"#);
+            println!(r#" > default: none"#);
+            prompt_t("textfile directory", "path")?
+        })
+        .inventory_watch_summary({
+            println!();
+            println!(r#"Next, to keep long-running `inventory watch` logs readable, specify how many checks to condense into each summary line. Updates, warnings and errors are always logged."#);
+            println!(r#" > examples: 0 (log every check), 60"#);
+            println!(r#" > default: 0"#);
+            prompt_t("checks per summary", "number")?
+        })
+        .inventory_ipv4({
+            println!();
+            println!(r#"Next, would you like to handle A records and resolve your public IPv4 address? Disable this on hosts without IPv4 connectivity."#);
+            println!(r#" > default: yes"#);
+            prompt_yes_or_no("handle IPv4?", "Y/n")?
+        })
+        .inventory_ipv6({
+            println!();
+            println!(r#"Next, would you like to handle AAAA records and resolve your public IPv6 address? Disable this on hosts without IPv6 connectivity."#);
+            println!(r#" > default: yes"#);
+            prompt_yes_or_no("handle IPv6?", "Y/n")?
+        })
+        .inventory_pair_records({
+            println!();
+            println!(r#"Next, would you like both the A and AAAA records of a name kept current when either is in your inventory? This lets one entry manage a dual-stack host."#);
+            println!(r#" > default: no"#);
+            prompt_yes_or_no("pair A and AAAA records?", "y/N")?
+        })
+        .inventory_check_via({
+            println!();
+            println!(r#"Next, specify how records are checked. "api" lists records through Cloudflare, while "dns" resolves your inventory names and only uses the API when records are outdated, conserving API quota."#);
+            println!(r#" > options: api, dns"#);
+            println!(r#" > default: api"#);
+            prompt_t("check via", "api|dns")?
+        })
+        .inventory_unsupported_types({
+            println!();
+            println!(r#"Next, specify how records of types cddns can't resolve, such as CNAME records selected by a tag, are handled. "skip" warns and skips them, "error" fails the check, and "static" treats them as always valid."#);
+            println!(r#" > options: skip, error, static"#);
+            println!(r#" > default: skip"#);
+            prompt_t("unsupported types", "skip|error|static")?
+        })
+        .inventory_comment_template({
+            println!();
+            println!(r#"Next, if you want the Cloudflare dashboard to show which records are managed by cddns, provide a template of the comment set on updated records. "{{name}}", "{{content}}" and "{{time}}" are replaced."#);
+            println!(r#" > example: managed by cddns, updated {{time}}"#);
+            println!(r#" > default: none (unchanged)"#);
+            prompt("comment template", "string")?
+        })
+        .inventory_create_missing({
+            println!();
+            println!(r#"Next, would you like `cddns inventory update` to create inventory records missing from Cloudflare with your public IP, rather than reporting them as invalid?"#);
+            println!(r#" > default: no"#);
+            prompt_yes_or_no("create missing records?", "y/N")?
+        })
+        .inventory_prune_invalid({
+            println!();
+            println!(r#"Next, would you like `cddns inventory update` to also prune invalid records from the inventory, in the same run?"#);
+            println!(r#" > default: no"#);
+            prompt_yes_or_no("prune invalid records?", "y/N")?
+        })
+        .inventory_notifiers({
+            println!();
+            println!(r#"Next, if you want to be notified when records become outdated or invalid and when they recover, provide notifier plugin executables in RON notation. Executables in the `plugins/notify` directory next to the default config are also used."#);
+            println!(r#" > what is RON? https://github.com/ron-rs/ron/wiki/Specification"#);
+            println!(r#" > example: ["/usr/local/bin/cddns-notify-slack"]"#);
+            println!(r#" > default: none"#);
+            prompt_ron("notifiers", "list[path]")?
+        })
+        .api_max_attempts({
+            println!();
+            println!(r#"Next, specify the maximum attempts for each Cloudflare request. Network errors, timeouts, and server errors are retried."#);
+            println!(r#" > examples: 1 (no retries), 5"#);
+            println!(r#" > default: 3"#);
+            prompt_t("max request attempts", "number")?
+        })
+        .api_retry_backoff({
+            println!();
+            println!(r#"Next, specify the initial delay (in milliseconds) between retried requests, which doubles after each attempt."#);
+            println!(r#" > examples: 250, 1000 (1 second)"#);
+            println!(r#" > default: 500"#);
+            prompt_t("retry backoff", "number")?
+        })
+        .api_user_agent({
+            println!();
+            println!(r#"Next, if you want to identify this host in Cloudflare API traffic, provide a suffix for the `cddns/<version>` user agent."#);
+            println!(r#" > examples: home-router, (fleet; host=nas01)"#);
+            println!(r#" > default: none"#);
+            prompt("user agent suffix", "string")?
+        })
+        .api_base_url({
+            println!();
+            println!(r#"Next, if you reach Cloudflare through a proxy, provide the base URL of API requests."#);
+            println!(r#" > example: https://proxy.example.com/client/v4"#);
+            println!(r#" > default: {API_BASE}"#);
+            prompt("API base URL", "url")?
+        })
+        .api_zones_ttl({
+            println!();
+            println!(r#"Next, specify how long (in seconds) the zone list is cached, since zones change far less often than records. 0 lists zones on every run."#);
+            println!(r#" > examples: 0 (no caching), 86400 (1 day)"#);
+            println!(r#" > default: 3600"#);
+            prompt_t("zones TTL", "number")?
+        })
+        .ip_ipv4_urls({
+            println!();
+            println!(r#"Next, if you want to resolve your public IPv4 address with your own services, provide URLs in RON notation which are tried in order. Responses are plain text, unless a URL fragment names a JSON field to read."#);
+            println!(r#" > what is RON? https://github.com/ron-rs/ron/wiki/Specification"#);
+            println!(r#" > examples: ["https://ifconfig.me/ip"], ["https://api.ipify.org?format=json#ip"]"#);
+            println!(r#" > default: built-in sources"#);
+            prompt_ron("IPv4 source URLs", "list[string]")?
+        })
+        .ip_ipv6_urls({
+            println!();
+            println!(r#"Next, if you want to resolve your public IPv6 address with your own services, provide URLs in RON notation which are tried in order. Responses are plain text, unless a URL fragment names a JSON field to read."#);
+            println!(r#" > what is RON? https://github.com/ron-rs/ron/wiki/Specification"#);
+            println!(r#" > examples: ["https://ifconfig.me/ip"], ["https://api64.ipify.org?format=json#ip"]"#);
+            println!(r#" > default: built-in sources"#);
+            prompt_ron("IPv6 source URLs", "list[string]")?
+        })
+        .ip_interface({
+            println!();
+            println!(r#"Next, if your public addresses are configured on a network interface, provide its name to read them without external services."#);
+            println!(r#" > examples: eth0, ppp0"#);
+            println!(r#" > default: none"#);
+            prompt("network interface", "string")?
+        })
+        .ip_router({
+            println!();
+            println!(r#"Next, would you like to query your public IPv4 address from your router via UPnP or NAT-PMP, instead of external services?"#);
+            println!(r#" > default: no"#);
+            prompt_yes_or_no("query the router?", "y/N")?
+        })
+        .ip_dns({
+            println!();
+            println!(r#"Next, if you want to resolve your public IPs with DNS instead of HTTP services, provide a DNS provider."#);
+            println!(r#" > options: opendns, cloudflare, google"#);
+            println!(r#" > default: none"#);
+            prompt_t("DNS provider", "provider")?
+        })
+        .ip_command({
+            println!();
+            println!(r#"Next, if you have your own way to detect your public IPs, provide a shell command printing them. It runs with CDDNS_IP_VERSION set to 4 or 6."#);
+            println!(r#" > example: curl -s http://169.254.169.254/latest/meta-data/public-ipv4"#);
+            println!(r#" > default: none"#);
+            prompt("IP command", "string")?
+        })
+        .ip_plugins({
+            println!();
+            println!(r#"Next, if you have IP source plugins, provide their executables in RON notation, which are tried in order. Executables in the `plugins/ip` directory next to the default config are also used."#);
+            println!(r#" > what is RON? https://github.com/ron-rs/ron/wiki/Specification"#);
+            println!(r#" > example: ["/usr/local/bin/cddns-ip-fritzbox"]"#);
+            println!(r#" > default: none"#);
+            prompt_ron("IP plugins", "list[path]")?
+        })
+        .ip_sources({
+            println!();
+            println!(r#"Next, if you want to choose the order your public IPs are resolved in, provide IP sources in RON notation which are tried in order until one succeeds."#);
+            println!(r#" > what is RON? https://github.com/ron-rs/ron/wiki/Specification"#);
+            println!(r#" > options: command, plugin, interface, router, dns, http, builtin"#);
+            println!(r#" > example: [interface, dns, builtin]"#);
+            println!(r#" > default: every configured source, or else built-in sources"#);
+            prompt_ron("IP sources", "list[source]")?
+        })
+        .ip_consensus({
+            println!();
+            println!(r#"Next, if you want to protect your records from a single faulty IP source, provide how many sources must resolve the same address before it is trusted."#);
+            println!(r#" > default: 1"#);
+            prompt_t("IP consensus", "number")?
+        })
+        .ip_confirm_delay({
+            println!();
+            println!(r#"Next, if you want to avoid publishing a transient IP seen during a reconnect, provide the milliseconds to wait before re-resolving your public IPs to confirm an update. Updates are skipped unless both readings match."#);
+            println!(r#" > default: 0 (off)"#);
+            prompt_t("IP confirm delay", "number")?
+        })
+        .ip_allow_private({
+            println!();
+            println!(r#"Next, specify whether to publish resolved IPs which are not publicly routable, such as private or CGNAT (100.64.0.0/10) addresses. Otherwise, updates with them are refused."#);
+            println!(r#" > default: no"#);
+            prompt_yes_or_no("allow private IPs?", "y/N")?
+        })
+        .ip_ipv6_suffix({
+            println!();
+            println!(r#"Next, if your ISP delegates an IPv6 prefix and you want to publish a stable address for this host, provide the host suffix to combine with the prefix of your resolved IPv6 address. Provide an interface identifier, or a MAC address which is expanded to EUI-64."#);
+            println!(r#" > examples: ::1234:5678:9abc:def0, 00:11:22:33:44:55"#);
+            println!(r#" > default: none"#);
+            prompt("IPv6 suffix", "string")?
+        })
+        .ip_ipv6_prefix_len({
+            println!();
+            println!(r#"Next, specify the length of the prefix kept from your resolved IPv6 address when combining it with a host suffix."#);
+            println!(r#" > examples: 48, 56, 64"#);
+            println!(r#" > default: 64"#);
+            prompt_t("IPv6 prefix length", "number")?
+        })
+        .ip_ipv6_filter({
+            println!();
+            println!(r#"Next, if your network interface has several global IPv6 addresses, provide a prefix or regular expression selecting the one to publish. Stable addresses are preferred over temporary ones."#);
+            println!(r#" > examples: 2001:db8:1::/48, ^2001:db8:1:"#);
+            println!(r#" > default: none"#);
+            prompt("IPv6 filter", "string")?
+        })
+        .ip_bind({
+            println!();
+            println!(r#"Next, if your host has several network connections, provide a local address or network interface to send Cloudflare requests and IP detection from."#);
+            println!(r#" > examples: 192.168.1.10, eth1"#);
+            println!(r#" > default: chosen by the OS"#);
+            prompt("bind address", "string")?
         });
 
     // Save
@@ -143,5 +387,25 @@ async fn build() -> Result<()> {
 
 #[tracing::instrument(level = "trace", skip_all)]
 async fn show(opts: &ConfigOpts) -> Result<()> {
-    Ok(println!("{opts}"))
+    println!("{opts}");
+    Ok(())
+}
+
+#[tracing::instrument(level = "trace", skip_all)]
+async fn warnings(cli_opts: &WarningsOpts) -> Result<()> {
+    let warnings = cddns_core::util::warnings::surfaced();
+    if cli_opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&warnings)
+                .context("encoding warnings as JSON")?
+        );
+    } else if warnings.is_empty() {
+        println!("No warnings.");
+    } else {
+        for warning in warnings {
+            println!("{warning}");
+        }
+    }
+    Ok(())
 }
@@ -1,16 +1,38 @@
-use crate::cloudflare::{self, endpoints::update_record, models::Record};
-use crate::config::models::{ConfigOpts, ConfigOptsInventory};
-use crate::inventory::default_inventory_path;
-use crate::inventory::models::{Inventory, InventoryData};
+use crate::exit::InventoryFailure;
 use crate::util;
+use crate::util::daemon::DaemonOpts;
+use crate::util::github;
+use crate::util::health::Health;
+use crate::util::metrics::Metrics;
+use crate::util::report;
 use crate::util::scanner::{prompt_t, prompt_yes_or_no};
+use crate::util::signal::{Hangup, Shutdown};
 use anyhow::{Context, Result};
+use cddns_core::cloudflare::models::{NewRecord, Record};
+use cddns_core::cloudflare::{self, edge};
+use cddns_core::config::models::{ConfigOpts, ConfigOptsInventory};
+use cddns_core::error::Error;
+use cddns_core::inventory::audit::{self, AuditEntry};
+use cddns_core::inventory::default_inventory_path;
+use cddns_core::inventory::lookup::{self, CheckVia};
+use cddns_core::inventory::models::{Inventory, InventoryData};
+use cddns_core::inventory::policy::UnsupportedTypes;
+use cddns_core::inventory::queue::{self, QueuedUpdate, UpdateQueue};
+use cddns_core::inventory::scope::Scope;
+use cddns_core::inventory::selector::RecordSelector;
+use cddns_core::inventory::state::{AlertKind, State};
+use cddns_core::inventory::ttl;
+use cddns_core::ip;
+use cddns_core::ip::events::NetworkEvents;
+use cddns_core::ip::uplink::{Resolved, Resolver};
+use cddns_core::plugin::{self, Event, Notification, PluginKind};
+use cddns_core::util::{names, progress, release};
 use clap::{Args, Subcommand};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::Debug;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::path::PathBuf;
-use tokio::time::{self, Duration, MissedTickBehavior};
+use tokio::time::{self, Duration, Instant};
 use tracing::{debug, error, info, trace, warn};
 
 /// Build or manage your DNS record inventory.
@@ -30,13 +52,15 @@ enum InventorySubcommands {
     /// Print your inventory.
     Show(ShowOpts),
     /// Print erroneous DNS records.
-    Check,
+    Check(CheckOpts),
     /// Update outdated DNS records present in the inventory.
-    Update,
+    Update(UpdateOpts),
     /// Prune invalid DNS records present in the inventory.
     Prune,
+    /// Rewrite the name and ID comments of the inventory from Cloudflare.
+    RefreshNames,
     /// Continuously update DNS records on an interval.
-    Watch,
+    Watch(WatchOpts),
 }
 
 #[derive(Debug, Clone, Args)]
@@ -49,6 +73,159 @@ pub struct BuildOpts {
     pub clean: bool,
 }
 
+#[derive(Debug, Clone, Args)]
+pub struct CheckOpts {
+    /// Compare records against this address instead of the public IP, such
+    /// as from a machine other than the DDNS host. Repeat to give both an
+    /// IPv4 and IPv6 address.
+    #[clap(long, value_name = "address")]
+    pub expect_ip: Vec<IpAddr>,
+    /// Check records through the API or by resolving their names through
+    /// DNS, overriding `inventory.check_via`.
+    #[clap(long, value_name = "api|dns")]
+    pub via: Option<CheckVia>,
+    /// Only check records matching a name, ID or regex of the whole name.
+    /// Repeat to check several records.
+    #[clap(long, value_name = "name|id|regex")]
+    pub record: Vec<String>,
+    /// Update outdated records if any are found, as with `inventory update`.
+    #[clap(long)]
+    pub fix: bool,
+    /// Also prune invalid records when fixing, as if
+    /// `inventory.prune_invalid` were true.
+    #[clap(long, requires = "fix")]
+    pub prune: bool,
+    /// Print GitHub Actions annotations for outdated and invalid records,
+    /// surfacing them in workflow summaries.
+    #[clap(long)]
+    pub github_annotations: bool,
+    /// Print a JSON report of the run on stdout when it ends, with counts,
+    /// changed records, resolved IPs and the duration. Logs are written to
+    /// stderr.
+    #[clap(long, conflicts_with = "github_annotations")]
+    pub summary_json: bool,
+}
+
+impl CheckOpts {
+    /// Apply the check method and pruning of invalid records, if any.
+    fn apply(&self, opts: ConfigOpts) -> ConfigOpts {
+        let mut builder = ConfigOpts::builder();
+        builder.merge(opts);
+        if self.via.is_some() {
+            builder.inventory_check_via(self.via);
+        }
+        if self.prune {
+            builder.inventory_prune_invalid(Some(true));
+        }
+        builder.build()
+    }
+
+    /// Return the expected addresses, if any.
+    fn expected(&self) -> Resolved {
+        let mut expected = Resolved::default();
+        for ip in self.expect_ip.iter() {
+            match ip {
+                IpAddr::V4(ip) => expected.ipv4 = Some(*ip),
+                IpAddr::V6(ip) => expected.ipv6 = Some(*ip),
+            }
+        }
+        expected
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct UpdateOpts {
+    #[clap(flatten)]
+    pub family: FamilyOpts,
+    /// Publish this IPv4 address to all matching A records, instead of
+    /// resolving the public IPv4 address.
+    #[clap(long, value_name = "address")]
+    pub ipv4_address: Option<Ipv4Addr>,
+    /// Publish this IPv6 address to all matching AAAA records, instead of
+    /// resolving the public IPv6 address.
+    #[clap(long, value_name = "address")]
+    pub ipv6_address: Option<Ipv6Addr>,
+    /// Create records missing from Cloudflare with the public IP, as if
+    /// `inventory.create_missing` were true.
+    #[clap(long)]
+    pub create_missing: bool,
+    /// Only update records matching a name, ID or regex of the whole name.
+    /// Repeat to update several records.
+    #[clap(long, value_name = "name|id|regex")]
+    pub record: Vec<String>,
+    /// Prune invalid records from the inventory in the same run, as if
+    /// `inventory.prune_invalid` were true.
+    #[clap(long)]
+    pub prune: bool,
+    /// Print a JSON report of the run on stdout when it ends, with counts,
+    /// changed records, resolved IPs and the duration. Logs are written to
+    /// stderr.
+    #[clap(long)]
+    pub summary_json: bool,
+    /// Produce no output unless records changed or an error occurred, as
+    /// cron expects.
+    #[clap(long)]
+    pub changes_only: bool,
+}
+
+impl UpdateOpts {
+    /// Apply the creation of missing records and pruning of invalid
+    /// records, if enabled.
+    fn apply(&self, opts: ConfigOpts) -> ConfigOpts {
+        let mut builder = ConfigOpts::builder();
+        builder.merge(self.family.apply(opts));
+        if self.create_missing {
+            builder.inventory_create_missing(Some(true));
+        }
+        if self.prune {
+            builder.inventory_prune_invalid(Some(true));
+        }
+        builder.build()
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct WatchOpts {
+    #[clap(flatten)]
+    pub family: FamilyOpts,
+    /// Run a single iteration and exit, for supervisors which handle
+    /// scheduling externally.
+    #[clap(long, conflicts_with = "background")]
+    pub once: bool,
+    /// Print a JSON report of the iteration on stdout when it ends, with
+    /// counts, changed records, resolved IPs and the duration. Logs are
+    /// written to stderr.
+    #[clap(long, requires = "once")]
+    pub summary_json: bool,
+    #[clap(flatten)]
+    pub daemon: DaemonOpts,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct FamilyOpts {
+    /// Only handle A records, as if `inventory.ipv6` were false.
+    #[clap(long, conflicts_with = "ipv6_only")]
+    pub ipv4_only: bool,
+    /// Only handle AAAA records, as if `inventory.ipv4` were false.
+    #[clap(long)]
+    pub ipv6_only: bool,
+}
+
+impl FamilyOpts {
+    /// Apply the restriction to an address family, if any.
+    fn apply(&self, opts: ConfigOpts) -> ConfigOpts {
+        let mut builder = ConfigOpts::builder();
+        builder.merge(opts);
+        if self.ipv4_only {
+            builder.inventory_ipv6(Some(false));
+        }
+        if self.ipv6_only {
+            builder.inventory_ipv4(Some(false));
+        }
+        builder.build()
+    }
+}
+
 #[derive(Debug, Clone, Args)]
 pub struct ShowOpts {
     /// Output the inventory without post-processing.
@@ -57,11 +234,69 @@ pub struct ShowOpts {
 }
 
 impl InventoryCmd {
+    /// Return the daemon options, if `watch` should run as a daemon or
+    /// service.
+    pub fn daemon(&self) -> Option<&DaemonOpts> {
+        match self.action {
+            InventorySubcommands::Watch(ref watch_opts)
+                if watch_opts.daemon.daemon || watch_opts.daemon.service =>
+            {
+                Some(&watch_opts.daemon)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns whether only changes are logged, with `update --changes-only`.
+    pub fn changes_only(&self) -> bool {
+        match self.action {
+            InventorySubcommands::Update(ref update_opts) => {
+                update_opts.changes_only
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether the command runs continuously, with `watch`.
+    pub fn watching(&self) -> bool {
+        matches!(self.action, InventorySubcommands::Watch(_))
+    }
+
+    /// Returns whether a JSON report is printed on stdout, with
+    /// `--summary-json`.
+    pub fn summary_json(&self) -> bool {
+        match self.action {
+            InventorySubcommands::Check(ref check_opts) => {
+                check_opts.summary_json
+            }
+            InventorySubcommands::Update(ref update_opts) => {
+                update_opts.summary_json
+            }
+            InventorySubcommands::Watch(ref watch_opts) => {
+                watch_opts.summary_json
+            }
+            _ => false,
+        }
+    }
+
     #[tracing::instrument(level = "trace", skip_all)]
     pub async fn run(self, opts: ConfigOpts) -> Result<()> {
         // Apply CLI configuration layering
         let cli_opts = ConfigOpts::builder().inventory(Some(self.cfg)).build();
-        let opts = ConfigOpts::builder().merge(opts).merge(cli_opts).build();
+        let opts = ConfigOpts::builder()
+            .merge(opts)
+            .merge(cli_opts.clone())
+            .build();
+
+        // Refuse write operations while offline
+        match self.action {
+            InventorySubcommands::Update(_)
+            | InventorySubcommands::Prune
+            | InventorySubcommands::Watch(_) => {
+                cloudflare::cache::ensure_online("modify records")?
+            }
+            _ => {}
+        }
 
         // Run
         match self.action {
@@ -71,10 +306,101 @@ impl InventoryCmd {
             InventorySubcommands::Show(show_opts) => {
                 show(&opts, &show_opts).await
             }
-            InventorySubcommands::Check => check(&opts).await.map(|_| ()),
-            InventorySubcommands::Update => update(&opts).await,
+            InventorySubcommands::Check(check_opts) => {
+                if check_opts.summary_json {
+                    report::start("check");
+                }
+                let opts = check_opts.apply(opts);
+                let scope = Scope::records(&check_opts.record);
+                if check_opts.fix {
+                    cloudflare::cache::ensure_online("modify records")?
+                }
+                if opts.inventory.check_via == Some(CheckVia::Dns) {
+                    if let Some(result) =
+                        check_via_dns(&opts, check_opts.expected(), &scope)
+                            .await?
+                    {
+                        if result.outdated.is_empty() || !check_opts.fix {
+                            result.report();
+                            if check_opts.github_annotations {
+                                result.annotate();
+                            }
+                        }
+                        if result.outdated.is_empty() {
+                            return Ok(());
+                        } else if !check_opts.fix {
+                            return Err(InventoryFailure {
+                                outdated: result.outdated.len(),
+                                invalid: 0,
+                            }
+                            .into());
+                        }
+                    }
+                }
+                let result =
+                    check(&opts, check_opts.expected(), &scope).await?;
+                result.report();
+                if check_opts.github_annotations {
+                    result.annotate();
+                }
+                if result.outdated.is_empty() && result.invalid.is_empty() {
+                    Ok(())
+                } else if check_opts.fix {
+                    let (_, invalid) =
+                        __fix(&opts, check_opts.expected(), result).await?;
+                    if invalid == 0 {
+                        Ok(())
+                    } else {
+                        error!(
+                            "{invalid} invalid records remain, use `--prune` \
+                            to prune them"
+                        );
+                        Err(InventoryFailure {
+                            outdated: 0,
+                            invalid,
+                        }
+                        .into())
+                    }
+                } else {
+                    Err(InventoryFailure {
+                        outdated: result.outdated.len(),
+                        invalid: result.invalid.len(),
+                    }
+                    .into())
+                }
+            }
+            InventorySubcommands::Update(update_opts) => {
+                if update_opts.summary_json {
+                    report::start("update");
+                }
+                let fixed = Resolved {
+                    ipv4: update_opts.ipv4_address,
+                    ipv6: update_opts.ipv6_address,
+                };
+                let scope = Scope::records(&update_opts.record);
+                let changed =
+                    update(&update_opts.apply(opts), fixed, &scope).await?;
+                if update_opts.changes_only && changed == 0 {
+                    util::logging::discard();
+                }
+                Ok(())
+            }
             InventorySubcommands::Prune => prune(&opts).await,
-            InventorySubcommands::Watch => watch(&opts).await,
+            InventorySubcommands::RefreshNames => refresh_names(&opts).await,
+            InventorySubcommands::Watch(watch_opts) => {
+                if watch_opts.summary_json {
+                    report::start("watch");
+                }
+                let reload = || -> Result<ConfigOpts> {
+                    let opts = ConfigOpts::builder()
+                        .merge(util::layers::load()?)
+                        .merge(cli_opts.clone())
+                        .build();
+                    Ok(watch_opts.family.apply(opts))
+                };
+                watch(&watch_opts.family.apply(opts), watch_opts.once, reload)
+                    .await
+            }
         }
     }
 }
@@ -83,15 +409,13 @@ impl InventoryCmd {
 pub async fn build(opts: &ConfigOpts, cli_opts: &BuildOpts) -> Result<()> {
     info!("getting ready, please wait...");
     // Get zones and records to build inventory from
-    let token = opts
-        .verify.token.as_ref()
-        .context("no token was provided, need help? see https://github.com/simbleau/cddns#readme")?;
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
     trace!("retrieving cloudflare resources...");
     let mut all_zones = cloudflare::endpoints::zones(&token).await?;
-    crate::cmd::list::retain_zones(&mut all_zones, opts)?;
+    cloudflare::filters::retain_zones(&mut all_zones, opts)?;
     let mut all_records =
         cloudflare::endpoints::records(&all_zones, &token).await?;
-    crate::cmd::list::retain_records(&mut all_records, opts)?;
+    cloudflare::filters::retain_records(&mut all_records, opts)?;
 
     // Sort by name
     all_zones.sort_by_key(|z| z.name.to_owned());
@@ -222,11 +546,7 @@ pub async fn build(opts: &ConfigOpts, cli_opts: &BuildOpts) -> Result<()> {
 #[tracing::instrument(level = "trace", skip_all)]
 pub async fn show(opts: &ConfigOpts, cli_opts: &ShowOpts) -> Result<()> {
     info!("retrieving, please wait...");
-    let inventory_path = opts
-        .inventory
-        .path
-        .clone()
-        .unwrap_or_else(default_inventory_path);
+    let inventory_path = inventory_path(opts)?;
     let inventory = Inventory::from_file(inventory_path).await?;
 
     if inventory.data.is_empty() {
@@ -243,27 +563,71 @@ pub async fn show(opts: &ConfigOpts, cli_opts: &ShowOpts) -> Result<()> {
     Ok(())
 }
 
+/// Rewrite the alias comments of the inventory, such as after records or
+/// zones were renamed.
+#[tracing::instrument(level = "trace", skip_all)]
+pub async fn refresh_names(opts: &ConfigOpts) -> Result<()> {
+    info!("refreshing names, please wait...");
+    let inventory_path = inventory_path(opts)?;
+    let inventory = Inventory::from_file(inventory_path).await?;
+
+    if inventory.data.is_empty() {
+        warn!("inventory is empty");
+        return Ok(());
+    }
+    // Comments are dropped when parsing, and regenerated when saving
+    inventory.save(opts, true, true).await?;
+    info!(path = %inventory.path.display(), "refreshed inventory names");
+    Ok(())
+}
+
+/// Return the inventory path, which is only missing if config files are
+/// ignored and none was given.
+pub fn inventory_path(opts: &ConfigOpts) -> Result<PathBuf> {
+    Ok(opts.inventory.path.clone().ok_or_else(|| {
+        Error::Config(
+            "no inventory path was provided, set CDDNS_INVENTORY_PATH or use \
+            `--path`"
+                .into(),
+        )
+    })?)
+}
+
+/// Load the inventory, keeping only the entries in scope.
+async fn scoped_inventory(
+    opts: &ConfigOpts,
+    scope: &Scope,
+) -> Result<Inventory> {
+    let inventory_path = inventory_path(opts)?;
+    let mut inventory = Inventory::from_file(inventory_path).await?;
+    inventory
+        .data
+        .retain(|zone, record| scope.contains(&opts.inventory, zone, record));
+    Ok(inventory)
+}
+
 #[tracing::instrument(level = "trace", skip_all)]
-pub async fn check(opts: &ConfigOpts) -> Result<CheckResult> {
-    info!("checking records, please wait...");
+pub async fn check(
+    opts: &ConfigOpts,
+    fixed: Resolved,
+    scope: &Scope,
+) -> Result<CheckResult> {
+    info!(target: progress::TARGET, "checking records, please wait...");
     // Get inventory
     trace!("refreshing inventory...");
-    let inventory_path = opts
-        .inventory
-        .path
-        .clone()
-        .unwrap_or_else(default_inventory_path);
-    let inventory = Inventory::from_file(inventory_path).await?;
+    let inventory = scoped_inventory(opts, scope).await?;
 
     trace!("retrieving cloudflare resources...");
     // Token is required to fix inventory record.
-    let token = opts
-        .verify.token.as_ref()
-        .context("no token was provided, need help? see https://github.com/simbleau/cddns#readme")?;
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
 
     // End early if inventory is empty
     if inventory.data.is_empty() {
-        warn!("inventory is empty");
+        if *scope == Scope::All {
+            warn!("inventory is empty");
+        } else {
+            debug!(?scope, "no inventory entries in scope");
+        }
         return Ok(CheckResult::default());
     }
     // Get cloudflare records and zones
@@ -273,88 +637,175 @@ pub async fn check(opts: &ConfigOpts) -> Result<CheckResult> {
 
     // Match zones and records
     trace!("validating records...");
-    let mut ipv4: Option<Ipv4Addr> = None;
-    let mut ipv6: Option<Ipv6Addr> = None;
+    let ipv4_enabled = opts.inventory.ipv4.context("no default ipv4 option")?;
+    let ipv6_enabled = opts.inventory.ipv6.context("no default ipv6 option")?;
+    let pair_records = opts
+        .inventory
+        .pair_records
+        .context("no default pair records option")?;
+    let unsupported_types = opts
+        .inventory
+        .unsupported_types
+        .context("no default unsupported types option")?;
+    let mut resolver = Resolver::new(&opts.ip).fixed(fixed);
     let (mut valid, mut outdated, mut invalid) = (vec![], vec![], vec![]);
+    let mut suspicious = vec![];
+    let mut checked = HashSet::new();
     for (ref inv_zone, ref inv_records) in inventory.data.into_iter() {
+        let (valid_amt, outdated_amt, invalid_amt, suspicious_amt) =
+            (valid.len(), outdated.len(), invalid.len(), suspicious.len());
         for inv_record in inv_records {
-            let cf_record = records.iter().find(|r| {
-                (r.zone_id == *inv_zone || r.zone_name == *inv_zone)
-                    && (r.id == *inv_record || r.name == *inv_record)
-            });
-            match cf_record {
-                Some(cf_record) => {
-                    let ip = match cf_record.record_type.as_str() {
-                        "A" => {
-                            match ipv4 {
-                                Some(ip) => ip,
-                                None => {
-                                    trace!("resolving ipv4...");
-                                    let ip = public_ip::addr_v4()
-                                        .await
-                                        .context("could not resolve public ipv4 needed for A record")?;
-                                    ipv4.replace(ip);
-                                    ip
-                                }
-                            }
-                        }
+            let selector = RecordSelector::parse(inv_record)?;
+            let mut cf_records = if pair_records {
+                selector.select_paired(inv_zone, &records)
+            } else {
+                selector.select(inv_zone, &records)
+            };
+            if cf_records.is_empty() && scope.names(inv_record) {
+                // Invalid record, no match on zone and record
+                error!(zone = inv_zone, record = inv_record, "invalid");
+                if let Some(wildcard) =
+                    selector.covering_wildcard(inv_zone, &records)
+                {
+                    warn!(
+                        record = inv_record,
+                        wildcard = wildcard.name,
+                        "name is only covered by a wildcard record, add the \
+                        wildcard name to the inventory to update it"
+                    );
+                }
+                invalid.push((inv_zone.clone(), inv_record.clone()));
+            }
+            cf_records.retain(|cf_record| scope.selects(cf_record));
+            for cf_record in cf_records {
+                // Skip records selected by more than one entry
+                if !checked.insert(cf_record.id.clone()) {
+                    continue;
+                }
+                // Skip records of disabled address families
+                let enabled = match cf_record.record_type.as_str() {
+                    "A" => ipv4_enabled,
+                    "AAAA" => ipv6_enabled,
+                    _ => true,
+                };
+                if !enabled {
+                    debug!(
+                        name = cf_record.name,
+                        id = cf_record.id,
+                        "skipping {} record of a disabled address family",
+                        cf_record.record_type
+                    );
+                    continue;
+                }
+                let ip = match cf_record.record_type.as_str() {
+                    "A" => resolver
+                        .ipv4(cf_record)
+                        .await
+                        .context("could not resolve public ipv4 needed for A record")?
                         .to_string(),
-                        "AAAA" => {
-                            match ipv6 {
-                                Some(ip) => ip,
-                                None => {
-                                    trace!("resolving ipv6...");
-                                    let ip = public_ip::addr_v6()
-                                        .await
-                                        .context("could not resolve public ipv6 needed for AAAA record")?;
-                                    ipv6.replace(ip);
-                                    ip
-                                }
+                    "AAAA" => resolver
+                        .ipv6(cf_record)
+                        .await
+                        .context("could not resolve public ipv6 needed for AAAA record")?
+                        .to_string(),
+                    record_type => match unsupported_types {
+                        UnsupportedTypes::Skip => {
+                            warn!(
+                                name = cf_record.name,
+                                id = cf_record.id,
+                                "skipping unsupported {record_type} record"
+                            );
+                            continue;
+                        }
+                        UnsupportedTypes::Error => {
+                            return Err(Error::UnsupportedRecord {
+                                name: cf_record.name.clone(),
+                                record_type: record_type.to_string(),
                             }
+                            .into());
                         }
-                        .to_string(),
-                        _ => unimplemented!(),
-                    };
-                    if cf_record.content == ip {
-                        // IP Match
-                        debug!(
+                        UnsupportedTypes::Static => {
+                            debug!(
+                                name = cf_record.name,
+                                id = cf_record.id,
+                                "valid, static {record_type} record"
+                            );
+                            valid.push(cf_record.clone());
+                            continue;
+                        }
+                    },
+                };
+                ttl::check(cf_record);
+                // Proxied records are compared by their origin value
+                if cf_record.content == ip {
+                    // IP Match
+                    debug!(
+                        name = cf_record.name,
+                        id = cf_record.id,
+                        content = cf_record.content,
+                        proxied = cf_record.proxied,
+                        "valid"
+                    );
+                    valid.push(cf_record.clone());
+                } else {
+                    // IP outdated
+                    warn!(
+                        name = cf_record.name,
+                        id = cf_record.id,
+                        content = cf_record.content,
+                        proxied = cf_record.proxied,
+                        "outdated"
+                    );
+                    if cf_record.proxied
+                        && cf_record.content.parse().is_ok_and(edge::is_edge)
+                    {
+                        warn!(
                             name = cf_record.name,
-                            id = cf_record.id,
-                            content = cf_record.content,
-                            "valid"
+                            "proxied record contains a Cloudflare edge \
+                            address instead of its origin"
                         );
-                        valid.push(cf_record.clone());
-                    } else {
-                        // IP outdated
+                    }
+                    if cf_record.content.parse().is_ok_and(ip::is_bogon) {
                         warn!(
                             name = cf_record.name,
                             id = cf_record.id,
                             content = cf_record.content,
-                            "outdated"
+                            "suspicious, the content is a private, loopback \
+                            or documentation address"
                         );
-                        outdated.push(cf_record.clone());
+                        suspicious.push(cf_record.clone());
                     }
-                }
-                None => {
-                    // Invalid record, no match on zone and record
-                    error!(zone = inv_zone, record = inv_record, "invalid");
-                    invalid.push((inv_zone.clone(), inv_record.clone()));
+                    outdated.push(cf_record.clone());
                 }
             }
         }
+        // Log zone subtotal
+        info!(
+            target: progress::TARGET,
+            zone = inv_zone,
+            valid = valid.len() - valid_amt,
+            outdated = outdated.len() - outdated_amt,
+            invalid = invalid.len() - invalid_amt,
+            suspicious = suspicious.len() - suspicious_amt,
+            "zone summary"
+        );
     }
 
     let result = CheckResult {
         valid,
         outdated,
         invalid,
+        suspicious,
+        resolved: resolver.resolved().clone(),
     };
 
     // Log summary
     info!(
+        target: progress::TARGET,
         valid = result.valid.len(),
         outdated = result.outdated.len(),
         invalid = result.invalid.len(),
+        suspicious = result.suspicious.len(),
         "summary"
     );
     if !result.invalid.is_empty() {
@@ -369,37 +820,360 @@ pub async fn check(opts: &ConfigOpts) -> Result<CheckResult> {
             result.outdated.len()
         )
     }
+    if !result.suspicious.is_empty() {
+        warn!(
+            "inventory contains {} suspicious records, which a bad IP source \
+            may have published",
+            result.suspicious.len()
+        )
+    }
     if result.invalid.is_empty() && result.outdated.is_empty() {
         debug!("inventory contains {} valid records", result.valid.len())
     }
     Ok(result)
 }
 
+/// Check inventory names by resolving them through DNS, without using the
+/// Cloudflare API. Returns `None` if some entries cannot be checked this
+/// way, such as IDs, tags and names which do not resolve.
 #[tracing::instrument(level = "trace", skip_all)]
-pub async fn update(opts: &ConfigOpts) -> Result<()> {
-    let CheckResult { mut outdated, .. } = check(opts).await?;
+pub async fn check_via_dns(
+    opts: &ConfigOpts,
+    fixed: Resolved,
+    scope: &Scope,
+) -> Result<Option<DnsCheckResult>> {
+    info!(
+        target: progress::TARGET,
+        "checking records through DNS, please wait..."
+    );
+    // Entries may select filtered records by ID or tag
+    if let Scope::Records(_) = scope {
+        info!(
+            target: progress::TARGET,
+            "records are filtered, checking records through the API"
+        );
+        return Ok(None);
+    }
+    let inventory = scoped_inventory(opts, scope).await?;
+    if inventory.data.is_empty() {
+        if *scope == Scope::All {
+            warn!("inventory is empty");
+        } else {
+            debug!(?scope, "no inventory entries in scope");
+        }
+        return Ok(Some(DnsCheckResult::default()));
+    }
+
+    let ipv4_enabled = opts.inventory.ipv4.context("no default ipv4 option")?;
+    let ipv6_enabled = opts.inventory.ipv6.context("no default ipv6 option")?;
+    let local = ip::bind_address(&opts.ip)?
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    let mut resolver = Resolver::new(&opts.ip).fixed(fixed);
+    let (mut valid, mut outdated) = (vec![], vec![]);
+    for (inv_zone, inv_records) in inventory.data.into_iter() {
+        for inv_record in inv_records {
+            let name = inv_record.as_str();
+            let ascii = names::to_ascii(name);
+            let uplink = match resolver.uplink_of(name) {
+                Some(uplink) if lookup::is_hostname(&ascii) => uplink,
+                _ => {
+                    info!(
+                        target: progress::TARGET,
+                        zone = inv_zone,
+                        record = name,
+                        "entry cannot be checked through DNS, \
+                        checking records through the API"
+                    );
+                    return Ok(None);
+                }
+            };
+            let published =
+                match lookup::lookup(&ascii, ipv4_enabled, ipv6_enabled, local)
+                    .await
+                {
+                    Ok(published) if !published.is_empty() => published,
+                    Ok(_) => {
+                        info!(
+                            target: progress::TARGET,
+                            name,
+                            "name does not resolve, \
+                            checking records through the API"
+                        );
+                        return Ok(None);
+                    }
+                    Err(e) => {
+                        warn!(
+                            name,
+                            "unable to resolve name, \
+                            checking records through the API: {e}"
+                        );
+                        return Ok(None);
+                    }
+                };
+            // Proxied names resolve to the Cloudflare edge
+            let mut resolved_ips = published
+                .ipv4
+                .iter()
+                .map(|ip| IpAddr::from(*ip))
+                .chain(published.ipv6.iter().map(|ip| IpAddr::from(*ip)));
+            if resolved_ips.any(edge::is_edge) {
+                info!(
+                    target: progress::TARGET,
+                    name,
+                    "name is proxied by Cloudflare, \
+                    checking origin values through the API"
+                );
+                return Ok(None);
+            }
+            let mut current = true;
+            if !published.ipv4.is_empty() {
+                let ip = resolver.ipv4_on(uplink).await.context(
+                    "could not resolve public ipv4 needed for A record",
+                )?;
+                current &= published.ipv4.iter().all(|p| *p == ip);
+            }
+            if !published.ipv6.is_empty() {
+                let ip = resolver.ipv6_on(uplink).await.context(
+                    "could not resolve public ipv6 needed for AAAA record",
+                )?;
+                current &= published.ipv6.iter().all(|p| *p == ip);
+            }
+            if current {
+                debug!(name, "valid");
+                valid.push(inv_record.clone());
+            } else {
+                warn!(name, ?published, "outdated");
+                outdated.push(inv_record.clone());
+            }
+        }
+    }
+
+    let result = DnsCheckResult {
+        valid,
+        outdated,
+        resolved: resolver.resolved().clone(),
+    };
+    info!(
+        target: progress::TARGET,
+        valid = result.valid.len(),
+        outdated = result.outdated.len(),
+        "summary"
+    );
+    if !result.outdated.is_empty() {
+        warn!(
+            "inventory contains {} outdated names",
+            result.outdated.len()
+        )
+    }
+    Ok(Some(result))
+}
+
+/// Update outdated records, returning the number of updated records.
+#[tracing::instrument(level = "trace", skip_all)]
+pub async fn update(
+    opts: &ConfigOpts,
+    fixed: Resolved,
+    scope: &Scope,
+) -> Result<usize> {
+    // Only use the API once DNS shows outdated records
+    if opts.inventory.check_via == Some(CheckVia::Dns) {
+        if let Some(result) = check_via_dns(opts, fixed, scope).await? {
+            result.report();
+            if result.outdated.is_empty() {
+                let mut notifications = vec![];
+                State::update(|state| {
+                    state.observe_resolved(&result.resolved);
+                    notifications = alert(state, &[], &result.valid);
+                })
+                .await;
+                notify(opts, &notifications).await;
+                info!(target: progress::TARGET, "inventory is up to date");
+                return Ok(0);
+            }
+        }
+    }
+    let result = check(opts, fixed, scope).await?;
+    result.report();
+    __fix(opts, fixed, result).await.map(|(changed, _)| changed)
+}
+
+/// Fix the problems found by a check, creating, pruning and updating records
+/// as configured. Returns the number of updated and created records, and the
+/// number of invalid entries left in the inventory.
+#[tracing::instrument(level = "trace", skip_all)]
+async fn __fix(
+    opts: &ConfigOpts,
+    fixed: Resolved,
+    result: CheckResult,
+) -> Result<(usize, usize)> {
+    let CheckResult {
+        valid,
+        mut outdated,
+        mut invalid,
+        resolved,
+        ..
+    } = result;
+    let mut recovered: Vec<String> =
+        valid.into_iter().map(|record| record.name).collect();
+
+    // Create missing records
+    let create_missing = opts
+        .inventory
+        .create_missing
+        .context("no default create missing option")?;
+    let mut created = vec![];
+    if create_missing && !invalid.is_empty() {
+        created = __create(opts, &invalid, fixed)
+            .await
+            .context("error creating missing records")?;
+        created.iter().for_each(report::created);
+    }
+
+    // Prune invalid records, from the same check
+    let prune_invalid = opts
+        .inventory
+        .prune_invalid
+        .context("no default prune invalid option")?;
+    invalid.retain(|(_, r)| {
+        let missing = !created.iter().any(|c| names::same(&c.name, r));
+        if !missing {
+            recovered.push(r.clone());
+        }
+        missing
+    });
+    if prune_invalid && !invalid.is_empty() {
+        let new_inventory = __prune(opts, &invalid)
+            .await
+            .context("error pruning invalid records")?;
+        invalid.retain(|(z, r)| {
+            let kept = new_inventory.data.contains(z, r);
+            if !kept {
+                report::pruned(z, r);
+                recovered.push(r.clone());
+            }
+            kept
+        });
+    }
 
     // Update outdated records
+    let (mut updated, mut deferred) = (vec![], vec![]);
     if !outdated.is_empty() {
-        let fixed_record_ids = __update(opts, &outdated)
+        (updated, deferred) = __update(opts, &outdated, fixed)
             .await
             .context("error updating outdated records")?;
-        outdated.retain_mut(|r| !fixed_record_ids.contains(&r.id));
+        // Deferred records are neither failing nor recovered
+        outdated.retain(|r| {
+            !updated.iter().any(|u| u.record_id == r.id)
+                && !deferred.contains(&r.id)
+        });
     }
 
+    // Save state, alerting once for records which remain outdated or invalid
+    recovered.extend(updated.iter().map(|update| update.name.clone()));
+    let failing: Vec<(String, AlertKind)> = outdated
+        .iter()
+        .map(|record| (record.name.clone(), AlertKind::Outdated))
+        .chain(invalid.iter().map(|(_, r)| (r.clone(), AlertKind::Invalid)))
+        .collect();
+    let mut notifications = vec![];
+    State::update(|state| {
+        state.observe_resolved(&resolved);
+        for update in updated.iter() {
+            state.record_update(update);
+        }
+        notifications = alert(state, &failing, &recovered);
+    })
+    .await;
+    notify(opts, &notifications).await;
+
     // Log status
-    if outdated.is_empty() {
-        info!("inventory is up to date");
+    let unpruned = if prune_invalid { invalid.len() } else { 0 };
+    if outdated.is_empty() && unpruned == 0 {
+        if deferred.is_empty() {
+            info!(target: progress::TARGET, "inventory is up to date");
+        } else {
+            info!(
+                target: progress::TARGET,
+                "{} outdated records deferred until the cooldown ends",
+                deferred.len()
+            );
+        }
     } else {
-        error!("{} outdated records remain", outdated.len());
+        if !outdated.is_empty() {
+            error!("{} outdated records remain", outdated.len());
+        }
+        if unpruned > 0 {
+            error!("{unpruned} invalid records remain");
+        }
+        return Err(InventoryFailure {
+            outdated: outdated.len(),
+            invalid: unpruned,
+        }
+        .into());
     }
 
-    Ok(())
+    Ok((updated.len() + created.len(), invalid.len()))
+}
+
+/// Raise the alerts of failing records which were not already raised, and
+/// clear those of recovered records, logging each change once rather than on
+/// every check. Returns the notifications of the changes.
+fn alert(
+    state: &mut State,
+    failing: &[(String, AlertKind)],
+    recovered: &[String],
+) -> Vec<Notification> {
+    let mut notifications = vec![];
+    // Records of both types share a name, which recovers once neither fails
+    let recovered = recovered
+        .iter()
+        .filter(|name| !failing.iter().any(|(failing, _)| failing == *name));
+    for name in recovered {
+        if let Some(alert) = state.recover(name) {
+            info!(
+                name,
+                since = %alert.since,
+                "record recovered, it was {}", alert.kind
+            );
+            notifications.push(Notification {
+                event: Event::Recovered,
+                name: name.clone(),
+                since: alert.since,
+            });
+        }
+    }
+    for (name, kind) in failing.iter() {
+        if let Some(alert) = state.alert(name, *kind) {
+            warn!(name, "record became {kind}");
+            notifications.push(Notification {
+                event: match kind {
+                    AlertKind::Outdated => Event::Outdated,
+                    AlertKind::Invalid => Event::Invalid,
+                },
+                name: name.clone(),
+                since: alert.since,
+            });
+        }
+    }
+    notifications
+}
+
+/// Send notifications to the configured and discovered notifier plugins.
+async fn notify(opts: &ConfigOpts, notifications: &[Notification]) {
+    if notifications.is_empty() {
+        return;
+    }
+    let notifiers =
+        plugin::discover(PluginKind::Notify, opts.inventory.notifiers.as_ref());
+    for notification in notifications.iter() {
+        plugin::notify(&notifiers, notification).await;
+    }
 }
 
 #[tracing::instrument(level = "trace", skip_all)]
 pub async fn prune(opts: &ConfigOpts) -> Result<()> {
-    let CheckResult { mut invalid, .. } = check(opts).await?;
+    let CheckResult { mut invalid, .. } =
+        check(opts, Resolved::default(), &Scope::All).await?;
 
     // Prune invalid records
     if !invalid.is_empty() {
@@ -412,45 +1186,319 @@ pub async fn prune(opts: &ConfigOpts) -> Result<()> {
         info!("inventory contains no invalid records");
     } else {
         error!("{} invalid records remain", invalid.len());
+        return Err(InventoryFailure {
+            outdated: 0,
+            invalid: invalid.len(),
+        }
+        .into());
     }
 
     Ok(())
 }
 
 #[tracing::instrument(level = "trace", skip_all)]
-pub async fn watch(opts: &ConfigOpts) -> Result<()> {
-    // Override force update flag with true, to make `watch` non-interactive.
-    let opts = ConfigOpts::builder()
-        .merge(opts.to_owned())
-        .inventory_force_update(Some(true))
-        .build();
-
-    // Get watch interval
-    let interval = Duration::from_millis(
-        opts.inventory
-            .watch_interval
-            .context("no default interval")?,
-    );
-    debug!(interval_ms = interval.as_millis());
+pub async fn watch(
+    opts: &ConfigOpts,
+    once: bool,
+    reload: impl Fn() -> Result<ConfigOpts>,
+) -> Result<()> {
+    // Override force flags with true, to make `watch` non-interactive.
+    let non_interactive = |opts: ConfigOpts| {
+        ConfigOpts::builder()
+            .merge(opts)
+            .inventory_force_update(Some(true))
+            .inventory_force_prune(Some(true))
+            .build()
+    };
+    let mut opts = non_interactive(opts.to_owned());
 
-    if interval.is_zero() {
-        loop {
-            if let Err(e) = update(&opts).await {
-                error!("{:?}", e);
+    let mut buckets = Bucket::all(&opts)?;
+    let mut metrics = Metrics::default();
+    if once {
+        let mut result = Ok(());
+        for bucket in buckets.iter_mut() {
+            match watch_tick(&opts, bucket).await {
+                Ok(updated) => metrics.record_success(updated),
+                Err(e) => {
+                    metrics.record_failure(&e);
+                    result = Err(e);
+                }
             }
         }
-    } else {
-        let mut timer = time::interval(interval);
-        timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
-        loop {
-            timer.tick().await;
+        write_metrics(&opts, &metrics).await;
+        return result;
+    }
+    let mut events = network_events(&opts);
+    let mut shutdown = Shutdown::new()?;
+    let mut hangup = Hangup::new()?;
+    let health = Health::default();
+    if let Some(addr) = opts.inventory.watch_listen {
+        health.serve(addr).await?;
+    }
+    loop {
+        // Condense routine logs into a summary every few checks
+        let summary_every = opts
+            .inventory
+            .watch_summary
+            .context("no default summary option")?;
+        progress::set_hidden(summary_every > 0);
+
+        // Wait for the next bucket, or a network change refreshing all
+        let next = buckets
+            .iter()
+            .map(|b| b.next)
+            .min()
+            .context("no watch buckets")?;
+        let network_changed = async {
+            match events.as_mut() {
+                Some(events) => events.changed().await,
+                None => std::future::pending().await,
+            }
+        };
+        let changed = tokio::select! {
+            _ = time::sleep_until(next) => false,
+            _ = network_changed => true,
+            _ = shutdown.recv() => {
+                info!("shutting down");
+                return Ok(());
+            }
+            _ = hangup.recv() => {
+                // Reloaded buckets are checked as if watch had started
+                info!("reloading configuration...");
+                match reload().and_then(|reloaded| {
+                    let reloaded = non_interactive(reloaded);
+                    Ok((Bucket::all(&reloaded)?, reloaded))
+                }) {
+                    Ok((reloaded_buckets, reloaded)) => {
+                        events = network_events(&reloaded);
+                        buckets = reloaded_buckets;
+                        opts = reloaded;
+                        info!("configuration reloaded");
+                    }
+                    Err(e) => error!(
+                        "unable to reload configuration, keeping the current \
+                        one: {e:?}"
+                    ),
+                }
+                continue;
+            }
+        };
+        if changed {
+            info!("network changed, checking records...");
+        } else {
             trace!("awoken");
-            if let Err(e) = update(&opts).await {
+        }
+        release::check_daily(env!("CARGO_PKG_VERSION")).await;
+        let now = Instant::now();
+        let mut stopping = false;
+        for bucket in buckets.iter_mut() {
+            if changed || bucket.next <= now {
+                bucket.next = Instant::now() + bucket.interval;
+                // Complete an in-flight update before shutting down
+                let attempt = watch_attempt(&opts, bucket, &mut metrics);
+                tokio::pin!(attempt);
+                tokio::select! {
+                    result = &mut attempt => result?,
+                    _ = shutdown.recv() => {
+                        info!("shutting down after the current check...");
+                        stopping = true;
+                        attempt.await?;
+                    }
+                }
+            }
+            if stopping {
+                info!("shutting down");
+                return Ok(());
+            }
+        }
+        // Ready once every bucket has been reconciled
+        if buckets.iter().all(|b| b.reconciled.is_some()) {
+            health.set_ready();
+        }
+        write_metrics(&opts, &metrics).await;
+        if summary_every > 0 && metrics.summary().checks >= summary_every {
+            let summary = metrics.take_summary();
+            info!(
+                checks = summary.checks,
+                updated = summary.updated,
+                failures = summary.failures,
+                outdated = metrics.outdated(),
+                "watch summary"
+            );
+        }
+        trace!("sleeping...");
+    }
+}
+
+/// Write metrics for the textfile collector if
+/// `inventory.watch_textfile_dir` is set.
+async fn write_metrics(opts: &ConfigOpts, metrics: &Metrics) {
+    if let Some(ref dir) = opts.inventory.watch_textfile_dir {
+        if let Err(e) = metrics.write(dir).await {
+            warn!("unable to write metrics: {e:?}");
+        }
+    }
+}
+
+/// Subscribe to network changes if `inventory.watch_events` is enabled.
+fn network_events(opts: &ConfigOpts) -> Option<NetworkEvents> {
+    match opts.inventory.watch_events {
+        Some(true) => match NetworkEvents::new() {
+            Ok(events) => Some(events),
+            Err(e) => {
+                warn!("unable to watch network changes, polling only: {e}");
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+/// A group of inventory entries refreshed by `watch` on its own interval.
+struct Bucket {
+    scope: Scope,
+    interval: Duration,
+    next: Instant,
+    reconciled: Option<Instant>,
+    /// The state observed at the last reconciliation.
+    observed: Option<State>,
+}
+
+impl Bucket {
+    /// Return the buckets of each group in `inventory.groups`, and of the
+    /// remaining entries on the watch interval.
+    fn all(opts: &ConfigOpts) -> Result<Vec<Bucket>> {
+        let interval = Duration::from_millis(
+            opts.inventory
+                .watch_interval
+                .context("no default interval")?,
+        );
+        debug!(interval_ms = interval.as_millis());
+        let immediate = opts
+            .inventory
+            .watch_immediate
+            .context("no default immediate option")?;
+        let bucket = |scope, interval| Bucket {
+            scope,
+            interval,
+            next: if immediate {
+                Instant::now()
+            } else {
+                Instant::now() + interval
+            },
+            reconciled: None,
+            observed: None,
+        };
+        let Some(groups) = opts.inventory.groups.as_ref() else {
+            return Ok(vec![bucket(Scope::All, interval)]);
+        };
+        let mut buckets = vec![bucket(Scope::Ungrouped, interval)];
+        for (name, group) in groups.iter() {
+            debug!(group = name, interval_ms = group.interval);
+            buckets.push(bucket(
+                Scope::Group(name.clone()),
+                Duration::from_millis(group.interval),
+            ));
+        }
+        Ok(buckets)
+    }
+}
+
+/// Run a watch tick for a bucket, counting consecutive failures and
+/// returning an error once `inventory.watch_max_failures` is reached.
+async fn watch_attempt(
+    opts: &ConfigOpts,
+    bucket: &mut Bucket,
+    metrics: &mut Metrics,
+) -> Result<()> {
+    let max_failures = opts
+        .inventory
+        .watch_max_failures
+        .context("no default max failures")?;
+    match watch_tick(opts, bucket).await {
+        Ok(updated) => metrics.record_success(updated),
+        Err(e) => {
+            // Remaining records were already logged
+            if e.downcast_ref::<InventoryFailure>().is_none() {
                 error!("{:?}", e);
             }
-            trace!("sleeping...");
+            let failures = metrics.record_failure(&e);
+            if max_failures > 0 && failures >= max_failures {
+                error!(failures, "too many consecutive failures, exiting");
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Update the records of a bucket, skipping Cloudflare entirely while the
+/// public IPs are unchanged since its last reconciliation, which is repeated
+/// at least every reconcile interval. Returns the number of updated records.
+async fn watch_tick(opts: &ConfigOpts, bucket: &mut Bucket) -> Result<usize> {
+    let reconcile_interval = Duration::from_millis(
+        opts.inventory
+            .reconcile_interval
+            .context("no default reconcile interval")?,
+    );
+    let fresh = bucket
+        .reconciled
+        .is_some_and(|at| at.elapsed() < reconcile_interval);
+    if let Some(observed) = bucket.observed.as_ref().filter(|_| fresh) {
+        if public_ips_unchanged(opts, observed).await {
+            debug!(
+                scope = ?bucket.scope,
+                "public IPs unchanged, skipping reconciliation"
+            );
+            return Ok(0);
+        }
+    }
+    bucket.reconciled.take();
+    let updated = update(opts, Resolved::default(), &bucket.scope).await?;
+    bucket.observed = State::load().await.ok();
+    bucket.reconciled.replace(Instant::now());
+    Ok(updated)
+}
+
+/// Returns whether the public IPs resolve to those observed in a state, for
+/// the `ip` section and each uplink.
+async fn public_ips_unchanged(opts: &ConfigOpts, state: &State) -> bool {
+    if state.ipv4.is_none() && state.ipv6.is_none() && state.uplinks.is_empty()
+    {
+        return false;
+    }
+    let resolver = Resolver::new(&opts.ip);
+    let default = Resolved {
+        ipv4: state.ipv4,
+        ipv6: state.ipv6,
+    };
+    let observed = std::iter::once((None, default)).chain(
+        state
+            .uplinks
+            .iter()
+            .map(|(name, ips)| (Some(name.as_str()), *ips)),
+    );
+    for (uplink, last) in observed {
+        // Skip uplinks which are no longer configured
+        let Ok(ip_opts) = resolver.opts(uplink) else {
+            continue;
+        };
+        if let Some(last) =
+            last.ipv4.filter(|_| opts.inventory.ipv4 != Some(false))
+        {
+            if ip::ipv4(&ip_opts).await.ok() != Some(last) {
+                return false;
+            }
+        }
+        if let Some(last) =
+            last.ipv6.filter(|_| opts.inventory.ipv6 != Some(false))
+        {
+            if ip::ipv6(&ip_opts).await.ok() != Some(last) {
+                return false;
+            }
         }
     }
+    true
 }
 
 #[derive(Debug, Default, Clone)]
@@ -458,17 +1506,90 @@ pub struct CheckResult {
     valid: Vec<Record>,
     outdated: Vec<Record>,
     invalid: Vec<(String, String)>,
+    /// Outdated records whose content should never have been published.
+    suspicious: Vec<Record>,
+    resolved: BTreeMap<Option<String>, Resolved>,
+}
+
+impl CheckResult {
+    /// Add the counts of the check to the `--summary-json` report.
+    fn report(&self) {
+        report::checked(
+            self.valid.len(),
+            self.outdated.len(),
+            self.invalid.len(),
+            self.suspicious.len(),
+            &self.resolved,
+        );
+    }
+
+    /// Print GitHub Actions annotations for outdated and invalid records.
+    fn annotate(&self) {
+        for record in self.outdated.iter() {
+            github::warning(
+                "Outdated DNS record",
+                &format!(
+                    "{} record '{}' in zone '{}' is outdated: {}",
+                    record.record_type,
+                    names::to_unicode(&record.name),
+                    names::to_unicode(&record.zone_name),
+                    record.content
+                ),
+            );
+        }
+        for (zone, record) in self.invalid.iter() {
+            github::error(
+                "Invalid inventory entry",
+                &format!(
+                    "'{record}' in zone '{zone}' does not match any \
+                    Cloudflare record"
+                ),
+            );
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct DnsCheckResult {
+    valid: Vec<String>,
+    outdated: Vec<String>,
+    resolved: BTreeMap<Option<String>, Resolved>,
+}
+
+impl DnsCheckResult {
+    /// Add the counts of the check to the `--summary-json` report.
+    fn report(&self) {
+        report::checked(
+            self.valid.len(),
+            self.outdated.len(),
+            0,
+            0,
+            &self.resolved,
+        );
+    }
+
+    /// Print GitHub Actions annotations for outdated records.
+    fn annotate(&self) {
+        for name in self.outdated.iter() {
+            github::warning(
+                "Outdated DNS record",
+                &format!("'{name}' does not resolve to the public IP"),
+            );
+        }
+    }
 }
 
-/// Update a list of outdated records, returning those ids which were
-/// successfully updated.
+/// Update a list of outdated records, returning those updates which were
+/// successfully applied, and the IDs of records whose update was deferred
+/// because they were updated within the cooldown.
 #[tracing::instrument(level = "trace", skip_all)]
 async fn __update(
     opts: &ConfigOpts,
-    outdated: &Vec<Record>,
-) -> Result<HashSet<String>> {
-    // Track fixed records
-    let mut updated_ids = HashSet::new();
+    outdated: &[Record],
+    fixed: Resolved,
+) -> Result<(Vec<QueuedUpdate>, Vec<String>)> {
+    // Track fixed and deferred records
+    let (mut updated, mut deferred) = (vec![], vec![]);
     // Fix outdated records
     if !outdated.is_empty() {
         let force = opts
@@ -486,79 +1607,251 @@ async fn __update(
             .unwrap_or(true)
         };
         if fix {
-            info!("updating {} records...", outdated.len());
-            let token = opts
-                .verify.token.as_ref()
-                .context("no token was provided, need help? see https://github.com/simbleau/cddns#readme")?;
-            let mut ipv4: Option<Ipv4Addr> = None;
-            let mut ipv6: Option<Ipv6Addr> = None;
+            let token =
+                opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
+            let cooldown = Duration::from_millis(
+                opts.inventory
+                    .update_cooldown
+                    .context("no default update cooldown")?,
+            );
+            let state = State::load().await.unwrap_or_default();
+            let mut resolver = Resolver::new(&opts.ip).fixed(fixed);
+            let mut queue = UpdateQueue::default()
+                .comment_template(opts.inventory.comment_template.clone());
             for cf_record in outdated.iter() {
-                let updated = match cf_record.record_type.as_str() {
-                    "A" => {
-                        update_record(
-                            &token,
-                            &cf_record.zone_id,
-                            &cf_record.id,
-                            ipv4.get_or_insert({
-                                trace!("resolving ipv4...");
-                                public_ip::addr_v4()
-                                    .await
-                                    .context("could not resolve ipv4 address")?
-                            })
-                            .to_string(),
-                        )
-                        .await
+                // Defer the update of records updated within the cooldown
+                if let Some(last) = state.records.get(&cf_record.id) {
+                    if last.elapsed() < cooldown {
+                        info!(
+                            name = cf_record.name,
+                            id = cf_record.id,
+                            last_update = %last.updated,
+                            "deferring update within cooldown"
+                        );
+                        deferred.push(cf_record.id.clone());
+                        continue;
                     }
-                    "AAAA" => {
-                        update_record(
-                            &token,
-                            &cf_record.zone_id,
-                            &cf_record.id,
-                            ipv6.get_or_insert({
-                                trace!("resolving ipv6...");
-                                public_ip::addr_v6()
-                                    .await
-                                    .context("could not resolve ipv6 address")?
-                            })
-                            .to_string(),
-                        )
+                }
+                let content = match cf_record.record_type.as_str() {
+                    "A" => resolver
+                        .ipv4(cf_record)
                         .await
-                    }
-                    _ => unimplemented!(),
+                        .context("could not resolve ipv4 address")?
+                        .to_string(),
+                    "AAAA" => resolver
+                        .ipv6(cf_record)
+                        .await
+                        .context("could not resolve ipv6 address")?
+                        .to_string(),
+                    // Records of other types are never found outdated
+                    _ => continue,
                 };
-                if let Err(err) = updated {
-                    debug!("{err:?}");
-                    error!(
-                        id = cf_record.id,
-                        name = cf_record.name,
-                        "unsuccessful record update"
-                    );
-                } else {
-                    info!(
-                        id = cf_record.id,
-                        name = cf_record.name,
-                        "updated record"
-                    );
-                    updated_ids.insert(cf_record.id.clone());
+                queue.push(
+                    &cf_record.zone_id,
+                    &cf_record.id,
+                    &cf_record.name,
+                    content,
+                );
+            }
+
+            if queue.is_empty() {
+                return Ok((updated, deferred));
+            }
+            if !resolver.confirm().await {
+                warn!("public IPs are unconfirmed, skipping updates");
+                return Ok((updated, deferred));
+            }
+            info!("updating {} records...", queue.len());
+            let report = queue.flush(token).await;
+            for (update, err) in report.failed {
+                debug!("{err:?}");
+                error!(
+                    id = update.record_id,
+                    name = update.name,
+                    "unsuccessful record update"
+                );
+            }
+            let mut entries = vec![];
+            for update in report.updated {
+                info!(
+                    id = update.record_id,
+                    name = update.name,
+                    "updated record"
+                );
+                if let Some(record) =
+                    outdated.iter().find(|r| r.id == update.record_id)
+                {
+                    report::updated(record, &update.content);
+                    entries.push(AuditEntry::new(
+                        &update.zone_id,
+                        &update.record_id,
+                        &update.name,
+                        &record.content,
+                        &update.content,
+                    ));
                 }
+                updated.push(update);
             }
+            audit::append(&entries).await;
         }
     }
-    Ok(updated_ids)
+    Ok((updated, deferred))
+}
+
+/// Create the records of invalid inventory entries naming a record missing
+/// from their zone, returning the created records.
+///
+/// An A record is created if IPv4 is enabled, and an AAAA record if IPv6 is
+/// enabled and records are paired or IPv4 is disabled.
+#[tracing::instrument(level = "trace", skip_all)]
+async fn __create(
+    opts: &ConfigOpts,
+    invalid: &[(String, String)],
+    fixed: Resolved,
+) -> Result<Vec<Record>> {
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
+    let ipv4_enabled = opts.inventory.ipv4.context("no default ipv4 option")?;
+    let ipv6_enabled = opts.inventory.ipv6.context("no default ipv6 option")?;
+    let pair_records = opts
+        .inventory
+        .pair_records
+        .context("no default pair records option")?;
+    let mut record_types = vec![];
+    if ipv4_enabled {
+        record_types.push("A");
+    }
+    if ipv6_enabled && (pair_records || !ipv4_enabled) {
+        record_types.push("AAAA");
+    }
+
+    // Find the missing names
+    let zones = cloudflare::endpoints::zones(token.to_string()).await?;
+    let mut missing = vec![];
+    for (inv_zone, inv_record) in invalid.iter() {
+        let Ok(RecordSelector::Record(name)) =
+            RecordSelector::parse(inv_record)
+        else {
+            warn!(
+                zone = inv_zone,
+                record = inv_record,
+                "only records given by name can be created"
+            );
+            continue;
+        };
+        let Some(zone) = cloudflare::filters::find_zone(&zones, inv_zone)
+        else {
+            warn!(zone = inv_zone, record = name, "zone not found");
+            continue;
+        };
+        let ascii = names::to_ascii(name);
+        if ascii != zone.name && !ascii.ends_with(&format!(".{}", zone.name)) {
+            warn!(
+                zone = zone.name,
+                record = name,
+                "not a name in the zone, such as a missing record ID"
+            );
+            continue;
+        }
+        missing.push((zone, name));
+    }
+    if missing.is_empty() || record_types.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // Ask to create records
+    let force = opts
+        .inventory
+        .force_update
+        .context("no default force option")?;
+    let create = force || {
+        prompt_yes_or_no(
+            format!("Create {} missing records?", missing.len()),
+            "Y/n",
+        )?
+        .unwrap_or(true)
+    };
+    if !create {
+        return Ok(vec![]);
+    }
+
+    // Resolve the content of records
+    let mut resolver = Resolver::new(&opts.ip).fixed(fixed);
+    let mut new_records = vec![];
+    for (zone, name) in missing {
+        let uplink = resolver.uplink_of(name).flatten();
+        for record_type in record_types.iter() {
+            let content = match *record_type {
+                "A" => resolver
+                    .ipv4_on(uplink)
+                    .await
+                    .context("could not resolve ipv4 address")?
+                    .to_string(),
+                _ => resolver
+                    .ipv6_on(uplink)
+                    .await
+                    .context("could not resolve ipv6 address")?
+                    .to_string(),
+            };
+            let comment =
+                opts.inventory.comment_template.as_ref().map(|template| {
+                    queue::render_comment(template, name, &content)
+                });
+            new_records.push((
+                zone.id.clone(),
+                NewRecord {
+                    name: names::to_ascii(name).to_string(),
+                    record_type: record_type.to_string(),
+                    content,
+                    ttl: 1,
+                    proxied: false,
+                    comment,
+                },
+            ));
+        }
+    }
+    if !resolver.confirm().await {
+        warn!("public IPs are unconfirmed, skipping creation");
+        return Ok(vec![]);
+    }
+
+    // Create records
+    info!("creating {} records...", new_records.len());
+    let mut created = vec![];
+    for (zone_id, new_record) in new_records {
+        match cloudflare::endpoints::create_record(token, &zone_id, &new_record)
+            .await
+        {
+            Ok(record) => {
+                info!(
+                    id = record.id,
+                    name = record.name,
+                    content = record.content,
+                    "created {} record",
+                    record.record_type
+                );
+                created.push(record);
+            }
+            Err(err) => {
+                debug!("{err:?}");
+                error!(
+                    name = new_record.name,
+                    "unsuccessful {} record creation: {err}",
+                    new_record.record_type
+                );
+            }
+        }
+    }
+    Ok(created)
 }
 
 /// Prune invalid records, returning the resulting inventory.
 #[tracing::instrument(level = "trace", skip_all)]
 async fn __prune(
     opts: &ConfigOpts,
-    invalid: &Vec<(String, String)>,
+    invalid: &[(String, String)],
 ) -> Result<Inventory> {
     // Get inventory
-    let inventory_path = opts
-        .inventory
-        .path
-        .clone()
-        .unwrap_or_else(default_inventory_path);
+    let inventory_path = inventory_path(opts)?;
     let mut inventory = Inventory::from_file(inventory_path).await?;
 
     // Prune invalid records
@@ -1,9 +1,16 @@
-use crate::cloudflare;
-use crate::cloudflare::models::{Record, Zone};
-use crate::config::models::{ConfigOpts, ConfigOptsList};
+use crate::util::table::Table;
 use anyhow::{Context, Result};
-use clap::{Args, Subcommand};
-use regex::Regex;
+use cddns_core::cloudflare;
+use cddns_core::cloudflare::endpoints::Page;
+use cddns_core::cloudflare::filters::{
+    find_record, find_zone, retain_records, retain_zones,
+};
+use cddns_core::cloudflare::models::{Record, Zone};
+use cddns_core::config::models::{ConfigOpts, ConfigOptsList};
+use cddns_core::error::Error;
+use cddns_core::util::names;
+use clap::{Args, Subcommand, ValueEnum};
+use std::cmp::Ordering;
 use tracing::{debug, info, trace};
 
 /// List available resources
@@ -22,6 +29,8 @@ enum ListSubcommands {
     Zones(ZoneOpts),
     /// Show authoritative DNS records.
     Records(RecordOpts),
+    /// Show accounts (organizations) the token can access.
+    Accounts,
 }
 
 #[derive(Debug, Clone, Args)]
@@ -29,16 +38,67 @@ pub struct ZoneOpts {
     /// Print a single zone matching a name or id.
     #[clap(short, long, value_name = "name|id")]
     pub zone: Option<String>,
+    /// Sort zones by a field.
+    #[clap(long, value_name = "field", default_value = "name")]
+    pub sort: ZoneSort,
+    /// Sort in descending order.
+    #[clap(long)]
+    pub desc: bool,
+    /// Print only zone ids, one per line, for scripts.
+    #[clap(long)]
+    pub ids: bool,
+    /// Print the status, account, plan, DNSSEC status and nameservers of
+    /// zones.
+    #[clap(long, conflicts_with = "ids")]
+    pub details: bool,
 }
 
 #[derive(Debug, Clone, Args)]
 pub struct RecordOpts {
-    /// Print records from a single zone matching a name or id.
+    /// Print records from zones matching a name or id. Repeat to print
+    /// several zones.
     #[clap(short, long, value_name = "name|id")]
-    pub zone: Option<String>,
+    pub zone: Vec<String>,
     /// Print a single record matching a name or id.
     #[clap(short, long, value_name = "name|id")]
     pub record: Option<String>,
+    /// Print records of every type, not only the A and AAAA records managed
+    /// by cddns.
+    #[clap(long)]
+    pub all_types: bool,
+    /// Sort the records of each zone by a field.
+    #[clap(long, value_name = "field", default_value = "name")]
+    pub sort: RecordSort,
+    /// Sort in descending order.
+    #[clap(long)]
+    pub desc: bool,
+    /// Print only record ids, one per line, for scripts.
+    #[clap(long)]
+    pub ids: bool,
+    /// Retrieve and print only this page of the records of each zone,
+    /// starting at 1.
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub page: Option<u32>,
+    /// Retrieve at most this many records of each zone, as the size of the
+    /// page given by `--page`.
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub limit: Option<u32>,
+}
+
+/// A field to sort zones by.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ZoneSort {
+    Name,
+    Modified,
+}
+
+/// A field to sort records by.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum RecordSort {
+    Name,
+    Type,
+    Content,
+    Modified,
 }
 
 impl ListCmd {
@@ -48,8 +108,19 @@ impl ListCmd {
         let cli_opts = ConfigOpts::builder().list(Some(self.cfg)).build();
         let opts = ConfigOpts::builder().merge(opts).merge(cli_opts).build();
 
-        // Run
-        info!("retrieving, please wait...");
+        // Run, only printing ids if requested so output can be piped
+        let ids = match self.action {
+            Some(ListSubcommands::Zones(ref cli_zone_opts)) => {
+                cli_zone_opts.ids
+            }
+            Some(ListSubcommands::Records(ref cli_record_opts)) => {
+                cli_record_opts.ids
+            }
+            Some(ListSubcommands::Accounts) | None => false,
+        };
+        if !ids {
+            info!("retrieving, please wait...");
+        }
         match self.action {
             Some(subcommand) => match subcommand {
                 ListSubcommands::Zones(cli_zone_opts) => {
@@ -58,6 +129,7 @@ impl ListCmd {
                 ListSubcommands::Records(cli_record_opts) => {
                     list_records(&opts, &cli_record_opts).await
                 }
+                ListSubcommands::Accounts => list_accounts(&opts).await,
             },
             None => list_all(&opts).await,
         }
@@ -68,9 +140,7 @@ impl ListCmd {
 #[tracing::instrument(level = "trace", skip_all)]
 async fn list_all(opts: &ConfigOpts) -> Result<()> {
     // Get token
-    let token = opts
-        .verify.token.as_ref()
-        .context("no token was provided, need help? see https://github.com/simbleau/cddns#readme")?;
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
 
     // Get zones
     trace!("retrieving cloudflare resources...");
@@ -85,13 +155,8 @@ async fn list_all(opts: &ConfigOpts) -> Result<()> {
         records.len()
     );
 
-    // Print all
-    for zone in zones.iter() {
-        println!("{zone}");
-        for record in records.iter().filter(|r| r.zone_id == zone.id) {
-            println!("  - {record}");
-        }
-    }
+    // Print all, hiding zones without records matching a query
+    print_grouped(&zones, &records, opts.list.query.is_none());
     Ok(())
 }
 
@@ -99,9 +164,7 @@ async fn list_all(opts: &ConfigOpts) -> Result<()> {
 #[tracing::instrument(level = "trace", skip_all)]
 async fn list_zones(opts: &ConfigOpts, cli_opts: &ZoneOpts) -> Result<()> {
     // Get token
-    let token = opts
-        .verify.token.as_ref()
-        .context("no token was provided, need help? see https://github.com/simbleau/cddns#readme")?;
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
 
     // Get zones
     trace!("retrieving cloudflare resources...");
@@ -113,10 +176,35 @@ async fn list_zones(opts: &ConfigOpts, cli_opts: &ZoneOpts) -> Result<()> {
     } else {
         retain_zones(&mut zones, opts)?;
     }
+    sort_zones(&mut zones, cli_opts.sort, cli_opts.desc);
 
     // Print zones
+    if cli_opts.details {
+        return print_details(&zones, token).await;
+    }
     for zone in zones {
-        println!("{zone}");
+        if cli_opts.ids {
+            println!("{}", zone.id);
+        } else {
+            println!("{zone}");
+        }
+    }
+    Ok(())
+}
+
+/// Print only accounts.
+#[tracing::instrument(level = "trace", skip_all)]
+async fn list_accounts(opts: &ConfigOpts) -> Result<()> {
+    // Get token
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
+
+    // Get accounts
+    trace!("retrieving cloudflare resources...");
+    let accounts = cloudflare::endpoints::accounts(&token).await?;
+
+    // Print accounts
+    for account in accounts {
+        println!("{account}");
     }
     Ok(())
 }
@@ -125,22 +213,47 @@ async fn list_zones(opts: &ConfigOpts, cli_opts: &ZoneOpts) -> Result<()> {
 #[tracing::instrument(level = "trace", skip_all)]
 async fn list_records(opts: &ConfigOpts, cli_opts: &RecordOpts) -> Result<()> {
     // Get token
-    let token = opts
-        .verify.token.as_ref()
-        .context("no token was provided, need help? see https://github.com/simbleau/cddns#readme")?;
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
 
     // Get zones
     trace!("retrieving cloudflare resources...");
     let mut zones = cloudflare::endpoints::zones(&token).await?;
-    if let Some(ref zone_id) = cli_opts.zone {
-        zones = vec![find_zone(&zones, zone_id)
-            .context("no result with that zone id/name")?];
-    } else {
+    if cli_opts.zone.is_empty() {
         retain_zones(&mut zones, opts)?;
+    } else {
+        let mut selected: Vec<Zone> = vec![];
+        for zone_id in cli_opts.zone.iter() {
+            let zone = find_zone(&zones, zone_id).with_context(|| {
+                format!("no result with zone id/name '{zone_id}'")
+            })?;
+            if !selected.iter().any(|z| z.id == zone.id) {
+                selected.push(zone);
+            }
+        }
+        zones = selected;
     }
 
-    // Get records
-    let mut records = cloudflare::endpoints::records(&zones, &token).await?;
+    // Get records, or a single page of records if requested
+    let page =
+        (cli_opts.page.is_some() || cli_opts.limit.is_some()).then(|| Page {
+            number: cli_opts.page.unwrap_or(1),
+            per_page: cli_opts.limit,
+        });
+    let mut records = match page {
+        Some(page) => {
+            cloudflare::endpoints::records_page(
+                &zones,
+                &token,
+                page,
+                cli_opts.all_types,
+            )
+            .await?
+        }
+        None if cli_opts.all_types => {
+            cloudflare::endpoints::all_records(&zones, &token).await?
+        }
+        None => cloudflare::endpoints::records(&zones, &token).await?,
+    };
     // Apply filtering
     if let Some(ref record_id) = cli_opts.record {
         records = vec![find_record(&records, record_id)
@@ -148,98 +261,135 @@ async fn list_records(opts: &ConfigOpts, cli_opts: &RecordOpts) -> Result<()> {
     } else {
         retain_records(&mut records, opts)?;
     }
+    sort_records(&mut records, cli_opts.sort, cli_opts.desc);
 
     // Print records
-    for record in records {
-        println!("{record}");
+    if cli_opts.ids {
+        for record in records {
+            println!("{}", record.id);
+        }
+    } else {
+        print_grouped(&zones, &records, false);
     }
     Ok(())
 }
 
-/// Find a zone matching the given identifier.
-#[tracing::instrument(level = "trace", skip_all)]
-pub fn find_zone(zones: &Vec<Zone>, id: impl Into<String>) -> Option<Zone> {
-    let id_str = id.into();
-    for z in zones {
-        if id_str == z.id || id_str == z.name {
-            return Some(z.clone());
-        }
+/// Print zones in aligned columns of their details.
+async fn print_details(zones: &[Zone], token: &str) -> Result<()> {
+    let mut table = Table::new(&[
+        "NAME",
+        "STATUS",
+        "ACCOUNT",
+        "PLAN",
+        "DNSSEC",
+        "NAMESERVERS",
+        "ID",
+    ]);
+    for zone in zones {
+        let dnssec = cloudflare::endpoints::dnssec(&zone.id, token).await?;
+        let status = if zone.paused { "paused" } else { &zone.status };
+        let or_unknown = |value: Option<&str>| {
+            value.filter(|v| !v.is_empty()).unwrap_or("-").to_string()
+        };
+        table.push(vec![
+            names::to_unicode(&zone.name).into_owned(),
+            status.to_string(),
+            or_unknown(zone.account.as_ref().map(|a| a.name.as_str())),
+            or_unknown(zone.plan.as_ref().map(|p| p.name.as_str())),
+            or_unknown(dnssec.as_deref()),
+            or_unknown(Some(&zone.name_servers.join(","))),
+            zone.id.clone(),
+        ]);
     }
-    None
+    table.print("");
+    Ok(())
 }
 
-/// Retain zones matching the given configuration filters.
-#[tracing::instrument(level = "trace", skip_all)]
-pub fn retain_zones(zones: &mut Vec<Zone>, opts: &ConfigOpts) -> Result<()> {
-    let beginning_amt = zones.len();
-    // Filter zones by configuration options
-    if let Some(include_filters) = opts.list.include_zones.as_ref() {
-        for filter_str in include_filters {
-            debug!("applying include filter: '{}'", filter_str);
-            let pattern = Regex::new(filter_str)
-                .context("compiling include_zones regex filter")?;
-            zones.retain(|z| {
-                pattern.is_match(&z.id) || pattern.is_match(&z.name)
-            });
+/// Sort zones by a field, in ascending or descending order. Ties are
+/// sorted by name.
+fn sort_zones(zones: &mut [Zone], sort: ZoneSort, desc: bool) {
+    zones.sort_by(|a, b| {
+        let ordering = match sort {
+            ZoneSort::Name => Ordering::Equal,
+            ZoneSort::Modified => a.modified_on.cmp(&b.modified_on),
         }
-    }
-    if let Some(ignore_filters) = opts.list.ignore_zones.as_ref() {
-        for filter_str in ignore_filters {
-            debug!("applying ignore filter: '{}'", filter_str);
-            let pattern = Regex::new(filter_str)
-                .context("compiling ignore_zones regex filter")?;
-            zones.retain(|z| {
-                !pattern.is_match(&z.id) && !pattern.is_match(&z.name)
-            });
+        .then_with(|| a.name.cmp(&b.name));
+        if desc {
+            ordering.reverse()
+        } else {
+            ordering
         }
-    }
-    debug!("filtered out {} zones", beginning_amt - zones.len());
-    Ok(())
+    });
 }
 
-/// Find a record matching the given identifier.
-#[tracing::instrument(level = "trace", skip_all)]
-pub fn find_record(
-    records: &Vec<Record>,
-    id: impl Into<String>,
-) -> Option<Record> {
-    let id_str = id.into();
-    for r in records {
-        if id_str == r.id || id_str == r.name {
-            return Some(r.clone());
+/// Sort records by a field, in ascending or descending order. Ties are
+/// sorted by name.
+fn sort_records(records: &mut [Record], sort: RecordSort, desc: bool) {
+    records.sort_by(|a, b| {
+        let ordering = match sort {
+            RecordSort::Name => Ordering::Equal,
+            RecordSort::Type => a.record_type.cmp(&b.record_type),
+            RecordSort::Content => a.content.cmp(&b.content),
+            RecordSort::Modified => a.modified_on.cmp(&b.modified_on),
         }
-    }
-    None
+        .then_with(|| a.name.cmp(&b.name));
+        if desc {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
 }
 
-/// Retain records matching the given configuration filters.
-#[tracing::instrument(level = "trace", skip_all)]
-pub fn retain_records(
-    records: &mut Vec<Record>,
-    opts: &ConfigOpts,
-) -> Result<()> {
-    let beginning_amt = records.len();
-    // Filter records by configuration options
-    if let Some(include_filters) = opts.list.include_records.as_ref() {
-        for filter_str in include_filters {
-            debug!("applying include filter: '{}'", filter_str);
-            let pattern = Regex::new(filter_str)
-                .context("compiling include_records regex filter")?;
-            records.retain(|r| {
-                pattern.is_match(&r.id) || pattern.is_match(&r.name)
-            });
+/// Print records grouped by zone, with per-zone subtotals and a total.
+fn print_grouped(zones: &[Zone], records: &[Record], show_empty: bool) {
+    let mut total_zones = 0;
+    for zone in zones {
+        let zone_records = records
+            .iter()
+            .filter(|r| r.zone_id == zone.id)
+            .collect::<Vec<&Record>>();
+        if zone_records.is_empty() && !show_empty {
+            continue;
         }
-    }
-    if let Some(ignore_filters) = opts.list.ignore_records.as_ref() {
-        for filter_str in ignore_filters {
-            debug!("applying ignore filter: '{}'", filter_str);
-            let pattern = Regex::new(filter_str)
-                .context("compiling ignore_records regex filter")?;
-            records.retain(|r| {
-                !pattern.is_match(&r.id) && !pattern.is_match(&r.name)
-            });
+        total_zones += 1;
+        println!("{zone}");
+        if !zone_records.is_empty() {
+            let mut table = Table::new(&[
+                "NAME", "TYPE", "CONTENT", "TTL", "PROXIED", "MODIFIED", "ID",
+            ]);
+            for record in zone_records.iter() {
+                table.push(record_row(record));
+            }
+            table.print("  ");
         }
+        println!("  records: {}", zone_records.len());
     }
-    debug!("filtered out {} records", beginning_amt - records.len());
-    Ok(())
+    println!(
+        "total zones: {total_zones}, total records: {}",
+        records.len()
+    );
+}
+
+/// Return the columns of a record, as printed by `list`.
+fn record_row(record: &Record) -> Vec<String> {
+    let ttl = match record.ttl {
+        0 => "-".to_string(),
+        1 => "auto".to_string(),
+        ttl => ttl.to_string(),
+    };
+    let proxied = if record.proxied { "yes" } else { "no" };
+    let modified = record
+        .modified_on
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_string());
+    vec![
+        names::to_unicode(&record.name).into_owned(),
+        record.record_type.clone(),
+        record.content.clone(),
+        ttl,
+        proxied.to_string(),
+        modified,
+        record.id.clone(),
+    ]
 }
@@ -1,6 +1,11 @@
 //! Clap commands handled by the CLI.
 
+pub mod acme;
 pub mod config;
 pub mod inventory;
 pub mod list;
+pub mod record;
+pub mod rollback;
+pub mod service;
+pub mod status;
 pub mod verify;
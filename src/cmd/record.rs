@@ -0,0 +1,347 @@
+use crate::cmd::inventory::inventory_path;
+use crate::util::scanner::prompt_yes_or_no;
+use anyhow::{bail, Context, Result};
+use cddns_core::cloudflare;
+use cddns_core::cloudflare::models::{NewRecord, Zone};
+use cddns_core::config::models::{
+    ConfigOpts, ConfigOptsTemplateRecord, ConfigOptsVerify,
+};
+use cddns_core::error::Error;
+use cddns_core::inventory::models::{Inventory, InventoryData};
+use cddns_core::ip::uplink::Resolver;
+use cddns_core::util::names;
+use clap::{Args, Subcommand};
+use tracing::{error, info};
+
+/// Create or delete DNS records.
+#[derive(Debug, Args)]
+#[clap(name = "record")]
+pub struct RecordCmd {
+    #[clap(subcommand)]
+    action: RecordSubcommands,
+    #[clap(flatten)]
+    pub cfg: ConfigOptsVerify,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum RecordSubcommands {
+    /// Create a DNS record in a zone.
+    Create(CreateOpts),
+    /// Delete DNS records by name or ID.
+    Delete(DeleteOpts),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CreateOpts {
+    /// The name of the record, absolute or relative to its zone, e.g. `vpn`
+    /// or `vpn.example.com`. Use `@` for the zone apex.
+    #[clap(value_name = "name")]
+    pub name: String,
+    /// The name or ID of the zone to create the record in. [default: the
+    /// zone the name ends with]
+    #[clap(short, long, value_name = "name|id")]
+    pub zone: Option<String>,
+    /// Create the records of a template in the `templates` config section,
+    /// instead of a single record.
+    #[clap(long, value_name = "template", conflicts_with_all = ["record_type", "content"])]
+    pub template: Option<String>,
+    /// The type of the record. [default: A]
+    #[clap(short = 't', long = "type", value_name = "type")]
+    pub record_type: Option<String>,
+    /// The content of the record. [default: your public IP, for A and AAAA
+    /// records]
+    #[clap(short, long, value_name = "content")]
+    pub content: Option<String>,
+    /// The time to live in seconds, where 1 is "Auto". [default: 1]
+    #[clap(long, value_name = "seconds")]
+    pub ttl: Option<u32>,
+    /// Proxy traffic through Cloudflare.
+    #[clap(long)]
+    pub proxied: bool,
+    /// The Cloudflare comment of the record.
+    #[clap(long, value_name = "comment")]
+    pub comment: Option<String>,
+    /// Add the created A and AAAA records to the inventory, creating the
+    /// inventory file if it does not exist.
+    #[clap(long)]
+    pub inventory: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DeleteOpts {
+    /// The name or ID of the record to delete. A name deletes all of its
+    /// records, such as both its A and AAAA records.
+    #[clap(value_name = "name|id")]
+    pub record: String,
+    /// Only delete records in the zone matching the given name or ID.
+    #[clap(short, long, value_name = "name|id")]
+    pub zone: Option<String>,
+    /// Only delete records of the given type.
+    #[clap(short = 't', long = "type", value_name = "type")]
+    pub record_type: Option<String>,
+    /// Skip the confirmation prompt.
+    #[clap(short, long)]
+    pub yes: bool,
+    /// Remove the deleted records from the inventory.
+    #[clap(long)]
+    pub inventory: bool,
+}
+
+impl RecordCmd {
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub async fn run(self, opts: ConfigOpts) -> Result<()> {
+        // Apply CLI configuration layering
+        let cli_opts = ConfigOpts::builder().verify(Some(self.cfg)).build();
+        let opts = ConfigOpts::builder().merge(opts).merge(cli_opts).build();
+
+        // Run
+        match self.action {
+            RecordSubcommands::Create(create_opts) => {
+                create(&opts, &create_opts).await
+            }
+            RecordSubcommands::Delete(delete_opts) => {
+                delete(&opts, &delete_opts).await
+            }
+        }
+    }
+}
+
+/// Create a record, or the records of a template, resolving the public IP
+/// for A and AAAA records without content.
+#[tracing::instrument(level = "trace", skip_all)]
+async fn create(opts: &ConfigOpts, cli_opts: &CreateOpts) -> Result<()> {
+    cloudflare::cache::ensure_online("create records")?;
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
+
+    // Find the zone
+    let input = names::to_ascii(&cli_opts.name);
+    let zones = cloudflare::endpoints::zones(token.to_string()).await?;
+    let zone = match cli_opts.zone.as_ref() {
+        Some(zone) => {
+            cloudflare::filters::find_zone(&zones, zone).ok_or_else(|| {
+                Error::NotFound(format!("zone with id/name '{zone}'"))
+            })?
+        }
+        None => zone_of(&zones, &input).ok_or_else(|| {
+            Error::Config(format!(
+                "no zone contains '{}', use `--zone`",
+                cli_opts.name
+            ))
+        })?,
+    };
+    let name = match input.as_ref() {
+        "@" => zone.name.clone(),
+        name if is_in_zone(name, &zone.name) => name.to_string(),
+        name => format!("{name}.{}", zone.name),
+    };
+
+    // Collect the records to create
+    let templated = match cli_opts.template.as_ref() {
+        Some(template) => opts
+            .templates
+            .as_ref()
+            .and_then(|templates| templates.get(template))
+            .ok_or_else(|| {
+                Error::Config(format!("no template named '{template}'"))
+            })?
+            .records
+            .clone(),
+        None => vec![ConfigOptsTemplateRecord {
+            record_type: cli_opts
+                .record_type
+                .clone()
+                .unwrap_or_else(|| "A".to_string()),
+            content: cli_opts.content.clone(),
+            ttl: None,
+            proxied: None,
+            comment: None,
+        }],
+    };
+
+    // Resolve the content of records
+    let mut resolver = Resolver::new(&opts.ip);
+    let uplink = resolver.uplink_of(&name).flatten();
+    let mut new_records = vec![];
+    for templated in templated {
+        let record_type = templated.record_type.to_uppercase();
+        let content = match (templated.content, record_type.as_str()) {
+            (Some(content), _) => content.replace("{name}", &name),
+            (None, "A") => resolver
+                .ipv4_on(uplink)
+                .await
+                .context("could not resolve public ipv4 address")?
+                .to_string(),
+            (None, "AAAA") => resolver
+                .ipv6_on(uplink)
+                .await
+                .context("could not resolve public ipv6 address")?
+                .to_string(),
+            (None, _) => {
+                return Err(Error::Config(format!(
+                    "content is required for {record_type} records"
+                ))
+                .into())
+            }
+        };
+        new_records.push(NewRecord {
+            name: name.clone(),
+            record_type,
+            content,
+            ttl: cli_opts.ttl.or(templated.ttl).unwrap_or(1),
+            proxied: cli_opts.proxied || templated.proxied.unwrap_or(false),
+            comment: cli_opts
+                .comment
+                .clone()
+                .or(templated.comment)
+                .map(|comment| comment.replace("{name}", &name)),
+        });
+    }
+
+    // Create the records
+    let mut created = vec![];
+    for new_record in new_records {
+        info!(
+            name = new_record.name,
+            content = new_record.content,
+            "creating {} record...",
+            new_record.record_type
+        );
+        let record =
+            cloudflare::endpoints::create_record(token, &zone.id, &new_record)
+                .await?;
+        info!(id = record.id, name = record.name, "created record");
+        println!("{record}");
+        created.push(record);
+    }
+
+    // Add the records to the inventory
+    created.retain(|r| r.record_type == "A" || r.record_type == "AAAA");
+    if cli_opts.inventory && !created.is_empty() {
+        let path = inventory_path(opts)?;
+        let mut inventory = if path.exists() {
+            Inventory::from_file(&path).await?
+        } else {
+            Inventory::builder()
+                .path(&path)
+                .with_data(InventoryData(None))
+                .build()?
+        };
+        for record in created.iter() {
+            inventory.data.insert(&zone.id, &record.id);
+        }
+        inventory.save(opts, true, true).await?;
+        info!(path = %inventory.path.display(), "added records to inventory");
+    }
+    Ok(())
+}
+
+/// Return the most specific zone a name belongs to.
+pub fn zone_of(zones: &[Zone], name: &str) -> Option<Zone> {
+    zones
+        .iter()
+        .filter(|z| is_in_zone(name, &z.name))
+        .max_by_key(|z| z.name.len())
+        .cloned()
+}
+
+/// Returns whether a name is the zone apex or a name in the zone.
+fn is_in_zone(name: &str, zone: &str) -> bool {
+    name == zone || name.ends_with(&format!(".{zone}"))
+}
+
+/// Delete the records matching a name or ID, after confirmation.
+#[tracing::instrument(level = "trace", skip_all)]
+async fn delete(opts: &ConfigOpts, cli_opts: &DeleteOpts) -> Result<()> {
+    cloudflare::cache::ensure_online("delete records")?;
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
+
+    // Find the records
+    let mut zones = cloudflare::endpoints::zones(token.to_string()).await?;
+    if let Some(zone) = cli_opts.zone.as_ref() {
+        zones.retain(|z| &z.id == zone || names::same(&z.name, zone));
+        if zones.is_empty() {
+            return Err(
+                Error::NotFound(format!("zone with id/name '{zone}'")).into()
+            );
+        }
+    }
+    let mut records =
+        cloudflare::endpoints::all_records(&zones, token.to_string()).await?;
+    records.retain(|r| {
+        (r.id == cli_opts.record || names::same(&r.name, &cli_opts.record))
+            && cli_opts
+                .record_type
+                .as_ref()
+                .is_none_or(|t| r.record_type.eq_ignore_ascii_case(t))
+    });
+    if records.is_empty() {
+        return Err(Error::NotFound(format!(
+            "record with id/name '{}'",
+            cli_opts.record
+        ))
+        .into());
+    }
+
+    // Ask to delete records
+    for record in records.iter() {
+        println!("{} {record}", record.record_type);
+    }
+    let delete = cli_opts.yes || {
+        prompt_yes_or_no(format!("Delete {} records?", records.len()), "y/N")?
+            .unwrap_or(false)
+    };
+    if !delete {
+        info!("no records were deleted");
+        return Ok(());
+    }
+
+    // Delete records
+    let (mut deleted, mut failed) = (vec![], 0);
+    for record in records {
+        match cloudflare::endpoints::delete_record(
+            token,
+            &record.zone_id,
+            &record.id,
+        )
+        .await
+        {
+            Ok(()) => {
+                info!(
+                    id = record.id,
+                    name = record.name,
+                    "deleted {} record",
+                    record.record_type
+                );
+                deleted.push(record);
+            }
+            Err(err) => {
+                error!(
+                    id = record.id,
+                    name = record.name,
+                    "unsuccessful record deletion: {err}"
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    // Remove the records from the inventory
+    if cli_opts.inventory && !deleted.is_empty() {
+        let mut inventory = Inventory::from_file(inventory_path(opts)?).await?;
+        inventory.data.retain(|zone, entry| {
+            !deleted.iter().any(|r| {
+                (zone == r.zone_id || zone == r.zone_name)
+                    && (entry == r.id || names::same(entry, &r.name))
+            })
+        });
+        inventory.save(opts, true, true).await?;
+        info!(
+            path = %inventory.path.display(),
+            "removed records from inventory"
+        );
+    }
+    if failed > 0 {
+        bail!("{failed} records could not be deleted");
+    }
+    Ok(())
+}
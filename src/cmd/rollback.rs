@@ -0,0 +1,89 @@
+use anyhow::Result;
+use cddns_core::cloudflare;
+use cddns_core::config::models::{ConfigOpts, ConfigOptsVerify};
+use cddns_core::error::Error;
+use cddns_core::inventory::audit::{self, AuditEntry};
+use clap::{ArgGroup, Args};
+use tracing::info;
+
+/// Restore the previous content of a record from the audit log.
+#[derive(Debug, Args)]
+#[clap(name = "rollback")]
+#[clap(group(ArgGroup::new("target").required(true).args(["record", "last"])))]
+pub struct RollbackCmd {
+    #[clap(flatten)]
+    pub cfg: ConfigOptsVerify,
+    /// The name or ID of the record to roll back.
+    #[clap(short, long, value_name = "name|id")]
+    pub record: Option<String>,
+    /// Roll back the most recent change to any record.
+    #[clap(long)]
+    pub last: bool,
+}
+
+impl RollbackCmd {
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub async fn run(self, opts: ConfigOpts) -> Result<()> {
+        // Apply CLI configuration layering
+        let cli_opts = ConfigOpts::builder().verify(Some(self.cfg)).build();
+        let opts = ConfigOpts::builder().merge(opts).merge(cli_opts).build();
+
+        // Run
+        rollback(&opts, self.record.as_deref()).await
+    }
+}
+
+/// Re-apply the previous content of the latest change to a record, or to
+/// any record if none is given.
+#[tracing::instrument(level = "trace", skip_all)]
+async fn rollback(opts: &ConfigOpts, record: Option<&str>) -> Result<()> {
+    cloudflare::cache::ensure_online("roll back records")?;
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
+
+    // Find the latest change
+    let entry = audit::read()
+        .await?
+        .into_iter()
+        .rev()
+        .find(|entry| {
+            record.is_none_or(|r| entry.name == r || entry.record_id == r)
+        })
+        .ok_or_else(|| match record {
+            Some(record) => Error::NotFound(format!(
+                "change to record '{record}' in the audit log"
+            )),
+            None => Error::NotFound("change in the audit log".into()),
+        })?;
+
+    // Restore the previous content
+    info!(
+        name = entry.name,
+        id = entry.record_id,
+        changed = %entry.time,
+        "rolling back '{}' to '{}'...",
+        entry.to,
+        entry.from
+    );
+    cloudflare::endpoints::update_record(
+        token,
+        &entry.zone_id,
+        &entry.record_id,
+        &entry.from,
+        None,
+    )
+    .await?;
+    audit::append(&[AuditEntry::new(
+        &entry.zone_id,
+        &entry.record_id,
+        &entry.name,
+        &entry.to,
+        &entry.from,
+    )])
+    .await;
+    info!(
+        name = entry.name,
+        content = entry.from,
+        "rolled back record"
+    );
+    Ok(())
+}
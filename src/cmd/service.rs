@@ -0,0 +1,446 @@
+use crate::util;
+use crate::util::service::SERVICE_NAME;
+use anyhow::{bail, Context, Result};
+use cddns_core::config::models::ConfigOpts;
+use cddns_core::error::Error;
+use cddns_core::inventory::state::default_state_path;
+use clap::{ArgGroup, Args, Subcommand};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// Install or remove cddns as a service running `inventory watch`.
+#[derive(Debug, Args)]
+#[clap(name = "service")]
+pub struct ServiceCmd {
+    #[clap(subcommand)]
+    action: ServiceSubcommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum ServiceSubcommands {
+    /// Install a service running `inventory watch` with the current config.
+    Install(InstallOpts),
+    /// Stop and remove an installed service.
+    Uninstall(UninstallOpts),
+}
+
+#[derive(Debug, Args)]
+#[clap(group(ArgGroup::new("manager").required(true).args(["systemd", "windows"])))]
+pub struct InstallOpts {
+    /// Install a systemd unit.
+    #[clap(long)]
+    systemd: bool,
+    /// Register a Windows service, started automatically at boot.
+    #[clap(long)]
+    windows: bool,
+    /// Install a user unit instead of a system unit.
+    #[clap(long, requires = "systemd")]
+    user: bool,
+    /// Enable and start the service after installing it.
+    #[clap(long)]
+    enable: bool,
+    /// Print the service definition instead of installing it.
+    #[clap(long, requires = "systemd", conflicts_with = "enable")]
+    stdout: bool,
+    /// Append service output to this file (Windows only).
+    /// [default: service.log beside the state file]
+    #[clap(long, value_name = "file", requires = "windows")]
+    log_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+#[clap(group(ArgGroup::new("manager").required(true).args(["systemd", "windows"])))]
+pub struct UninstallOpts {
+    /// Remove a systemd unit.
+    #[clap(long)]
+    systemd: bool,
+    /// Remove a Windows service.
+    #[clap(long)]
+    windows: bool,
+    /// Remove a user unit instead of a system unit.
+    #[clap(long, requires = "systemd")]
+    user: bool,
+}
+
+impl ServiceCmd {
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub async fn run(self, opts: ConfigOpts) -> Result<()> {
+        match self.action {
+            ServiceSubcommands::Install(install_opts) => {
+                install(&opts, &install_opts).await
+            }
+            ServiceSubcommands::Uninstall(uninstall_opts) => {
+                uninstall(&uninstall_opts).await
+            }
+        }
+    }
+}
+
+#[tracing::instrument(level = "trace", skip_all)]
+async fn install(opts: &ConfigOpts, install_opts: &InstallOpts) -> Result<()> {
+    let config = util::layers::config_path();
+    if util::layers::is_stdin(Some(&config)) {
+        return Err(Error::Config(
+            "a service can't read its config from stdin, use a config file"
+                .into(),
+        )
+        .into());
+    }
+    let config = absolute(config)?;
+    let inventory = absolute(
+        opts.inventory
+            .path
+            .clone()
+            .context("no inventory path was provided")?,
+    )?;
+    if install_opts.stdout {
+        print!("{}", systemd_unit(&config, &inventory, install_opts.user)?);
+        return Ok(());
+    }
+
+    // The service only sees the token if it is saved in the config file
+    let saved_token = ConfigOpts::from_file(Some(config.clone()))
+        .ok()
+        .flatten()
+        .and_then(|cfg| cfg.verify.token);
+    if saved_token.is_none() {
+        warn!(
+            "no token is saved in '{}', the service will not see tokens given on the command line or environment",
+            config.display()
+        );
+    }
+
+    if install_opts.windows {
+        let log_file = match install_opts.log_file {
+            Some(ref path) => absolute(path.clone())?,
+            None => default_state_path()
+                .context("no state directory found for the service log, use `--log-file`")?
+                .with_file_name("service.log"),
+        };
+        windows::install(&config, &inventory, &log_file, install_opts.enable)
+    } else {
+        install_systemd(&config, &inventory, install_opts).await
+    }
+}
+
+#[tracing::instrument(level = "trace", skip_all)]
+async fn uninstall(uninstall_opts: &UninstallOpts) -> Result<()> {
+    if uninstall_opts.windows {
+        windows::uninstall()
+    } else {
+        uninstall_systemd(uninstall_opts.user).await
+    }
+}
+
+/// Write a systemd unit and optionally enable it.
+async fn install_systemd(
+    config: &Path,
+    inventory: &Path,
+    install_opts: &InstallOpts,
+) -> Result<()> {
+    let unit = systemd_unit(config, inventory, install_opts.user)?;
+
+    // Save
+    let path = systemd_unit_path(install_opts.user)?;
+    util::fs::remove_interactive(&path).await?;
+    cddns_core::util::fs::save(&path, unit)
+        .await
+        .with_context(|| {
+            if install_opts.user {
+                "writing the user unit"
+            } else {
+                "writing the system unit, try again as root or with `--user`"
+            }
+        })?;
+    info!("installed: '{}'", path.display());
+
+    // Enable
+    let unit_name = format!("{SERVICE_NAME}.service");
+    if install_opts.enable {
+        systemctl(install_opts.user, &["daemon-reload"]).await?;
+        systemctl(install_opts.user, &["enable", "--now", &unit_name]).await?;
+        info!("enabled and started: {unit_name}");
+    } else {
+        let user = if install_opts.user { " --user" } else { "" };
+        info!("to start the service, run: systemctl{user} daemon-reload && systemctl{user} enable --now {unit_name}");
+    }
+    Ok(())
+}
+
+/// Disable and remove a systemd unit.
+async fn uninstall_systemd(user: bool) -> Result<()> {
+    let path = systemd_unit_path(user)?;
+    if !path.exists() {
+        bail!("no unit is installed at '{}'", path.display());
+    }
+    let unit_name = format!("{SERVICE_NAME}.service");
+    systemctl(user, &["disable", "--now", &unit_name]).await?;
+    cddns_core::util::fs::remove_force(&path).await?;
+    systemctl(user, &["daemon-reload"]).await?;
+    info!("uninstalled: '{}'", path.display());
+    Ok(())
+}
+
+/// Return a systemd unit running `inventory watch` with the given config
+/// and inventory files.
+fn systemd_unit(config: &Path, inventory: &Path, user: bool) -> Result<String> {
+    let exe = std::env::current_exe().context("locating the cddns binary")?;
+    let exec_start = [
+        quote(&exe.to_string_lossy()),
+        "--config".to_string(),
+        quote(&config.to_string_lossy()),
+        "inventory".to_string(),
+        "--path".to_string(),
+        quote(&inventory.to_string_lossy()),
+        "watch".to_string(),
+    ]
+    .join(" ");
+
+    let mut unit = String::new();
+    writeln!(unit, "[Unit]")?;
+    writeln!(unit, "Description=Cloudflare Dynamic DNS (cddns)")?;
+    writeln!(unit, "Documentation=https://github.com/simbleau/cddns")?;
+    if !user {
+        // Network targets are not available to user managers
+        writeln!(unit, "Wants=network-online.target")?;
+        writeln!(unit, "After=network-online.target")?;
+    }
+    writeln!(unit)?;
+    writeln!(unit, "[Service]")?;
+    writeln!(unit, "Type=simple")?;
+    writeln!(unit, "ExecStart={exec_start}")?;
+    writeln!(unit, "ExecReload=/bin/kill -HUP $MAINPID")?;
+    writeln!(unit, "Restart=on-failure")?;
+    writeln!(unit, "RestartSec=30")?;
+    writeln!(unit, "NoNewPrivileges=yes")?;
+    writeln!(unit, "LockPersonality=yes")?;
+    writeln!(
+        unit,
+        "RestrictAddressFamilies=AF_UNIX AF_INET AF_INET6 AF_NETLINK"
+    )?;
+    if !user {
+        // State and cache are kept under /var, as home directories are
+        // read-only
+        writeln!(unit, "Environment=XDG_STATE_HOME=/var/lib")?;
+        writeln!(unit, "Environment=XDG_CACHE_HOME=/var/cache")?;
+        writeln!(unit, "StateDirectory={SERVICE_NAME}")?;
+        writeln!(unit, "CacheDirectory={SERVICE_NAME}")?;
+        writeln!(unit, "ProtectSystem=strict")?;
+        writeln!(unit, "ProtectHome=read-only")?;
+        writeln!(unit, "PrivateTmp=yes")?;
+        writeln!(unit, "PrivateDevices=yes")?;
+        writeln!(unit, "ProtectKernelTunables=yes")?;
+        writeln!(unit, "ProtectKernelModules=yes")?;
+        writeln!(unit, "ProtectControlGroups=yes")?;
+        writeln!(unit, "RestrictSUIDSGID=yes")?;
+    }
+    writeln!(unit)?;
+    writeln!(unit, "[Install]")?;
+    if user {
+        writeln!(unit, "WantedBy=default.target")?;
+    } else {
+        writeln!(unit, "WantedBy=multi-user.target")?;
+    }
+    Ok(unit)
+}
+
+/// Return the install location of the systemd unit.
+///
+/// - System: /etc/systemd/system/cddns.service
+/// - User: $XDG_CONFIG_HOME/systemd/user/cddns.service or
+///   $HOME/.config/systemd/user/cddns.service
+fn systemd_unit_path(user: bool) -> Result<PathBuf> {
+    let dir = if user {
+        let base_dirs = directories::BaseDirs::new()
+            .context("no home directory found for user units")?;
+        base_dirs.config_dir().join("systemd").join("user")
+    } else {
+        PathBuf::from("/etc/systemd/system")
+    };
+    Ok(dir.join(format!("{SERVICE_NAME}.service")))
+}
+
+/// Run `systemctl` on the system or user manager.
+async fn systemctl(user: bool, args: &[&str]) -> Result<()> {
+    let mut command = Command::new("systemctl");
+    if user {
+        command.arg("--user");
+    }
+    let status = command
+        .args(args)
+        .status()
+        .await
+        .context("running systemctl")?;
+    if !status.success() {
+        bail!("`systemctl {}` failed: {status}", args.join(" "));
+    }
+    Ok(())
+}
+
+/// Quote an argument for a systemd command line.
+fn quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{escaped}\"")
+}
+
+/// Register and remove Windows services.
+#[cfg(windows)]
+mod windows {
+    use super::SERVICE_NAME;
+    use anyhow::{Context, Result};
+    use std::ffi::OsString;
+    use std::path::Path;
+    use std::time::Duration;
+    use tracing::info;
+    use windows_service::service::{
+        ServiceAccess, ServiceAction, ServiceActionType, ServiceErrorControl,
+        ServiceFailureActions, ServiceFailureResetPeriod, ServiceInfo,
+        ServiceStartType, ServiceState, ServiceType,
+    };
+    use windows_service::service_manager::{
+        ServiceManager, ServiceManagerAccess,
+    };
+
+    /// Register a service running `inventory watch` with the given config
+    /// and inventory files, and optionally start it.
+    pub fn install(
+        config: &Path,
+        inventory: &Path,
+        log_file: &Path,
+        start: bool,
+    ) -> Result<()> {
+        let manager = ServiceManager::local_computer(
+            None::<&str>,
+            ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+        )
+        .context("unable to connect to the service control manager, try again as an administrator")?;
+        let info = ServiceInfo {
+            name: OsString::from(SERVICE_NAME),
+            display_name: OsString::from("Cloudflare Dynamic DNS (cddns)"),
+            service_type: ServiceType::OWN_PROCESS,
+            start_type: ServiceStartType::AutoStart,
+            error_control: ServiceErrorControl::Normal,
+            executable_path: std::env::current_exe()
+                .context("locating the cddns binary")?,
+            launch_arguments: vec![
+                OsString::from("--config"),
+                config.into(),
+                OsString::from("inventory"),
+                OsString::from("--path"),
+                inventory.into(),
+                OsString::from("watch"),
+                OsString::from("--service"),
+                OsString::from("--log-file"),
+                log_file.into(),
+            ],
+            dependencies: vec![],
+            // Run as LocalSystem
+            account_name: None,
+            account_password: None,
+        };
+        if let Some(dir) = log_file.parent() {
+            std::fs::create_dir_all(dir).with_context(|| {
+                format!("unable to create '{}'", dir.display())
+            })?;
+        }
+        let service = manager
+            .create_service(
+                &info,
+                ServiceAccess::CHANGE_CONFIG | ServiceAccess::START,
+            )
+            .context("unable to register the service")?;
+        service
+            .set_description("Keeps Cloudflare DNS records up to date.")
+            .context("unable to describe the service")?;
+
+        // Restart after failures, including errors reported on exit
+        let restart = ServiceAction {
+            action_type: ServiceActionType::Restart,
+            delay: Duration::from_secs(30),
+        };
+        service
+            .update_failure_actions(ServiceFailureActions {
+                reset_period: ServiceFailureResetPeriod::After(
+                    Duration::from_secs(24 * 60 * 60),
+                ),
+                reboot_msg: None,
+                command: None,
+                actions: Some(vec![restart.clone(), restart.clone(), restart]),
+            })
+            .context("unable to set the service recovery actions")?;
+        service
+            .set_failure_actions_on_non_crash_failures(true)
+            .context("unable to set the service recovery actions")?;
+        info!(
+            "installed service: {SERVICE_NAME}, logging to '{}'",
+            log_file.display()
+        );
+
+        if start {
+            service
+                .start::<OsString>(&[])
+                .context("unable to start the service")?;
+            info!("started service: {SERVICE_NAME}");
+        } else {
+            info!("to start the service, run: sc.exe start {SERVICE_NAME}");
+        }
+        Ok(())
+    }
+
+    /// Stop and remove the service.
+    pub fn uninstall() -> Result<()> {
+        let manager = ServiceManager::local_computer(
+            None::<&str>,
+            ServiceManagerAccess::CONNECT,
+        )
+        .context("unable to connect to the service control manager, try again as an administrator")?;
+        let service = manager
+            .open_service(
+                SERVICE_NAME,
+                ServiceAccess::QUERY_STATUS
+                    | ServiceAccess::STOP
+                    | ServiceAccess::DELETE,
+            )
+            .context("unable to open the service")?;
+        // The service is removed once stopped and all handles are closed
+        service.delete().context("unable to remove the service")?;
+        if service.query_status()?.current_state != ServiceState::Stopped {
+            service.stop().context("unable to stop the service")?;
+        }
+        info!("uninstalled service: {SERVICE_NAME}");
+        Ok(())
+    }
+}
+
+/// Register and remove Windows services, which are unsupported on this OS.
+#[cfg(not(windows))]
+mod windows {
+    use anyhow::{bail, Result};
+    use std::path::Path;
+
+    pub fn install(
+        _config: &Path,
+        _inventory: &Path,
+        _log_file: &Path,
+        _start: bool,
+    ) -> Result<()> {
+        bail!("Windows services are only supported on Windows")
+    }
+
+    pub fn uninstall() -> Result<()> {
+        bail!("Windows services are only supported on Windows")
+    }
+}
+
+/// Resolve a path against the working directory, as services do not share
+/// it.
+fn absolute(path: PathBuf) -> Result<PathBuf> {
+    std::path::absolute(&path)
+        .with_context(|| format!("resolving '{}'", path.display()))
+}
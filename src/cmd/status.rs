@@ -0,0 +1,83 @@
+use anyhow::Result;
+use cddns_core::cloudflare;
+use cddns_core::config::models::{ConfigOpts, ConfigOptsVerify};
+use cddns_core::error::Error;
+use cddns_core::inventory::audit::{self, AuditEntry};
+use cddns_core::inventory::state::State;
+use clap::Args;
+use std::collections::BTreeMap;
+use tracing::info;
+
+/// Show the status of your token, Cloudflare API quota, and recent updates.
+#[derive(Debug, Args)]
+#[clap(name = "status")]
+pub struct StatusCmd {
+    #[clap(flatten)]
+    pub cfg: ConfigOptsVerify,
+    /// The number of recent changes to show for each record.
+    #[clap(long, value_name = "count", default_value_t = 3)]
+    pub history: usize,
+}
+
+impl StatusCmd {
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub async fn run(self, opts: ConfigOpts) -> Result<()> {
+        // Apply CLI configuration layering
+        let cli_opts = ConfigOpts::builder().verify(Some(self.cfg)).build();
+        let opts = ConfigOpts::builder().merge(opts).merge(cli_opts).build();
+
+        // Run
+        status(&opts, self.history).await
+    }
+}
+
+#[tracing::instrument(level = "trace", skip_all)]
+async fn status(opts: &ConfigOpts, history: usize) -> Result<()> {
+    cloudflare::cache::ensure_online("retrieve status")?;
+    info!("retrieving status, please wait...");
+    // Get token
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
+
+    // Token status
+    match cloudflare::endpoints::verify(token).await {
+        Ok(_) => println!("Token: valid"),
+        Err(err) => println!("Token: invalid ({err})"),
+    }
+
+    // API quota
+    match cloudflare::requests::rate_limit() {
+        Some(rate_limit) => println!("API rate limit: {rate_limit}"),
+        None => println!("API rate limit: unknown"),
+    }
+
+    // State of previous updates
+    let state = State::load().await?;
+    let unknown = || "unknown".to_string();
+    if let Some(observed) = state.observed {
+        let ipv4 = state.ipv4.map_or_else(unknown, |ip| ip.to_string());
+        let ipv6 = state.ipv6.map_or_else(unknown, |ip| ip.to_string());
+        println!("Public IPv4: {ipv4}");
+        println!("Public IPv6: {ipv6}");
+        for (name, ips) in state.uplinks.iter() {
+            let ipv4 = ips.ipv4.map_or_else(unknown, |ip| ip.to_string());
+            let ipv6 = ips.ipv6.map_or_else(unknown, |ip| ip.to_string());
+            println!("Uplink {name}: {ipv4}, {ipv6}");
+        }
+        println!("Last observed: {observed}");
+    } else {
+        println!("Last observed: never");
+    }
+
+    // Recent changes, per record
+    let mut changes: BTreeMap<String, Vec<AuditEntry>> = BTreeMap::new();
+    for entry in audit::read().await? {
+        changes.entry(entry.name.clone()).or_default().push(entry);
+    }
+    for (name, entries) in changes {
+        println!("History of {name}:");
+        for entry in entries.iter().rev().take(history) {
+            println!("  {}: {} -> {}", entry.time, entry.from, entry.to);
+        }
+    }
+    Ok(())
+}
@@ -1,8 +1,17 @@
-use crate::cloudflare;
-use crate::config::models::{ConfigOpts, ConfigOptsVerify};
+use crate::exit::{error_code, ExitCode};
+use crate::util::layers;
 use anyhow::{Context, Result};
+use cddns_core::cloudflare;
+use cddns_core::cloudflare::models::CloudflareMessage;
+use cddns_core::config::models::{ConfigOpts, ConfigOptsVerify};
+use cddns_core::error::Error;
 use clap::Args;
-use tracing::info;
+use std::time::Duration;
+use tokio::time::{self, Instant};
+use tracing::{info, warn};
+
+/// The longest delay between verification attempts with `--wait`.
+const MAX_WAIT_BACKOFF: Duration = Duration::from_secs(30);
 
 /// Verify authentication to Cloudflare.
 #[derive(Debug, Args)]
@@ -10,6 +19,26 @@ use tracing::info;
 pub struct VerifyCmd {
     #[clap(flatten)]
     pub cfg: ConfigOptsVerify,
+    /// Retry verification with backoff until it succeeds or times out.
+    #[clap(long)]
+    pub wait: bool,
+    /// The maximum time to wait for verification with `--wait`.
+    #[clap(
+        long,
+        value_name = "duration",
+        value_parser = humantime::parse_duration,
+        default_value = "120s",
+        requires = "wait"
+    )]
+    pub timeout: Duration,
+    /// Print nothing, not even errors, communicating the result by exit
+    /// code only.
+    #[clap(long)]
+    pub quiet: bool,
+    /// Print the result as a JSON object on stdout, with whether the token
+    /// is valid and the error code of a failure. Logs are written to stderr.
+    #[clap(long)]
+    pub json: bool,
 }
 
 impl VerifyCmd {
@@ -20,25 +49,111 @@ impl VerifyCmd {
         let opts = ConfigOpts::builder().merge(opts).merge(cli_opts).build();
 
         // Run
-        verify(&opts).await
+        let result = if self.wait {
+            verify_wait(&opts, self.timeout).await
+        } else {
+            verify(&opts).await
+        };
+
+        // Log responses
+        if let Ok(ref cf_messages) = result {
+            for (i, response) in cf_messages.iter().enumerate() {
+                info!(response = i + 1, response.message);
+            }
+            info!("verification complete");
+        }
+        if self.json {
+            let output = match result {
+                Ok(ref cf_messages) => serde_json::json!({
+                    "valid": true,
+                    "messages": cf_messages
+                        .iter()
+                        .map(|m| m.message.as_str())
+                        .collect::<Vec<_>>(),
+                }),
+                Err(ref err) => serde_json::json!({
+                    "valid": false,
+                    "error": {
+                        "code": error_code(err),
+                        "message": format!("{err:#}"),
+                        "exit_code": ExitCode::of(err) as i32,
+                    },
+                }),
+            };
+            println!("{output}");
+        }
+        result.map(|_| ())
+    }
+
+    /// Returns whether nothing is printed but the result, with `--quiet`.
+    pub fn silent(&self) -> bool {
+        self.quiet
+    }
+
+    /// Returns whether the result is printed as JSON, with `--json`.
+    pub fn json(&self) -> bool {
+        self.json
     }
 }
 
 #[tracing::instrument(level = "trace", skip_all)]
-async fn verify(opts: &ConfigOpts) -> Result<()> {
+async fn verify(opts: &ConfigOpts) -> Result<Vec<CloudflareMessage>> {
     info!("verifying, please wait...");
     // Get token
-    let token = opts
-        .verify.token.as_ref()
-        .context("no token was provided, need help? see https://github.com/simbleau/cddns#readme")?;
+    let token = opts.verify.token.as_ref().ok_or(Error::MissingToken)?;
     // Get response
-    let cf_messages = cloudflare::endpoints::verify(token)
+    cloudflare::endpoints::verify(token)
         .await
-        .context("verification failure, need help? see https://github.com/simbleau/cddns#readme")?;
-    // Log responses
-    for (i, response) in cf_messages.iter().enumerate() {
-        info!(response = i + 1, response.message);
+        .context("verification failure, need help? see https://github.com/simbleau/cddns#readme")
+}
+
+#[tracing::instrument(level = "trace", skip_all)]
+async fn verify_wait(
+    opts: &ConfigOpts,
+    timeout: Duration,
+) -> Result<Vec<CloudflareMessage>> {
+    info!(
+        "waiting up to {} for verification, please wait...",
+        humantime::format_duration(timeout)
+    );
+    // Poll with backoff until the deadline, bounding each attempt by it
+    let deadline = Instant::now() + timeout;
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let attempt = async {
+            let token = wait_token(opts)?;
+            cloudflare::endpoints::verify(&token).await
+        };
+        let result = time::timeout(remaining, attempt)
+            .await
+            .unwrap_or(Err(Error::Timeout));
+        match result {
+            Ok(cf_messages) => return Ok(cf_messages),
+            Err(err) => {
+                let remaining =
+                    deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Err(err).context("verification timed out, need help? see https://github.com/simbleau/cddns#readme");
+                }
+                let delay = backoff.min(remaining);
+                warn!(
+                    retry_ms = delay.as_millis(),
+                    "verification failed: {err}"
+                );
+                time::sleep(delay).await;
+                backoff = (backoff * 2).min(MAX_WAIT_BACKOFF);
+            }
+        }
     }
-    info!("verification complete");
-    Ok(())
+}
+
+/// Return the token to verify, reloading the configuration while none was
+/// provided, such as a config file mounted after startup.
+fn wait_token(opts: &ConfigOpts) -> Result<String, Error> {
+    opts.verify
+        .token
+        .clone()
+        .or_else(|| layers::load().ok()?.verify.token)
+        .ok_or(Error::MissingToken)
 }
@@ -0,0 +1,109 @@
+//! cddns exit codes.
+//!
+//! Exit codes are a stable contract for scripts and monitoring wrappers:
+//! - 0: success
+//! - 1: unclassified error
+//! - 2: invalid command line usage
+//! - 3: configuration error, such as an unreadable config or inventory file, or
+//!   another local file which can't be accessed
+//! - 4: authentication failure, such as a missing or rejected token
+//! - 5: network error, such as a timeout, rate limit, or server error
+//! - 6: outdated records were found
+//! - 7: invalid records were found, taking precedence over outdated records
+
+use cddns_core::error::Error;
+use std::fmt::{self, Display};
+
+/// The exit code of a cddns process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitCode {
+    Failure = 1,
+    Config = 3,
+    Auth = 4,
+    Network = 5,
+    Outdated = 6,
+    Invalid = 7,
+}
+
+impl ExitCode {
+    /// Classify an error by its typed causes.
+    pub fn of(err: &anyhow::Error) -> Self {
+        if let Some(failure) = err.downcast_ref::<InventoryFailure>() {
+            return failure.code();
+        }
+        if err.downcast_ref::<ConfigError>().is_some() {
+            return ExitCode::Config;
+        }
+        match err.downcast_ref::<Error>() {
+            Some(Error::MissingToken | Error::Auth { .. }) => ExitCode::Auth,
+            Some(
+                Error::RateLimited { .. }
+                | Error::Server { .. }
+                | Error::Network(_)
+                | Error::Timeout,
+            ) => ExitCode::Network,
+            Some(
+                Error::Parse { .. }
+                | Error::Config(_)
+                | Error::InventoryNotFound(_)
+                | Error::Io { .. },
+            ) => ExitCode::Config,
+            _ => ExitCode::Failure,
+        }
+    }
+}
+
+/// Return the machine-stable code of an error, such as `AUTH_FAILED`, by its
+/// typed causes.
+pub fn error_code(err: &anyhow::Error) -> &'static str {
+    if let Some(failure) = err.downcast_ref::<InventoryFailure>() {
+        return match failure.code() {
+            ExitCode::Invalid => "RECORDS_INVALID",
+            _ => "RECORDS_OUTDATED",
+        };
+    }
+    if err.downcast_ref::<ConfigError>().is_some() {
+        return "CONFIG_INVALID";
+    }
+    err.downcast_ref::<Error>().map_or("FAILURE", Error::code)
+}
+
+/// A marker for errors in the configuration layers.
+#[derive(Debug)]
+pub struct ConfigError;
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error loading configuration")
+    }
+}
+
+/// Records which remain outdated or invalid after a command.
+#[derive(Debug)]
+pub struct InventoryFailure {
+    pub outdated: usize,
+    pub invalid: usize,
+}
+
+impl InventoryFailure {
+    /// The exit code of the failure.
+    pub fn code(&self) -> ExitCode {
+        if self.invalid > 0 {
+            ExitCode::Invalid
+        } else {
+            ExitCode::Outdated
+        }
+    }
+}
+
+impl Display for InventoryFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} outdated and {} invalid records remain",
+            self.outdated, self.invalid
+        )
+    }
+}
+
+impl std::error::Error for InventoryFailure {}
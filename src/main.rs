@@ -1,26 +1,19 @@
 // Clippy
 #![deny(clippy::unwrap_used)] // use context/with_context
 #![deny(clippy::expect_used)] // use context/with_context
-// Features
-#![feature(slice_pattern)]
-#![feature(try_blocks)]
-#![feature(unwrap_infallible)]
-#![feature(iter_intersperse)]
-#![feature(exact_size_is_empty)]
-#![feature(is_some_and)]
-#![feature(async_closure)]
-#![feature(option_get_or_insert_default)]
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use config::models::ConfigOpts;
+use cddns_core::cloudflare;
+use cddns_core::config::models::{ConfigOpts, ConfigOptsApi, ConfigOptsIp};
+use cddns_core::util::progress;
+use clap::{CommandFactory, Parser, Subcommand};
+use exit::{error_code, ExitCode, InventoryFailure};
 use std::path::PathBuf;
 use tracing::{error, Level};
 use tracing_subscriber::prelude::*;
-mod cloudflare;
+use util::logging::{ColorChoice, ErrorFormat, LogLevel};
 mod cmd;
-mod config;
-mod inventory;
+mod exit;
 mod util;
 
 /// Cloudflare DDNS command line utility
@@ -29,37 +22,173 @@ mod util;
 struct Args {
     #[clap(subcommand)]
     action: Subcommands,
-    /// A config file to use. [default: $XDG_CONFIG_HOME/cddns/config.toml]
+    /// A config file to use, or `-` to read it from stdin. [default:
+    /// $XDG_CONFIG_HOME/cddns/config.toml]
     #[clap(short, long, env = "CDDNS_CONFIG", value_name = "file")]
     pub config: Option<PathBuf>,
-    /// Enable verbose logging.
+    /// Enable verbose logging, as `--log-level trace`.
     #[clap(short)]
     pub v: bool,
+    /// The verbosity of logs, overriding RUST_LOG and `-v`.
+    #[clap(
+        long,
+        value_name = "level",
+        env = "CDDNS_LOG_LEVEL",
+        conflicts_with = "v"
+    )]
+    pub log_level: Option<LogLevel>,
+    /// When to color logs. NO_COLOR and CLICOLOR_FORCE are honored with
+    /// `auto`.
+    #[clap(
+        long,
+        value_name = "when",
+        env = "CDDNS_COLOR",
+        value_enum,
+        default_value = "auto"
+    )]
+    pub color: ColorChoice,
+    /// How the error ending a command is printed: `text` logs it, while
+    /// `json` prints an object with a machine-stable `error.code` to stderr.
+    #[clap(
+        long,
+        value_name = "format",
+        env = "CDDNS_ERROR_FORMAT",
+        value_enum,
+        default_value = "text"
+    )]
+    pub error_format: ErrorFormat,
+    /// Include timestamps in logs. [default: true for `inventory watch`,
+    /// otherwise false]
+    #[clap(long, value_name = "bool", env = "CDDNS_TIMESTAMPS")]
+    pub timestamps: Option<bool>,
+    /// Only log errors, as `--log-level error`, so printed data can be
+    /// captured cleanly.
+    #[clap(
+        short,
+        long,
+        env = "CDDNS_QUIET",
+        conflicts_with_all = ["v", "log_level"]
+    )]
+    pub quiet: bool,
     /// Your Cloudflare API key token.
     #[clap(short, long, value_name = "token")]
     pub token: Option<String>,
+    /// Log full Cloudflare requests and responses, with the token redacted.
+    #[clap(long, env = "CDDNS_TRACE_HTTP")]
+    pub trace_http: bool,
+    /// Suppress deprecation and compatibility warnings.
+    #[clap(long, env = "CDDNS_NO_WARNINGS")]
+    pub no_warnings: bool,
+    /// Disable the daily check for new releases during `inventory watch`.
+    #[clap(long, env = "CDDNS_DISABLE_UPDATE_CHECK")]
+    pub disable_update_check: bool,
+    /// Serve Cloudflare data from the cache, refusing write operations.
+    #[clap(long, env = "CDDNS_OFFLINE")]
+    pub offline: bool,
+    /// List zones from Cloudflare, instead of serving zones cached within
+    /// `api.zones_ttl`.
+    #[clap(long, env = "CDDNS_REFRESH_ZONES")]
+    pub refresh_zones: bool,
+    /// Serve synthetic zones and records from a fixture file, recording
+    /// updates in memory.
+    #[clap(long, env = "CDDNS_MOCK", value_name = "fixture")]
+    pub mock: Option<PathBuf>,
+    /// Ignore config files and read settings from `CDDNS_*` environment
+    /// variables only, refusing unknown variables and a missing token or
+    /// inventory path.
+    #[clap(long, env = "CDDNS_ENV_ONLY", conflicts_with = "config")]
+    pub env_only: bool,
+    #[clap(flatten)]
+    pub api: ConfigOptsApi,
+    #[clap(flatten)]
+    pub ip: ConfigOptsIp,
 }
 
 impl Args {
+    /// Return the daemon options, if the command should run as a daemon or
+    /// service.
+    pub fn daemon(&self) -> Option<&util::daemon::DaemonOpts> {
+        match self.action {
+            Subcommands::Inventory(ref inner) => inner.daemon(),
+            _ => None,
+        }
+    }
+
+    /// Returns whether logs are only output when records change or an error
+    /// occurs, with `inventory update --changes-only`.
+    pub fn changes_only(&self) -> bool {
+        match self.action {
+            Subcommands::Inventory(ref inner) => inner.changes_only(),
+            _ => false,
+        }
+    }
+
+    /// Returns whether no logs are printed, not even errors, with
+    /// `verify --quiet`.
+    pub fn silent(&self) -> bool {
+        match self.action {
+            Subcommands::Verify(ref inner) => inner.silent(),
+            _ => false,
+        }
+    }
+
+    /// Returns whether stdout carries a JSON report, so logs go to stderr,
+    /// with `inventory --summary-json` or `verify --json`.
+    pub fn json_output(&self) -> bool {
+        match self.action {
+            Subcommands::Inventory(ref inner) => inner.summary_json(),
+            Subcommands::Verify(ref inner) => inner.json(),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the command runs continuously, with `inventory watch`.
+    pub fn watching(&self) -> bool {
+        match self.action {
+            Subcommands::Inventory(ref inner) => inner.watching(),
+            _ => false,
+        }
+    }
+
     #[tracing::instrument(level = "trace", skip_all)]
     pub async fn run(self) -> Result<()> {
+        cddns_core::util::warnings::set_enabled(!self.no_warnings);
+        cddns_core::util::release::set_enabled(!self.disable_update_check);
+        cloudflare::cache::set_offline(self.offline);
+
         // Apply CLI configuration layering
-        let default_cfg = ConfigOpts::default();
-        let toml_cfg = ConfigOpts::from_file(self.config)?;
-        let env_cfg = ConfigOpts::from_env()?;
-        let cli_cfg = ConfigOpts::builder().verify_token(self.token).build();
-        let opts = ConfigOpts::builder()
-            .merge(default_cfg)
-            .merge(toml_cfg)
-            .merge(env_cfg)
-            .merge(cli_cfg)
+        let cli_cfg = ConfigOpts::builder()
+            .api(Some(self.api))
+            .ip(Some(self.ip))
+            .verify_token(self.token)
             .build();
+        util::layers::init(
+            self.config,
+            cli_cfg,
+            self.offline || self.mock.is_some(),
+            self.env_only
+                .then(|| util::layers::env_vars(&Args::command())),
+        );
+        let opts = util::layers::load()?;
+        cloudflare::requests::init(&opts, self.trace_http)?;
+        if !self.refresh_zones {
+            let zones_ttl = opts.api.zones_ttl.unwrap_or_default();
+            cloudflare::cache::set_zones_ttl(zones_ttl);
+        }
+        if let Some(ref fixture) = self.mock {
+            cloudflare::mock::init(fixture)?;
+        }
 
         match self.action {
             Subcommands::Config(inner) => inner.run(opts).await,
             Subcommands::Verify(inner) => inner.run(opts).await,
             Subcommands::List(inner) => inner.run(opts).await,
+            Subcommands::Record(inner) => inner.run(opts).await,
+            Subcommands::Acme(inner) => inner.run(opts).await,
             Subcommands::Inventory(inner) => inner.run(opts).await,
+            Subcommands::Status(inner) => inner.run(opts).await,
+            Subcommands::Rollback(inner) => inner.run(opts).await,
+            Subcommands::Service(inner) => inner.run(opts).await,
         }
     }
 }
@@ -69,62 +198,152 @@ enum Subcommands {
     Config(cmd::config::ConfigCmd),
     Verify(cmd::verify::VerifyCmd),
     List(cmd::list::ListCmd),
+    Record(cmd::record::RecordCmd),
+    Acme(cmd::acme::AcmeCmd),
     Inventory(cmd::inventory::InventoryCmd),
+    Status(cmd::status::StatusCmd),
+    Rollback(cmd::rollback::RollbackCmd),
+    Service(cmd::service::ServiceCmd),
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+fn main() -> Result<()> {
     let args = Args::parse();
+    let daemon = args.daemon().cloned();
+
+    // Read a piped config before forking, which detaches stdin. Errors are
+    // reported when the config is loaded.
+    if util::layers::is_stdin(args.config.as_deref()) && !args.env_only {
+        let _ = util::layers::stdin_config();
+    }
 
+    // Daemon and service output is written to a log file
+    let ansi = daemon.is_none() && args.color.enabled();
     #[cfg(windows)]
-    if let Err(err) = ansi_term::enable_ansi_support() {
-        eprintln!("error enabling ANSI support: {:?}", err);
+    if ansi {
+        if let Err(err) = ansi_term::enable_ansi_support() {
+            eprintln!("error enabling ANSI support: {:?}", err);
+        }
     }
 
-    // Filter spans based on the RUST_LOG env var or -v flag.
-    let (verbose, log_filter) =
-        match tracing_subscriber::EnvFilter::try_from_default_env() {
-            Ok(filter) => {
-                if filter.max_level_hint().is_some_and(|f| f >= Level::DEBUG) {
-                    (true, filter)
-                } else {
-                    (false, filter)
-                }
+    // Filter spans based on the --log-level or --quiet flags, RUST_LOG env
+    // var or -v flag.
+    let log_level = args.log_level.or(args.quiet.then_some(LogLevel::Error));
+    let env_filter = match log_level {
+        Some(_) => None,
+        None => tracing_subscriber::EnvFilter::try_from_default_env().ok(),
+    };
+    let (verbose, log_filter) = match env_filter {
+        Some(filter) => {
+            if filter.max_level_hint().is_some_and(|f| f >= Level::DEBUG) {
+                (true, filter)
+            } else {
+                (false, filter)
             }
-            Err(_) => (
-                args.v,
-                tracing_subscriber::EnvFilter::new(if args.v {
-                    "info,cddns=trace"
-                } else {
-                    "info"
-                }),
-            ),
-        };
+        }
+        None => {
+            let level = log_level.unwrap_or(if args.v {
+                LogLevel::Trace
+            } else {
+                LogLevel::Info
+            });
+            (
+                level >= LogLevel::Debug,
+                tracing_subscriber::EnvFilter::new(level.directives()),
+            )
+        }
+    };
+
+    let log_filter = if args.silent() {
+        tracing_subscriber::EnvFilter::new("off")
+    } else {
+        log_filter
+    };
+
+    // Format tracing, with timestamps by default only for `watch`, where
+    // they're essential
+    let format = tracing_subscriber::fmt::layer()
+        .with_target(false)
+        .with_level(true)
+        .with_ansi(ansi)
+        .with_writer(util::daemon::log_writer(daemon.as_ref())?)
+        .compact();
+    let format = if args.timestamps.unwrap_or(args.watching()) {
+        format.boxed()
+    } else {
+        format.without_time().boxed()
+    };
 
     // Enable tracing/logging
     tracing_subscriber::registry()
-        // Filter spans based on the RUST_LOG env var or -v flag.
+        // Filter spans based on the --log-level or --quiet flags, RUST_LOG
+        // env var or -v flag.
         .with(log_filter)
+        // Hide routine progress logs when requested
+        .with(tracing_subscriber::filter::dynamic_filter_fn(|meta, _| {
+            meta.target() != progress::TARGET || !progress::is_hidden()
+        }))
         // Format tracing
-        .with(
-            tracing_subscriber::fmt::layer()
-                .with_target(false)
-                .with_level(true)
-                .compact(),
-        )
+        .with(format)
         // Install this registry as the global tracing registry.
         .try_init()
         .context("error initializing logging")?;
 
-    if let Err(err) = args.run().await {
-        if verbose {
+    // Keep stdout to the JSON report
+    if args.json_output() {
+        util::logging::to_stderr();
+    }
+
+    // Hold back logs until records change or an error occurs
+    if args.changes_only() {
+        util::logging::hold();
+    }
+
+    // Show fetch progress in interactive, one-shot commands
+    if !args.quiet
+        && daemon.is_none()
+        && !args.watching()
+        && !args.changes_only()
+        && !args.json_output()
+    {
+        util::progress::install();
+    }
+
+    let error_format = args.error_format;
+    let run = move || {
+        tokio::runtime::Runtime::new()
+            .context("error starting the async runtime")
+            .and_then(|runtime| runtime.block_on(args.run()))
+    };
+    let result = match daemon {
+        Some(ref opts) if opts.service => util::service::dispatch(run),
+        // Fork before the async runtime starts any threads
+        Some(ref opts) => util::daemon::daemonize(opts).and_then(|_| run()),
+        None => run(),
+    };
+    util::logging::release();
+    util::report::finish(&result);
+    if let Err(err) = result {
+        util::progress::clear();
+        let code = ExitCode::of(&err);
+        if error_format == ErrorFormat::Json {
+            let error = serde_json::json!({
+                "error": {
+                    "code": error_code(&err),
+                    "message": format!("{err:#}"),
+                    "exit_code": code as i32,
+                }
+            });
+            eprintln!("{error}");
+        } else if err.downcast_ref::<InventoryFailure>().is_some() {
+            // Remaining records were already logged
+        } else if verbose {
             error!("{err:?}");
         } else {
             error!(
                 "{err}\n\nEnable verbose logging (-v) for the full stack trace."
             );
         }
-        std::process::exit(1);
+        std::process::exit(code as i32);
     }
     Ok(())
 }
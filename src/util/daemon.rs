@@ -0,0 +1,93 @@
+use crate::util::logging;
+use anyhow::{Context, Result};
+use clap::{ArgGroup, Args};
+use std::path::PathBuf;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+
+#[derive(Debug, Clone, Args)]
+#[clap(group(ArgGroup::new("background").args(["daemon", "service"])))]
+pub struct DaemonOpts {
+    /// Fork to the background, for systems without a service manager
+    /// (Unix only).
+    #[clap(long)]
+    pub daemon: bool,
+    /// Write the process ID to this file when running as a daemon, refusing
+    /// to start if another daemon holds it.
+    #[clap(long, value_name = "file", requires = "daemon")]
+    pub pid_file: Option<PathBuf>,
+    /// Append output to this file when running as a daemon or service.
+    /// [default: discarded]
+    #[clap(long, value_name = "file", requires = "background")]
+    pub log_file: Option<PathBuf>,
+    /// Run under the Windows service control manager, as registered by
+    /// `service install --windows`.
+    #[clap(long, hide = true)]
+    pub service: bool,
+}
+
+/// Return the log output for the process: the log file of a service, which
+/// has no console, or stdout, which a daemon redirects when forking and
+/// which is shared with progress spinners and may hold back logs or move
+/// them to stderr.
+pub fn log_writer(opts: Option<&DaemonOpts>) -> Result<BoxMakeWriter> {
+    match opts {
+        Some(DaemonOpts {
+            service: true,
+            log_file: Some(path),
+            ..
+        }) if cfg!(windows) => {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| {
+                    format!("unable to open log file '{}'", path.display())
+                })?;
+            Ok(BoxMakeWriter::new(std::sync::Mutex::new(file)))
+        }
+        Some(DaemonOpts { service: true, .. }) if cfg!(windows) => {
+            Ok(BoxMakeWriter::new(std::io::sink))
+        }
+        _ => Ok(BoxMakeWriter::new(|| logging::Output)),
+    }
+}
+
+/// Fork to the background, detaching from the terminal and redirecting
+/// output. Must be called before any threads are started, such as those of
+/// the async runtime.
+#[cfg(unix)]
+pub fn daemonize(opts: &DaemonOpts) -> Result<()> {
+    use daemonize::{Daemonize, Stdio};
+    use std::fs::OpenOptions;
+
+    let output = |path: &Option<PathBuf>| -> Result<Stdio> {
+        Ok(match path {
+            Some(path) => OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| {
+                    format!("unable to open log file '{}'", path.display())
+                })?
+                .into(),
+            None => Stdio::devnull(),
+        })
+    };
+    // Relative paths, such as the inventory, resolve as before forking
+    let mut daemon = Daemonize::new()
+        .working_directory(std::env::current_dir()?)
+        .stdout(output(&opts.log_file)?)
+        .stderr(output(&opts.log_file)?);
+    if let Some(ref path) = opts.pid_file {
+        daemon = daemon.pid_file(path);
+    }
+    daemon.start().context("unable to start daemon")
+}
+
+/// Fork to the background, which is unsupported on this OS.
+#[cfg(not(unix))]
+pub fn daemonize(_opts: &DaemonOpts) -> Result<()> {
+    anyhow::bail!(
+        "daemon mode is only supported on Unix, use a service instead"
+    )
+}
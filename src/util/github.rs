@@ -0,0 +1,37 @@
+//! GitHub Actions workflow commands.
+//!
+//! Annotations are printed to stdout, where the runner picks them up and
+//! surfaces them in workflow summaries. See
+//! <https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions>
+
+/// Print a warning annotation.
+pub fn warning(title: &str, message: &str) {
+    annotate("warning", title, message);
+}
+
+/// Print an error annotation.
+pub fn error(title: &str, message: &str) {
+    annotate("error", title, message);
+}
+
+fn annotate(level: &str, title: &str, message: &str) {
+    println!(
+        "::{level} title={}::{}",
+        escape_property(title),
+        escape_data(message)
+    );
+}
+
+/// Escape the message of a workflow command.
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property of a workflow command, which also ends at `:` or `,`.
+fn escape_property(property: &str) -> String {
+    escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
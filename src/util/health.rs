@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{self, Duration};
+use tracing::{debug, info};
+
+/// The health of `watch`, served to probes such as those of Kubernetes.
+///
+/// - `/livez` succeeds while `watch` is running.
+/// - `/readyz` succeeds once every record has been reconciled successfully.
+#[derive(Clone, Default)]
+pub struct Health {
+    ready: Arc<AtomicBool>,
+}
+
+impl Health {
+    /// Serve the health endpoints on an address in the background.
+    pub async fn serve(&self, addr: SocketAddr) -> Result<()> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("unable to listen on {addr}"))?;
+        info!("serving health endpoints on http://{addr}");
+        let health = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        let health = health.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = health.respond(stream).await {
+                                debug!("unable to answer health probe: {e}");
+                            }
+                        });
+                    }
+                    Err(e) => debug!("unable to accept health probe: {e}"),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Mark `watch` as ready, after its first successful reconciliation.
+    pub fn set_ready(&self) {
+        self.ready.store(true, Ordering::Relaxed);
+    }
+
+    /// Answer a single probe, closing the connection afterwards.
+    async fn respond(&self, mut stream: TcpStream) -> Result<()> {
+        // Only the request line is needed
+        let mut buf = [0; 1024];
+        let len = time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+            .await
+            .context("timed out reading request")??;
+        let request = String::from_utf8_lossy(&buf[..len]);
+        let mut request_line = request.split_whitespace();
+        let method = request_line.next().unwrap_or_default();
+        let path = request_line
+            .next()
+            .and_then(|target| target.split('?').next())
+            .unwrap_or_default();
+
+        let ready = self.ready.load(Ordering::Relaxed);
+        let (status, body) = match (method, path) {
+            ("GET" | "HEAD", "/livez") => ("200 OK", "ok"),
+            ("GET" | "HEAD", "/readyz") if ready => ("200 OK", "ok"),
+            ("GET" | "HEAD", "/readyz") => {
+                ("503 Service Unavailable", "not ready")
+            }
+            ("GET" | "HEAD", _) => ("404 Not Found", "not found"),
+            _ => ("405 Method Not Allowed", "method not allowed"),
+        };
+        let mut response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        if method != "HEAD" {
+            response.push_str(body);
+        }
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use crate::exit::ConfigError;
+use anyhow::{Context, Result};
+use cddns_core::config::default_config_path;
+use cddns_core::config::models::ConfigOpts;
+use cddns_core::error::Error;
+use std::collections::BTreeSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// The config path reading the config from stdin.
+pub const STDIN: &str = "-";
+
+/// The configuration layers given on the command line, kept so the
+/// configuration can be rebuilt when reloaded.
+static LAYERS: OnceLock<Layers> = OnceLock::new();
+
+/// The config read from stdin, kept so it can be reloaded.
+static STDIN_CONFIG: OnceLock<String> = OnceLock::new();
+
+struct Layers {
+    /// The config file given on the command line, if any.
+    config: Option<PathBuf>,
+    /// Options given on the command line.
+    cli: ConfigOpts,
+    /// Whether requests are served without authentication, from the cache
+    /// or a mock fixture.
+    anonymous: bool,
+    /// The environment variables known to the command line, if config files
+    /// are ignored and settings are read from the environment only.
+    env_only: Option<BTreeSet<String>>,
+}
+
+/// Keep the configuration layers given on the command line.
+pub fn init(
+    config: Option<PathBuf>,
+    cli: ConfigOpts,
+    anonymous: bool,
+    env_only: Option<BTreeSet<String>>,
+) {
+    let _ = LAYERS.set(Layers {
+        config,
+        cli,
+        anonymous,
+        env_only,
+    });
+}
+
+/// Return the environment variables read by a command and its
+/// subcommands.
+pub fn env_vars(command: &clap::Command) -> BTreeSet<String> {
+    let mut vars: BTreeSet<String> = command
+        .get_arguments()
+        .filter_map(|arg| arg.get_env())
+        .filter_map(|var| var.to_str())
+        .map(str::to_owned)
+        .collect();
+    for subcommand in command.get_subcommands() {
+        vars.extend(env_vars(subcommand));
+    }
+    vars
+}
+
+/// Build the configuration from defaults, the config file, the environment
+/// and the command line, in increasing priority.
+pub fn load() -> Result<ConfigOpts> {
+    let layers = LAYERS.get().context("configuration layers not set")?;
+    let mut default_cfg = ConfigOpts::default();
+    let toml_cfg = match layers.env_only {
+        Some(ref known) => {
+            check_env(known)?;
+            // Files are only read from locations given explicitly
+            default_cfg.inventory.path = None;
+            None
+        }
+        None if is_stdin(layers.config.as_deref()) => Some(
+            ConfigOpts::from_toml(stdin_config()?)
+                .context("reading config from stdin as TOML data")
+                .context(ConfigError)?,
+        ),
+        None => {
+            ConfigOpts::from_file(layers.config.clone()).context(ConfigError)?
+        }
+    };
+    let env_cfg = ConfigOpts::from_env().context(ConfigError)?;
+    let mut opts = ConfigOpts::builder()
+        .merge(default_cfg)
+        .merge(toml_cfg)
+        .merge(env_cfg)
+        .merge(layers.cli.clone())
+        .build();
+    // Cached and mocked data is served without authentication
+    if layers.anonymous {
+        opts.verify.token.get_or_insert_with(String::new);
+    }
+    if layers.env_only.is_some() && opts.verify.token.is_none() {
+        return Err(Error::Config(
+            "no token was provided, set CDDNS_VERIFY_TOKEN".into(),
+        )
+        .into());
+    }
+    Ok(opts)
+}
+
+/// Returns whether a config path reads the config from stdin.
+pub fn is_stdin(config: Option<&Path>) -> bool {
+    config.is_some_and(|path| path == Path::new(STDIN))
+}
+
+/// Return the config read from stdin, reading it on first use. Read it
+/// before forking, which detaches stdin.
+pub fn stdin_config() -> Result<&'static str> {
+    if let Some(config) = STDIN_CONFIG.get() {
+        return Ok(config);
+    }
+    let mut config = String::new();
+    std::io::stdin()
+        .read_to_string(&mut config)
+        .context("reading config from stdin")
+        .context(ConfigError)?;
+    Ok(STDIN_CONFIG.get_or_init(|| config))
+}
+
+/// Refuse unknown `CDDNS_*` environment variables, such as misspelled
+/// settings, which would otherwise be ignored.
+fn check_env(known: &BTreeSet<String>) -> Result<()> {
+    let unknown = std::env::vars_os()
+        .filter_map(|(var, _)| var.into_string().ok())
+        .filter(|var| var.starts_with("CDDNS_") && !known.contains(var))
+        .collect::<Vec<_>>();
+    if !unknown.is_empty() {
+        return Err(Error::Config(format!(
+            "unknown environment variables: {}",
+            unknown.join(", ")
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Return the path of the config file in use, given on the command line or
+/// the default.
+pub fn config_path() -> PathBuf {
+    LAYERS
+        .get()
+        .and_then(|layers| layers.config.clone())
+        .unwrap_or_else(default_config_path)
+}
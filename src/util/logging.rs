@@ -0,0 +1,132 @@
+use crate::util::progress;
+use clap::ValueEnum;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Logs held back until the run turns out noteworthy, if holding.
+static HELD: Mutex<Option<Vec<u8>>> = Mutex::new(None);
+
+/// Whether logs are written to stderr, leaving stdout to a JSON report.
+static STDERR: AtomicBool = AtomicBool::new(false);
+
+/// The verbosity of logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// Return the log filter directives of this level. Dependencies are
+    /// kept at `info` when debugging cddns.
+    pub fn directives(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "info,cddns=debug,cddns_core=debug",
+            LogLevel::Trace => "info,cddns=trace,cddns_core=trace",
+        }
+    }
+}
+
+/// When to color logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color logs written to a terminal, unless NO_COLOR is set or
+    /// CLICOLOR_FORCE forces color.
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Returns whether logs written to stdout should be colored.
+    pub fn enabled(self) -> bool {
+        let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto if var("NO_COLOR").is_some() => false,
+            ColorChoice::Auto
+                if var("CLICOLOR_FORCE").is_some_and(|v| v != "0") =>
+            {
+                true
+            }
+            ColorChoice::Auto => std::io::stdout().is_terminal(),
+        }
+    }
+}
+
+/// How the error ending a command is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// A log line with the error message.
+    Text,
+    /// A JSON object on stderr with a machine-stable `error.code`, the
+    /// message and the exit code.
+    Json,
+}
+
+/// Hold back logs until they are released or discarded, such as for
+/// `inventory update --changes-only`.
+pub fn hold() {
+    if let Ok(mut held) = HELD.lock() {
+        held.replace(vec![]);
+    }
+}
+
+/// Discard the logs held back so far, such as when nothing changed.
+pub fn discard() {
+    if let Ok(mut held) = HELD.lock() {
+        if let Some(held) = held.as_mut() {
+            held.clear();
+        }
+    }
+}
+
+/// Write the logs held back so far, and stop holding logs.
+pub fn release() {
+    let held = HELD.lock().ok().and_then(|mut held| held.take());
+    if let Some(held) = held {
+        let _ = Output.write_all(&held);
+    }
+}
+
+/// Write logs to stderr instead of stdout, such as when stdout carries a
+/// JSON report for scripts.
+pub fn to_stderr() {
+    STDERR.store(true, Ordering::Relaxed);
+}
+
+/// The log output: stdout, or stderr if logs were moved there, unless logs
+/// are held back.
+pub struct Output;
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Ok(mut held) = HELD.lock() {
+            if let Some(held) = held.as_mut() {
+                held.extend_from_slice(buf);
+                return Ok(buf.len());
+            }
+        }
+        if STDERR.load(Ordering::Relaxed) {
+            io::stderr().write(buf)
+        } else {
+            progress::Stdout.write(buf)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if STDERR.load(Ordering::Relaxed) {
+            io::stderr().flush()
+        } else {
+            progress::Stdout.flush()
+        }
+    }
+}
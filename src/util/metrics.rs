@@ -0,0 +1,157 @@
+use crate::exit::InventoryFailure;
+use anyhow::{Context, Result};
+use std::fmt::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The file metrics are written to, in the textfile collector directory.
+const TEXTFILE_NAME: &str = "cddns.prom";
+
+/// Counters of `watch`, written for the node_exporter textfile collector.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    iterations: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    updated: u64,
+    outdated: usize,
+    last_iteration: Option<SystemTime>,
+    last_success: Option<SystemTime>,
+    summary: Summary,
+}
+
+/// Counts of `watch` iterations since the last condensed summary.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub checks: u32,
+    pub updated: usize,
+    pub failures: u32,
+}
+
+impl Metrics {
+    /// Count a successful iteration, which updated some records.
+    pub fn record_success(&mut self, updated: usize) {
+        let now = SystemTime::now();
+        self.iterations += 1;
+        self.consecutive_failures = 0;
+        self.updated += updated as u64;
+        self.outdated = 0;
+        self.last_iteration = Some(now);
+        self.last_success = Some(now);
+        self.summary.checks += 1;
+        self.summary.updated += updated;
+    }
+
+    /// Count a failed iteration, and return the number of consecutive
+    /// failures.
+    pub fn record_failure(&mut self, err: &anyhow::Error) -> u32 {
+        self.iterations += 1;
+        self.failures += 1;
+        self.consecutive_failures += 1;
+        self.outdated = err
+            .downcast_ref::<InventoryFailure>()
+            .map_or(0, |failure| failure.outdated);
+        self.last_iteration = Some(SystemTime::now());
+        self.summary.checks += 1;
+        self.summary.failures += 1;
+        self.consecutive_failures
+    }
+
+    /// Return the records which remained outdated after the last iteration.
+    pub fn outdated(&self) -> usize {
+        self.outdated
+    }
+
+    /// Return the counts since the last summary.
+    pub fn summary(&self) -> &Summary {
+        &self.summary
+    }
+
+    /// Return the counts since the last summary, starting a new one.
+    pub fn take_summary(&mut self) -> Summary {
+        std::mem::take(&mut self.summary)
+    }
+
+    /// Write the metrics to the textfile collector directory, replacing the
+    /// previous file atomically so partial files are never collected.
+    pub async fn write(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(TEXTFILE_NAME);
+        let tmp = dir.join(format!(".{TEXTFILE_NAME}.{}", std::process::id()));
+        tokio::fs::write(&tmp, self.render()?)
+            .await
+            .with_context(|| format!("unable to write '{}'", tmp.display()))?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .with_context(|| format!("unable to write '{}'", path.display()))
+    }
+
+    /// Render the metrics in the Prometheus text format.
+    fn render(&self) -> Result<String> {
+        let mut out = String::new();
+        writeln!(
+            out,
+            "# HELP cddns_build_info The version of cddns, as a label."
+        )?;
+        writeln!(out, "# TYPE cddns_build_info gauge")?;
+        writeln!(
+            out,
+            "cddns_build_info{{version=\"{}\"}} 1",
+            env!("CARGO_PKG_VERSION")
+        )?;
+        let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+            writeln!(out, "# HELP {name} {help}")?;
+            writeln!(out, "# TYPE {name} {kind}")?;
+            writeln!(out, "{name} {value}")
+        };
+        metric(
+            "cddns_watch_iterations_total",
+            "counter",
+            "Checks of the inventory run by watch.",
+            self.iterations.to_string(),
+        )?;
+        metric(
+            "cddns_watch_failures_total",
+            "counter",
+            "Checks of the inventory which failed.",
+            self.failures.to_string(),
+        )?;
+        metric(
+            "cddns_watch_consecutive_failures",
+            "gauge",
+            "Checks of the inventory which failed since the last success.",
+            self.consecutive_failures.to_string(),
+        )?;
+        metric(
+            "cddns_records_updated_total",
+            "counter",
+            "Records updated by watch.",
+            self.updated.to_string(),
+        )?;
+        metric(
+            "cddns_records_outdated",
+            "gauge",
+            "Records which remained outdated after the last check.",
+            self.outdated.to_string(),
+        )?;
+        metric(
+            "cddns_watch_last_iteration_timestamp_seconds",
+            "gauge",
+            "The time of the last check, or 0 if none ran.",
+            timestamp(self.last_iteration),
+        )?;
+        metric(
+            "cddns_watch_last_success_timestamp_seconds",
+            "gauge",
+            "The time of the last successful check, or 0 if none succeeded.",
+            timestamp(self.last_success),
+        )?;
+        Ok(out)
+    }
+}
+
+/// Return a time in seconds since the Unix epoch.
+fn timestamp(time: Option<SystemTime>) -> String {
+    time.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_secs())
+        .to_string()
+}
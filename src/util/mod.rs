@@ -1,6 +1,15 @@
-//! cddns utility and helper functions.
+//! cddns CLI utility and helper functions.
 
-pub mod encoding;
+pub mod daemon;
 pub mod fs;
-pub mod postprocessors;
+pub mod github;
+pub mod health;
+pub mod layers;
+pub mod logging;
+pub mod metrics;
+pub mod progress;
+pub mod report;
 pub mod scanner;
+pub mod service;
+pub mod signal;
+pub mod table;
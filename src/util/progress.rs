@@ -0,0 +1,57 @@
+use cddns_core::util::progress::{self, Fetch};
+use indicatif::ProgressBar;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The spinner of the fetch in flight, if any.
+static SPINNER: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+/// Show a spinner on stderr while Cloudflare resources are fetched, if
+/// stderr is a terminal.
+pub fn install() {
+    progress::set_reporter(report);
+}
+
+/// Clear the spinner, such as when a fetch fails before completing.
+pub fn clear() {
+    if let Some(spinner) = SPINNER.lock().ok().and_then(|mut s| s.take()) {
+        spinner.finish_and_clear();
+    }
+}
+
+/// Update the spinner with the progress of a fetch.
+fn report(fetch: Fetch) {
+    match fetch {
+        Fetch::Done => clear(),
+        _ => {
+            if let Ok(mut spinner) = SPINNER.lock() {
+                spinner
+                    .get_or_insert_with(|| {
+                        let spinner = ProgressBar::new_spinner();
+                        spinner.enable_steady_tick(Duration::from_millis(100));
+                        spinner
+                    })
+                    .set_message(fetch.to_string());
+            }
+        }
+    }
+}
+
+/// Stdout, hiding the spinner while logs are written so they aren't
+/// garbled.
+pub struct Stdout;
+
+impl Write for Stdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let spinner = SPINNER.lock().ok().and_then(|s| s.clone());
+        match spinner {
+            Some(spinner) => spinner.suspend(|| io::stdout().write(buf)),
+            None => io::stdout().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
@@ -0,0 +1,171 @@
+use crate::exit::{error_code, ExitCode};
+use cddns_core::cloudflare::models::Record;
+use cddns_core::ip::uplink::Resolved;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// The report of the running command, if `--summary-json` was given.
+static REPORT: Mutex<Option<Report>> = Mutex::new(None);
+
+/// An end-of-run report of `check`, `update` and `watch --once`.
+#[derive(Debug)]
+struct Report {
+    command: &'static str,
+    started: Instant,
+    counts: Counts,
+    changes: Vec<Change>,
+    resolved: BTreeMap<String, Resolved>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+struct Counts {
+    valid: usize,
+    outdated: usize,
+    invalid: usize,
+    suspicious: usize,
+    updated: usize,
+    created: usize,
+    pruned: usize,
+}
+
+/// A change made to a record or the inventory.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum Change {
+    Updated {
+        zone_id: String,
+        id: String,
+        name: String,
+        #[serde(rename = "type")]
+        record_type: String,
+        from: String,
+        to: String,
+    },
+    Created {
+        zone_id: String,
+        id: String,
+        name: String,
+        #[serde(rename = "type")]
+        record_type: String,
+        content: String,
+    },
+    Pruned {
+        zone: String,
+        entry: String,
+    },
+}
+
+/// Start reporting the given command, to print the report when it ends.
+pub fn start(command: &'static str) {
+    with(|report| {
+        report.replace(Report {
+            command,
+            started: Instant::now(),
+            counts: Counts::default(),
+            changes: vec![],
+            resolved: BTreeMap::new(),
+        });
+    });
+}
+
+/// Count the records of a check, and the public IPs they were compared to.
+/// Checks of several `watch` buckets add up.
+pub fn checked(
+    valid: usize,
+    outdated: usize,
+    invalid: usize,
+    suspicious: usize,
+    resolved: &BTreeMap<Option<String>, Resolved>,
+) {
+    with(|report| {
+        if let Some(report) = report {
+            report.counts.valid += valid;
+            report.counts.outdated += outdated;
+            report.counts.invalid += invalid;
+            report.counts.suspicious += suspicious;
+            for (uplink, ips) in resolved.iter() {
+                let uplink = uplink.as_deref().unwrap_or("default");
+                report.resolved.insert(uplink.to_string(), *ips);
+            }
+        }
+    });
+}
+
+/// Report an updated record, with its new content.
+pub fn updated(record: &Record, content: &str) {
+    change(Change::Updated {
+        zone_id: record.zone_id.clone(),
+        id: record.id.clone(),
+        name: record.name.clone(),
+        record_type: record.record_type.clone(),
+        from: record.content.clone(),
+        to: content.to_string(),
+    });
+}
+
+/// Report a created record.
+pub fn created(record: &Record) {
+    change(Change::Created {
+        zone_id: record.zone_id.clone(),
+        id: record.id.clone(),
+        name: record.name.clone(),
+        record_type: record.record_type.clone(),
+        content: record.content.clone(),
+    });
+}
+
+/// Report an entry pruned from the inventory.
+pub fn pruned(zone: &str, entry: &str) {
+    change(Change::Pruned {
+        zone: zone.to_string(),
+        entry: entry.to_string(),
+    });
+}
+
+/// Print the report as a single JSON object on stdout, if one was started.
+pub fn finish(result: &anyhow::Result<()>) {
+    let Some(report) = with(Option::take) else {
+        return;
+    };
+    let mut counts = report.counts;
+    for change in report.changes.iter() {
+        match change {
+            Change::Updated { .. } => counts.updated += 1,
+            Change::Created { .. } => counts.created += 1,
+            Change::Pruned { .. } => counts.pruned += 1,
+        }
+    }
+    let (exit_code, error) = match result {
+        Ok(()) => (0, None),
+        Err(err) => (ExitCode::of(err) as i32, Some(error_code(err))),
+    };
+    let summary = serde_json::json!({
+        "command": report.command,
+        "success": result.is_ok(),
+        "exit_code": exit_code,
+        "error": error,
+        "duration_ms": report.started.elapsed().as_millis() as u64,
+        "counts": counts,
+        "changes": report.changes,
+        "resolved": report.resolved,
+    });
+    println!("{summary}");
+}
+
+fn change(change: Change) {
+    with(|report| {
+        if let Some(report) = report {
+            report.changes.push(change);
+        }
+    });
+}
+
+/// Run a closure on the report, which is `None` unless reporting.
+fn with<T: Default>(f: impl FnOnce(&mut Option<Report>) -> T) -> T {
+    REPORT
+        .lock()
+        .map(|mut report| f(&mut report))
+        .unwrap_or_default()
+}
@@ -0,0 +1,124 @@
+use anyhow::Result;
+
+/// The name cddns is registered under with service managers.
+pub const SERVICE_NAME: &str = "cddns";
+
+/// The command to run once the service control manager starts the service.
+#[cfg(windows)]
+type Run = Box<dyn FnOnce() -> Result<()> + Send>;
+
+#[cfg(windows)]
+static RUN: std::sync::Mutex<Option<Run>> = std::sync::Mutex::new(None);
+
+/// The result of the command, returned once the service stops.
+#[cfg(windows)]
+static RESULT: std::sync::Mutex<Option<Result<()>>> =
+    std::sync::Mutex::new(None);
+
+#[cfg(windows)]
+windows_service::define_windows_service!(ffi_service_main, service_main);
+
+/// Hand control to the Windows service control manager, which runs the
+/// command until the service is stopped.
+#[cfg(windows)]
+pub fn dispatch(
+    run: impl FnOnce() -> Result<()> + Send + 'static,
+) -> Result<()> {
+    use anyhow::{anyhow, Context};
+
+    *RUN.lock()
+        .map_err(|_| anyhow!("service state is poisoned"))? =
+        Some(Box::new(run));
+    windows_service::service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+        .context("unable to connect to the service control manager, services must be started by Windows")?;
+    RESULT
+        .lock()
+        .map_err(|_| anyhow!("service state is poisoned"))?
+        .take()
+        .unwrap_or(Ok(()))
+}
+
+/// Hand control to the Windows service control manager, which is
+/// unsupported on this OS.
+#[cfg(not(windows))]
+pub fn dispatch(
+    _run: impl FnOnce() -> Result<()> + Send + 'static,
+) -> Result<()> {
+    anyhow::bail!("Windows services are only supported on Windows")
+}
+
+#[cfg(windows)]
+fn service_main(_arguments: Vec<std::ffi::OsString>) {
+    let result = run_service();
+    if let Ok(mut slot) = RESULT.lock() {
+        *slot = Some(result);
+    }
+}
+
+/// Report the service as running, run the command, and report the service
+/// as stopped with the exit code of the command.
+#[cfg(windows)]
+fn run_service() -> Result<()> {
+    use crate::exit::ExitCode;
+    use crate::util::signal;
+    use anyhow::Context;
+    use std::time::Duration;
+    use windows_service::service::{
+        ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState,
+        ServiceStatus, ServiceType,
+    };
+    use windows_service::service_control_handler::{
+        self, ServiceControlHandlerResult,
+    };
+
+    let run = RUN
+        .lock()
+        .ok()
+        .and_then(|mut run| run.take())
+        .context("the service was already started")?;
+
+    // Stop requests finish an in-flight update, as termination signals do
+    let handler = |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            signal::request_shutdown();
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    };
+    let status_handle =
+        service_control_handler::register(SERVICE_NAME, handler)
+            .context("unable to register the service control handler")?;
+    let status = |state, controls_accepted, exit_code| ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state: state,
+        controls_accepted,
+        exit_code,
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    };
+
+    status_handle
+        .set_service_status(status(
+            ServiceState::Running,
+            ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+            ServiceExitCode::Win32(0),
+        ))
+        .context("unable to report the service as running")?;
+    let result = run();
+    let exit_code = match result {
+        Ok(()) => ServiceExitCode::Win32(0),
+        Err(ref err) => {
+            ServiceExitCode::ServiceSpecific(ExitCode::of(err) as u32)
+        }
+    };
+    status_handle
+        .set_service_status(status(
+            ServiceState::Stopped,
+            ServiceControlAccept::empty(),
+            exit_code,
+        ))
+        .context("unable to report the service as stopped")?;
+    result
+}
@@ -0,0 +1,106 @@
+use anyhow::{Context, Result};
+
+/// Stop requests from the Windows service control manager, held until the
+/// next wait for a termination request.
+#[cfg(windows)]
+static SERVICE_STOP: tokio::sync::Notify = tokio::sync::Notify::const_new();
+
+/// Request a shutdown on behalf of the Windows service control manager.
+#[cfg(windows)]
+pub fn request_shutdown() {
+    SERVICE_STOP.notify_one();
+}
+
+/// A listener for termination requests, installed up front so a signal
+/// received during an update is held until the update completes.
+pub struct Shutdown {
+    #[cfg(unix)]
+    interrupt: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+    #[cfg(windows)]
+    ctrl_c: tokio::signal::windows::CtrlC,
+    #[cfg(windows)]
+    shutdown: tokio::signal::windows::CtrlShutdown,
+}
+
+impl Shutdown {
+    /// Listen for SIGINT and SIGTERM, or Ctrl-C, system shutdown and
+    /// service stop requests on Windows.
+    #[cfg(unix)]
+    pub fn new() -> Result<Self> {
+        use tokio::signal::unix::{signal, SignalKind};
+        Ok(Self {
+            interrupt: signal(SignalKind::interrupt())
+                .context("listening for SIGINT")?,
+            terminate: signal(SignalKind::terminate())
+                .context("listening for SIGTERM")?,
+        })
+    }
+
+    /// Listen for SIGINT and SIGTERM, or Ctrl-C, system shutdown and
+    /// service stop requests on Windows.
+    #[cfg(windows)]
+    pub fn new() -> Result<Self> {
+        use tokio::signal::windows::{ctrl_c, ctrl_shutdown};
+        Ok(Self {
+            ctrl_c: ctrl_c().context("listening for Ctrl-C")?,
+            shutdown: ctrl_shutdown().context("listening for shutdown")?,
+        })
+    }
+
+    /// Wait for a termination request.
+    #[cfg(unix)]
+    pub async fn recv(&mut self) {
+        tokio::select! {
+            _ = self.interrupt.recv() => {}
+            _ = self.terminate.recv() => {}
+        }
+    }
+
+    /// Wait for a termination request.
+    #[cfg(windows)]
+    pub async fn recv(&mut self) {
+        tokio::select! {
+            _ = self.ctrl_c.recv() => {}
+            _ = self.shutdown.recv() => {}
+            _ = SERVICE_STOP.notified() => {}
+        }
+    }
+}
+
+/// A listener for requests to reload the configuration, via SIGHUP.
+pub struct Hangup {
+    #[cfg(unix)]
+    hangup: tokio::signal::unix::Signal,
+}
+
+impl Hangup {
+    /// Listen for SIGHUP.
+    #[cfg(unix)]
+    pub fn new() -> Result<Self> {
+        use tokio::signal::unix::{signal, SignalKind};
+        Ok(Self {
+            hangup: signal(SignalKind::hangup())
+                .context("listening for SIGHUP")?,
+        })
+    }
+
+    /// Listen for reload requests, which are unsupported on Windows.
+    #[cfg(windows)]
+    pub fn new() -> Result<Self> {
+        Ok(Self {})
+    }
+
+    /// Wait for a reload request.
+    #[cfg(unix)]
+    pub async fn recv(&mut self) {
+        self.hangup.recv().await;
+    }
+
+    /// Wait for a reload request, which never arrives on Windows.
+    #[cfg(windows)]
+    pub async fn recv(&mut self) {
+        std::future::pending().await
+    }
+}
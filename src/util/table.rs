@@ -0,0 +1,42 @@
+/// Text printed in aligned columns, under a header.
+pub struct Table {
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Return an empty table with a header.
+    pub fn new(header: &[&str]) -> Self {
+        Self {
+            rows: vec![header.iter().map(|h| h.to_string()).collect()],
+        }
+    }
+
+    /// Append a row, with a cell for each column of the header.
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    /// Print the table, indenting every line.
+    pub fn print(&self, indent: &str) {
+        let columns = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths = (0..columns)
+            .map(|c| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(c))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<usize>>();
+        for row in self.rows.iter() {
+            let line = row
+                .iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<String>>()
+                .join("  ");
+            println!("{indent}{}", line.trim_end());
+        }
+    }
+}
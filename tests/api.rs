@@ -0,0 +1,3067 @@
+//! End to end tests of the binary against a mock Cloudflare API.
+//!
+//! The binary keeps its config, cache and state in the directories of the
+//! `directories` crate, which are only redirected to an isolated home by the
+//! `XDG_*` variables on Linux. Elsewhere tests would share the user's real
+//! directories, so they only run on Linux.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{failure, record, stdout, zone, Harness, TOKEN};
+use serde_json::json;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use wiremock::matchers::{body_json, header, method, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+#[tokio::test]
+async fn list_follows_pagination() {
+    let harness = Harness::new().await;
+    let (z1, z2) = (zone("z1", "example.com"), zone("z2", "example.dev"));
+    harness.zones(vec![z1.clone()], 1, 2).await;
+    harness.zones(vec![z2.clone()], 2, 2).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            2,
+        )
+        .await;
+    harness
+        .records(
+            "z1",
+            vec![record("r2", &z1, "b.example.com", "192.0.2.2")],
+            2,
+            2,
+        )
+        .await;
+    harness
+        .records(
+            "z2",
+            vec![record("r3", &z2, "c.example.dev", "192.0.2.3")],
+            1,
+            1,
+        )
+        .await;
+
+    let output = harness.run(&["list"]).await;
+    let stdout = stdout(&output);
+
+    assert!(output.status.success(), "{stdout}");
+    for name in ["a.example.com", "b.example.com", "c.example.dev"] {
+        assert!(stdout.contains(name), "missing {name}: {stdout}");
+    }
+    assert!(
+        stdout.contains("total zones: 2, total records: 3"),
+        "{stdout}"
+    );
+    // Progress spinners are only drawn to terminals
+    assert!(
+        output.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[tokio::test]
+async fn list_sends_token() {
+    let harness = Harness::new().await;
+    Mock::given(method("GET"))
+        .and(path("/zones"))
+        .and(header("authorization", format!("Bearer {TOKEN}").as_str()))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(common::envelope(vec![], 1)),
+        )
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+
+    let output = harness.run(&["list", "zones"]).await;
+
+    assert!(output.status.success(), "{}", stdout(&output));
+}
+
+#[tokio::test]
+async fn list_surfaces_cloudflare_errors() {
+    let harness = Harness::new().await;
+    Mock::given(method("GET"))
+        .and(path("/zones"))
+        .respond_with(
+            ResponseTemplate::new(403)
+                .insert_header("cf-ray", "7d1a2b3c4d5e6f70-YYZ")
+                .set_body_json(failure(9109, "Invalid access token")),
+        )
+        .mount(&harness.server)
+        .await;
+
+    let output = harness.run(&["-v", "list"]).await;
+    let stdout = stdout(&output);
+
+    assert_eq!(output.status.code(), Some(4), "{stdout}");
+    assert!(stdout.contains("9109: Invalid access token"), "{stdout}");
+    assert!(stdout.contains("7d1a2b3c4d5e6f70-YYZ"), "{stdout}");
+}
+
+#[tokio::test]
+async fn list_retries_rate_limited_requests() {
+    let harness = Harness::new().await;
+    Mock::given(method("GET"))
+        .and(path("/zones"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .insert_header("retry-after", "0")
+                .set_body_json(failure(10000, "Rate limited")),
+        )
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    harness.zones(vec![zone("z1", "example.com")], 1, 1).await;
+
+    let output = harness.run(&["list", "zones"]).await;
+    let stdout = stdout(&output);
+
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("example.com"), "{stdout}");
+}
+
+#[tokio::test]
+async fn list_gives_up_on_long_retry_after() {
+    let harness = Harness::new().await;
+    Mock::given(method("GET"))
+        .and(path("/zones"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .insert_header("retry-after", "3600")
+                .set_body_json(failure(10000, "Rate limited")),
+        )
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+
+    let output = harness.run(&["list", "zones"]).await;
+
+    assert_eq!(output.status.code(), Some(5), "{}", stdout(&output));
+}
+
+#[tokio::test]
+async fn list_gives_up_after_server_errors() {
+    let harness = Harness::new().await;
+    Mock::given(method("GET"))
+        .and(path("/zones"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(3)
+        .mount(&harness.server)
+        .await;
+
+    let output = harness.run(&["list", "zones"]).await;
+
+    assert_eq!(output.status.code(), Some(5), "{}", stdout(&output));
+}
+
+#[tokio::test]
+async fn check_reports_invalid_records() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness.records("z1", vec![], 1, 1).await;
+    let inventory = harness.inventory("z1:\n  - missing.example.com\n");
+
+    let output = harness
+        .run(&["inventory", "-p", inventory.to_str().unwrap(), "check"])
+        .await;
+    let stdout = stdout(&output);
+
+    assert_eq!(output.status.code(), Some(7), "{stdout}");
+    assert!(
+        stdout.contains("inventory contains 1 invalid records"),
+        "{stdout}"
+    );
+}
+
+#[tokio::test]
+async fn update_skips_invalid_records() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness.records("z1", vec![], 1, 1).await;
+    Mock::given(method("PATCH"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("z1:\n  - missing.example.com\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "update",
+        ])
+        .await;
+    let stdout = stdout(&output);
+
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("inventory is up to date"), "{stdout}");
+}
+
+#[tokio::test]
+async fn prune_removes_invalid_records() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness
+        .inventory("z1:\n  - missing.example.com\nz2:\n  - gone.example.dev\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-prune",
+            "true",
+            "prune",
+        ])
+        .await;
+    let stdout = stdout(&output);
+
+    assert!(output.status.success(), "{stdout}");
+    let contents = std::fs::read_to_string(&inventory).unwrap();
+    assert!(!contents.contains("missing.example.com"), "{contents}");
+    assert!(!contents.contains("gone.example.dev"), "{contents}");
+}
+
+#[tokio::test]
+async fn update_prunes_invalid_records_in_one_pass() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "a.example.com", "203.0.113.7"),
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    let inventory =
+        harness.inventory("z1:\n  - a.example.com\n  - gone.example.com\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "--force-prune",
+            "true",
+            "update",
+            "--ipv4-address",
+            "203.0.113.7",
+            "--prune",
+        ])
+        .await;
+    let logs = stdout(&output);
+
+    assert!(output.status.success(), "{logs}");
+    let saved = std::fs::read_to_string(&inventory).unwrap();
+    assert!(saved.contains("a.example.com"), "{saved}");
+    assert!(!saved.contains("gone.example.com"), "{saved}");
+    // Annotating the saved inventory reuses the cached zones
+    let requests = harness.server.received_requests().await.unwrap();
+    let zone_fetches = requests.iter().filter(|r| r.url.path() == "/zones");
+    assert_eq!(zone_fetches.count(), 1);
+}
+
+#[tokio::test]
+async fn check_fix_updates_outdated_records() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .and(body_json(json!({ "content": "203.0.113.7" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "a.example.com", "203.0.113.7"),
+        })))
+        .expect(2)
+        .mount(&harness.server)
+        .await;
+    let inventory =
+        harness.inventory("z1:\n  - a.example.com\n  - gone.example.com\n");
+    let path = inventory.to_str().unwrap();
+
+    // Invalid records remain without `--prune`
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            path,
+            "--force-update",
+            "true",
+            "check",
+            "--expect-ip",
+            "203.0.113.7",
+            "--fix",
+        ])
+        .await;
+    let logs = stdout(&output);
+    assert_eq!(output.status.code(), Some(7), "{logs}");
+    assert!(logs.contains("use `--prune`"), "{logs}");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            path,
+            "--force-update",
+            "true",
+            "--force-prune",
+            "true",
+            "check",
+            "--expect-ip",
+            "203.0.113.7",
+            "--fix",
+            "--prune",
+        ])
+        .await;
+    let logs = stdout(&output);
+    assert!(output.status.success(), "{logs}");
+    let saved = std::fs::read_to_string(&inventory).unwrap();
+    assert!(!saved.contains("gone.example.com"), "{saved}");
+}
+
+#[tokio::test]
+async fn refresh_names_rewrites_stale_aliases() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "renamed.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    let inventory =
+        harness.inventory("z1: # 'example.org'\n  - r1 # 'old.example.com'\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "refresh-names",
+        ])
+        .await;
+    let logs = stdout(&output);
+
+    assert!(output.status.success(), "{logs}");
+    let saved = std::fs::read_to_string(&inventory).unwrap();
+    assert!(saved.contains("z1: # 'example.com'"), "{saved}");
+    assert!(saved.contains("r1 # 'renamed.example.com'"), "{saved}");
+    assert!(!saved.contains("old.example.com"), "{saved}");
+}
+
+#[tokio::test]
+async fn zones_are_cached_until_refreshed() {
+    let harness = Harness::new().await;
+    harness.zones(vec![zone("z1", "example.com")], 1, 1).await;
+    let zone_fetches = || async {
+        let requests = harness.server.received_requests().await.unwrap();
+        requests.iter().filter(|r| r.url.path() == "/zones").count()
+    };
+
+    assert!(harness.run(&["list", "zones"]).await.status.success());
+    assert!(harness.run(&["list", "zones"]).await.status.success());
+    assert_eq!(zone_fetches().await, 1);
+
+    let output = harness.run(&["--refresh-zones", "list", "zones"]).await;
+    assert!(output.status.success());
+    assert_eq!(zone_fetches().await, 2);
+
+    let output = harness.run(&["--zones-ttl", "0", "list", "zones"]).await;
+    assert!(output.status.success());
+    assert_eq!(zone_fetches().await, 3);
+}
+
+#[tokio::test]
+async fn unicode_names_match_punycode_records() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.de");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let punycode = "xn--mnchen-3ya.example.de";
+    harness
+        .records("z1", vec![record("r1", &z1, punycode, "192.0.2.1")], 1, 1)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, punycode, "203.0.113.7"),
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("example.de:\n  - münchen.example.de\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "update",
+            "--ipv4-address",
+            "203.0.113.7",
+        ])
+        .await;
+    let logs = stdout(&output);
+    assert!(output.status.success(), "{logs}");
+
+    let output = harness.run(&["list", "records"]).await;
+    let listed = stdout(&output);
+    assert!(output.status.success(), "{listed}");
+    assert!(listed.contains("münchen.example.de  A"), "{listed}");
+}
+
+#[tokio::test]
+async fn wildcard_entries_select_wildcard_records() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "*.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    // Wildcard entries need no quoting
+    let inventory = harness
+        .inventory("z1:\n  - *.example.com # all\n  - vpn.example.com\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "check",
+            "--expect-ip",
+            "192.0.2.1",
+        ])
+        .await;
+    let logs = stdout(&output);
+
+    assert_eq!(output.status.code(), Some(7), "{logs}");
+    assert!(logs.contains("valid=1 outdated=0 invalid=1"), "{logs}");
+    assert!(logs.contains("only covered by a wildcard record"), "{logs}");
+}
+
+#[tokio::test]
+async fn unsupported_record_types_follow_policy() {
+    let harness = Harness::new().await;
+    let fixture = harness.path("mock.yml");
+    std::fs::write(
+        &fixture,
+        r##"
+zones:
+  - { id: z1, name: example.com, permissions: ["#zone:edit"], status: active }
+records:
+  - { id: r1, zone_id: z1, zone_name: example.com, name: a.example.com,
+      type: A, content: 192.0.2.1, locked: false, tags: [ddns] }
+  - { id: r2, zone_id: z1, zone_name: example.com, name: www.example.com,
+      type: CNAME, content: a.example.com, locked: false, tags: [ddns] }
+"##,
+    )
+    .unwrap();
+    let inventory = harness.inventory("z1:\n  - tag:ddns\n");
+    let (fixture, inventory) =
+        (fixture.to_str().unwrap(), inventory.to_str().unwrap());
+    let check = |policy| {
+        [
+            "--mock",
+            fixture,
+            "inventory",
+            "-p",
+            inventory,
+            "--unsupported-types",
+            policy,
+            "check",
+            "--expect-ip",
+            "192.0.2.1",
+        ]
+    };
+
+    let output = harness.run(&check("skip")).await;
+    let logs = stdout(&output);
+    assert!(output.status.success(), "{logs}");
+    assert!(logs.contains("skipping unsupported CNAME record"), "{logs}");
+    assert!(logs.contains("valid=1 outdated=0"), "{logs}");
+
+    let output = harness.run(&check("static")).await;
+    let logs = stdout(&output);
+    assert!(output.status.success(), "{logs}");
+    assert!(logs.contains("valid=2 outdated=0"), "{logs}");
+
+    let output = harness.run(&check("error")).await;
+    let logs = stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{logs}");
+    assert!(logs.contains("unsupported CNAME record"), "{logs}");
+}
+
+#[tokio::test]
+async fn config_is_read_from_stdin() {
+    let harness = Harness::new().await;
+    let mut show = harness
+        .command(&["--config", "-", "config", "show"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = show.stdin.take().unwrap();
+    stdin.write_all(b"[api]\nzones_ttl = 42\n").await.unwrap();
+    drop(stdin);
+
+    let output = show.wait_with_output().await.unwrap();
+    let logs = stdout(&output);
+
+    assert!(output.status.success(), "{logs}");
+    assert!(logs.contains("Zones TTL: 42"), "{logs}");
+}
+
+#[tokio::test]
+async fn invalid_config_exits_with_config_code() {
+    let harness = Harness::new().await;
+    let config = harness.path("config.toml");
+    std::fs::write(&config, "[verify\n").unwrap();
+
+    let output = harness
+        .run(&["--config", config.to_str().unwrap(), "list"])
+        .await;
+
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+}
+
+#[tokio::test]
+async fn json_errors_include_a_stable_code() {
+    let harness = Harness::new().await;
+    let inventory = harness.path("missing.yml");
+
+    let output = harness
+        .run(&[
+            "--error-format",
+            "json",
+            "inventory",
+            "--path",
+            inventory.to_str().unwrap(),
+            "show",
+        ])
+        .await;
+
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+    let error: serde_json::Value =
+        serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["error"]["code"], "INVENTORY_NOT_FOUND", "{error}");
+    assert_eq!(error["error"]["exit_code"], 3, "{error}");
+    assert!(
+        error["error"]["message"]
+            .as_str()
+            .is_some_and(|message| message.contains("missing.yml")),
+        "{error}"
+    );
+}
+
+#[tokio::test]
+async fn exit_codes_separate_local_files_from_responses() {
+    let harness = Harness::new().await;
+    let inventory = harness.path("missing.yml");
+
+    let output = harness
+        .run(&["inventory", "-p", inventory.to_str().unwrap(), "check"])
+        .await;
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+
+    Mock::given(method("GET"))
+        .and(path("/zones"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<html>"))
+        .mount(&harness.server)
+        .await;
+
+    let output = harness.run(&["list", "zones"]).await;
+    assert_eq!(output.status.code(), Some(1), "{}", stdout(&output));
+}
+
+#[tokio::test]
+async fn summary_json_reports_the_run() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "a.example.com", "203.0.113.7"),
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    let inventory =
+        harness.inventory("z1:\n  - a.example.com\n  - gone.example.com\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "--force-prune",
+            "true",
+            "update",
+            "--ipv4-address",
+            "203.0.113.7",
+            "--prune",
+            "--summary-json",
+        ])
+        .await;
+    let logs = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{logs}");
+    assert!(logs.contains("a.example.com"), "{logs}");
+
+    // Logs go to stderr, leaving stdout to the report
+    let summary: serde_json::Value =
+        serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(summary["command"], "update", "{summary}");
+    assert_eq!(summary["success"], true, "{summary}");
+    assert_eq!(summary["counts"]["outdated"], 1, "{summary}");
+    assert_eq!(summary["counts"]["invalid"], 1, "{summary}");
+    assert_eq!(summary["counts"]["updated"], 1, "{summary}");
+    assert_eq!(summary["counts"]["pruned"], 1, "{summary}");
+    assert_eq!(summary["changes"][0]["action"], "pruned", "{summary}");
+    assert_eq!(summary["changes"][1]["from"], "192.0.2.1", "{summary}");
+    assert_eq!(summary["changes"][1]["to"], "203.0.113.7", "{summary}");
+    // Fixed addresses are not resolved
+    assert_eq!(summary["resolved"], json!({}), "{summary}");
+    assert!(summary["duration_ms"].is_u64(), "{summary}");
+}
+
+#[tokio::test]
+async fn check_prints_github_annotations() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    let inventory =
+        harness.inventory("z1:\n  - a.example.com\n  - gone.example.com\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "check",
+            "--expect-ip",
+            "203.0.113.7",
+            "--github-annotations",
+        ])
+        .await;
+    let logs = stdout(&output);
+
+    assert_eq!(output.status.code(), Some(7), "{logs}");
+    assert!(
+        logs.contains(
+            "::warning title=Outdated DNS record::A record 'a.example.com' \
+            in zone 'example.com' is outdated: 192.0.2.1\n"
+        ),
+        "{logs}"
+    );
+    assert!(
+        logs.contains(
+            "::error title=Invalid inventory entry::'gone.example.com' in \
+            zone 'z1' does not match any Cloudflare record\n"
+        ),
+        "{logs}"
+    );
+}
+
+#[tokio::test]
+async fn changes_only_is_silent_unless_records_change() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "a.example.com", "203.0.113.7"),
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+    let update = |ip| {
+        [
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "update",
+            "--ipv4-address",
+            ip,
+            "--changes-only",
+        ]
+    };
+
+    let output = harness.run(&update("192.0.2.1")).await;
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert_eq!(stdout(&output), "");
+
+    let output = harness.run(&update("203.0.113.7")).await;
+    let logs = stdout(&output);
+    assert!(output.status.success(), "{logs}");
+    assert!(logs.contains("updated record"), "{logs}");
+}
+
+#[tokio::test]
+async fn alerts_are_raised_once_until_records_recover() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .respond_with(
+            ResponseTemplate::new(400).set_body_json(failure(
+                9005,
+                "Content for A record is invalid",
+            )),
+        )
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+    let update = [
+        "inventory",
+        "-p",
+        inventory.to_str().unwrap(),
+        "--force-update",
+        "true",
+        "update",
+        "--ipv4-address",
+        "203.0.113.7",
+    ];
+
+    let output = harness.run(&update).await;
+    let logs = stdout(&output);
+    assert_eq!(output.status.code(), Some(6), "{logs}");
+    assert!(logs.contains("record became outdated"), "{logs}");
+
+    let output = harness.run(&update).await;
+    let logs = stdout(&output);
+    assert_eq!(output.status.code(), Some(6), "{logs}");
+    assert!(!logs.contains("record became outdated"), "{logs}");
+
+    harness.server.reset().await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "a.example.com", "203.0.113.7"),
+        })))
+        .mount(&harness.server)
+        .await;
+
+    let output = harness.run(&update).await;
+    let logs = stdout(&output);
+    assert!(output.status.success(), "{logs}");
+    assert!(logs.contains("record recovered, it was outdated"), "{logs}");
+}
+
+#[tokio::test]
+async fn plugins_resolve_ips_and_receive_alerts() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .and(body_json(json!({ "content": "198.51.100.7" })))
+        .respond_with(
+            ResponseTemplate::new(400).set_body_json(failure(
+                9005,
+                "Content for A record is invalid",
+            )),
+        )
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+
+    // An IP source discovered in the plugins directory
+    let plugins = harness.path("cddns/plugins/ip");
+    std::fs::create_dir_all(&plugins).unwrap();
+    let request = harness.path("request.json");
+    let ip_plugin = plugins.join("my-ip");
+    std::fs::write(
+        &ip_plugin,
+        format!(
+            "#!/bin/sh\ncat > '{}'\necho '{{\"ip\":\"198.51.100.7\"}}'\n",
+            request.display()
+        ),
+    )
+    .unwrap();
+    // A configured notifier
+    let notifications = harness.path("notifications.jsonl");
+    let notifier = harness.path("notify");
+    std::fs::write(
+        &notifier,
+        format!("#!/bin/sh\ncat >> '{}'\n", notifications.display()),
+    )
+    .unwrap();
+    for plugin in [&ip_plugin, &notifier] {
+        std::fs::set_permissions(
+            plugin,
+            std::fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+    }
+
+    let output = harness
+        .command(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "update",
+            "--ipv4-only",
+        ])
+        .env("CDDNS_INVENTORY_NOTIFIERS", &notifier)
+        .output()
+        .await
+        .unwrap();
+    let logs = stdout(&output);
+
+    assert_eq!(output.status.code(), Some(6), "{logs}");
+    assert!(logs.contains("source=plugin ip=198.51.100.7"), "{logs}");
+    let request = std::fs::read_to_string(&request).unwrap();
+    assert_eq!(request, r#"{"kind":"ip","version":4}"#);
+    let notifications = std::fs::read_to_string(&notifications).unwrap();
+    assert!(
+        notifications.starts_with(
+            r#"{"kind":"notify","event":"outdated","name":"a.example.com","#
+        ),
+        "{notifications}"
+    );
+}
+
+#[tokio::test]
+async fn rollback_restores_previous_content() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    let entry = |from: &str, to: &str| {
+        json!({
+            "time": "2024-01-01T00:00:00Z",
+            "zone_id": "z1",
+            "record_id": "r1",
+            "name": "a.example.com",
+            "from": from,
+            "to": to,
+        })
+    };
+    let audit = harness.audit(&[
+        entry("192.0.2.1", "192.0.2.2"),
+        entry("192.0.2.2", "192.0.2.3"),
+    ]);
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .and(body_json(json!({ "content": "192.0.2.2" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "a.example.com", "192.0.2.2"),
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+
+    let output = harness
+        .run(&["rollback", "--record", "a.example.com"])
+        .await;
+    let stdout = stdout(&output);
+
+    assert!(output.status.success(), "{stdout}");
+    let contents = std::fs::read_to_string(&audit).unwrap();
+    assert_eq!(contents.lines().count(), 3, "{contents}");
+    assert!(contents
+        .lines()
+        .last()
+        .unwrap()
+        .contains(r#""to":"192.0.2.2""#));
+}
+
+#[tokio::test]
+async fn check_reports_outdated_records() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.ip("192.0.2.9\n").await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+
+    let output = harness
+        .run(&["inventory", "-p", inventory.to_str().unwrap(), "check"])
+        .await;
+    let stdout = stdout(&output);
+
+    assert_eq!(output.status.code(), Some(6), "{stdout}");
+    assert!(
+        stdout.contains("inventory contains 1 outdated records"),
+        "{stdout}"
+    );
+}
+
+#[tokio::test]
+async fn update_patches_outdated_records() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.ip("192.0.2.9").await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .and(body_json(json!({ "content": "192.0.2.9" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "a.example.com", "192.0.2.9"),
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "update",
+        ])
+        .await;
+    let stdout = stdout(&output);
+
+    assert!(output.status.success(), "{stdout}");
+    let audit =
+        std::fs::read_to_string(harness.path("cddns/audit.jsonl")).unwrap();
+    assert!(audit.contains(r#""from":"192.0.2.1","to":"192.0.2.9""#));
+}
+
+#[tokio::test]
+async fn update_defers_records_within_cooldown() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.ip("192.0.2.9").await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    // The listed record stays outdated after it is updated
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "a.example.com", "192.0.2.9"),
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+    let args = [
+        "inventory",
+        "-p",
+        inventory.to_str().unwrap(),
+        "--force-update",
+        "true",
+        "--update-cooldown",
+        "3600000",
+        "update",
+    ];
+
+    let output = harness.run(&args).await;
+    assert!(output.status.success(), "{}", stdout(&output));
+
+    // A deferred update is not a failure
+    let output = harness.run(&args).await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{stdout}");
+    assert!(
+        stdout.contains("deferring update within cooldown"),
+        "{stdout}"
+    );
+    let state =
+        std::fs::read_to_string(harness.path("cddns/state.json")).unwrap();
+    assert!(!state.contains("outdated"), "{state}");
+}
+
+#[tokio::test]
+async fn update_does_not_retry_rejected_patches() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.ip("192.0.2.9").await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .respond_with(
+            ResponseTemplate::new(400).set_body_json(failure(
+                9005,
+                "Content for A record is invalid",
+            )),
+        )
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "update",
+        ])
+        .await;
+    let stdout = stdout(&output);
+
+    assert!(!output.status.success(), "{stdout}");
+    assert!(!stdout.contains("retrying record update"), "{stdout}");
+}
+
+#[tokio::test]
+async fn update_retries_patches_only_in_the_request_layer() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.ip("192.0.2.9").await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    // Attempted `api.max_attempts` times, by default 3
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(3)
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "update",
+        ])
+        .await;
+
+    assert!(!output.status.success(), "{}", stdout(&output));
+}
+
+#[tokio::test]
+async fn check_requires_ip_consensus() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.ip("192.0.2.9").await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.9")],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+    let check = |command: &'static str| {
+        [
+            "--ip-consensus",
+            "2",
+            "--ip-command",
+            command,
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "check",
+        ]
+    };
+
+    let agreed = harness.run(&check("echo 192.0.2.9")).await;
+    let agreed_stdout = stdout(&agreed);
+    assert!(agreed.status.success(), "{agreed_stdout}");
+
+    let disagreed = harness.run(&check("echo 192.0.2.66")).await;
+    let disagreed_stdout = stdout(&disagreed);
+    assert!(!disagreed.status.success(), "{disagreed_stdout}");
+    assert!(
+        disagreed_stdout.contains("IP sources disagree"),
+        "{disagreed_stdout}"
+    );
+}
+
+#[tokio::test]
+async fn update_skips_unconfirmed_ip() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    // Resolved by check and update, then changed before confirmation
+    Mock::given(method("GET"))
+        .and(path("/ip"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("192.0.2.9"))
+        .up_to_n_times(2)
+        .mount(&harness.server)
+        .await;
+    harness.ip("192.0.2.10").await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+
+    let output = harness
+        .run(&[
+            "--ip-confirm-delay",
+            "1",
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "update",
+        ])
+        .await;
+    let stdout = stdout(&output);
+
+    assert!(
+        stdout.contains("public IPs are unconfirmed, skipping updates"),
+        "{stdout}"
+    );
+}
+
+#[tokio::test]
+async fn check_refuses_cgnat_ip() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.ip("100.64.1.2").await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "100.64.1.2")],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+    let inventory = inventory.to_str().unwrap();
+
+    let refused = harness.run(&["inventory", "-p", inventory, "check"]).await;
+    let refused_stdout = stdout(&refused);
+    assert!(!refused.status.success(), "{refused_stdout}");
+    assert!(
+        refused_stdout.contains("is a private or CGNAT address"),
+        "{refused_stdout}"
+    );
+
+    let allowed = harness
+        .run(&[
+            "--ip-allow-private",
+            "true",
+            "inventory",
+            "-p",
+            inventory,
+            "check",
+        ])
+        .await;
+    let allowed_stdout = stdout(&allowed);
+    assert!(allowed.status.success(), "{allowed_stdout}");
+}
+
+#[tokio::test]
+async fn check_skips_disabled_address_family() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.ip("192.0.2.9").await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let mut aaaa = record("r2", &z1, "a.example.com", "2001:db8::1");
+    aaaa["type"] = json!("AAAA");
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.9"), aaaa],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - r1\n  - r2\n");
+    let inventory = inventory.to_str().unwrap();
+
+    // No IPv6 source is configured
+    let failed = harness.run(&["inventory", "-p", inventory, "check"]).await;
+    assert!(!failed.status.success(), "{}", stdout(&failed));
+
+    let output = harness
+        .run(&["inventory", "-p", inventory, "--ipv6", "false", "check"])
+        .await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{stdout}");
+}
+
+#[tokio::test]
+async fn check_pairs_records_by_name() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let mut aaaa = record("r2", &z1, "a.example.com", "2001:db8::1");
+    aaaa["type"] = json!("AAAA");
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.9"), aaaa],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+    let check = |pair: &'static str| {
+        [
+            "--ip-command",
+            "printf '192.0.2.9\\n2001:db8::9\\n'",
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--pair-records",
+            pair,
+            "check",
+        ]
+    };
+
+    let single = harness.run(&check("false")).await;
+    assert!(single.status.success(), "{}", stdout(&single));
+
+    let paired = harness.run(&check("true")).await;
+    let paired_stdout = stdout(&paired);
+    assert_eq!(paired.status.code(), Some(6), "{paired_stdout}");
+    assert!(
+        paired_stdout.contains("inventory contains 1 outdated records"),
+        "{paired_stdout}"
+    );
+}
+
+#[tokio::test]
+async fn check_resolves_records_per_uplink() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.ip("192.0.2.9").await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![
+                record("r1", &z1, "a.example.com", "192.0.2.9"),
+                record("r2", &z1, "b.example.com", "198.51.100.7"),
+            ],
+            1,
+            1,
+        )
+        .await;
+    let config = harness.path("cddns/config.toml");
+    std::fs::create_dir_all(config.parent().unwrap()).unwrap();
+    std::fs::write(
+        &config,
+        "[ip.uplinks.wan2]\n\
+        records = [\"b.example.com\"]\n\
+        command = \"echo 198.51.100.7\"\n",
+    )
+    .unwrap();
+    let inventory =
+        harness.inventory("z1:\n  - a.example.com\n  - b.example.com\n");
+
+    let output = harness
+        .run(&["inventory", "-p", inventory.to_str().unwrap(), "check"])
+        .await;
+    let stdout = stdout(&output);
+
+    assert!(output.status.success(), "{stdout}");
+}
+
+#[tokio::test]
+async fn update_publishes_manual_ip() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .and(body_json(json!({ "content": "203.0.113.7" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "a.example.com", "203.0.113.7"),
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+
+    // No public IP is served, so it must not be resolved
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "update",
+            "--ipv4-address",
+            "203.0.113.7",
+        ])
+        .await;
+    let stdout = stdout(&output);
+
+    assert!(output.status.success(), "{stdout}");
+}
+
+#[tokio::test]
+async fn update_sets_comment_from_template() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .and(body_json(json!({
+            "content": "203.0.113.7",
+            "comment": "cddns: a.example.com => 203.0.113.7",
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "a.example.com", "203.0.113.7"),
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "--comment-template",
+            "cddns: {name} => {content}",
+            "update",
+            "--ipv4-address",
+            "203.0.113.7",
+        ])
+        .await;
+    let logs = stdout(&output);
+
+    assert!(output.status.success(), "{logs}");
+}
+
+#[tokio::test]
+async fn update_only_filtered_records() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![
+                record("r1", &z1, "a.example.com", "192.0.2.1"),
+                record("r2", &z1, "b.example.com", "192.0.2.1"),
+            ],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "a.example.com", "203.0.113.7"),
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r2"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&harness.server)
+        .await;
+    // Entries outside the filter are neither updated nor reported invalid
+    let inventory = harness.inventory(
+        "z1:\n  - a.example.com\n  - b.example.com\n  - gone.example.com\n",
+    );
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "update",
+            "--ipv4-address",
+            "203.0.113.7",
+            "--record",
+            "a\\..*",
+        ])
+        .await;
+    let logs = stdout(&output);
+
+    assert!(output.status.success(), "{logs}");
+}
+
+#[tokio::test]
+async fn update_creates_missing_records() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness.records("z1", vec![], 1, 1).await;
+    Mock::given(method("POST"))
+        .and(path("/zones/z1/dns_records"))
+        .and(body_json(json!({
+            "name": "new.example.com",
+            "type": "A",
+            "content": "203.0.113.7",
+            "ttl": 1,
+            "proxied": false,
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "new.example.com", "203.0.113.7"),
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory(
+        "z1:\n  - new.example.com\n  - tag:missing\n  - other.org\n",
+    );
+    let update = |create_missing: bool| {
+        let mut args = vec![
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--force-update",
+            "true",
+            "update",
+            "--ipv4-address",
+            "203.0.113.7",
+        ];
+        if create_missing {
+            args.push("--create-missing");
+        }
+        args
+    };
+
+    // Missing records are only created when enabled
+    let output = harness.run(&update(false)).await;
+    assert!(output.status.success(), "{}", stdout(&output));
+
+    let output = harness.run(&update(true)).await;
+    let logs = stdout(&output);
+    assert!(output.status.success(), "{logs}");
+    assert!(logs.contains("created A record"), "{logs}");
+    assert!(logs.contains("only records given by name"), "{logs}");
+    assert!(logs.contains("not a name in the zone"), "{logs}");
+}
+
+#[tokio::test]
+async fn record_create_is_not_resent_after_server_errors() {
+    let harness = Harness::new().await;
+    harness.zones(vec![zone("z1", "example.com")], 1, 1).await;
+    Mock::given(method("POST"))
+        .and(path("/zones/z1/dns_records"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+
+    let output = harness
+        .run(&["record", "create", "vpn.example.com", "-c", "192.0.2.1"])
+        .await;
+
+    assert_eq!(output.status.code(), Some(5), "{}", stdout(&output));
+}
+
+#[tokio::test]
+async fn record_create_adds_to_inventory() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let mut created = record("r1", &z1, "vpn.example.com", "203.0.113.7");
+    created["proxied"] = json!(true);
+    harness.records("z1", vec![created.clone()], 1, 1).await;
+    Mock::given(method("POST"))
+        .and(path("/zones/z1/dns_records"))
+        .and(body_json(json!({
+            "name": "vpn.example.com",
+            "type": "A",
+            "content": "203.0.113.7",
+            "ttl": 1,
+            "proxied": true,
+            "comment": "home",
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": created,
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.path("created.yml");
+
+    let output = harness
+        .command(&[
+            "record",
+            "create",
+            "vpn",
+            "-z",
+            "example.com",
+            "--content",
+            "203.0.113.7",
+            "--proxied",
+            "--comment",
+            "home",
+            "--inventory",
+        ])
+        .env("CDDNS_INVENTORY_PATH", &inventory)
+        .output()
+        .await
+        .unwrap();
+    let logs = stdout(&output);
+    assert!(output.status.success(), "{logs}");
+    assert!(
+        logs.contains("vpn.example.com: r1 => 203.0.113.7"),
+        "{logs}"
+    );
+    let saved = std::fs::read_to_string(&inventory).unwrap();
+    assert!(saved.contains("z1:"), "{saved}");
+    assert!(saved.contains("- r1"), "{saved}");
+
+    // Types other than A and AAAA need content
+    let output = harness
+        .run(&["record", "create", "@", "-z", "z1", "-t", "MX"])
+        .await;
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+}
+
+#[tokio::test]
+async fn record_create_expands_templates() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let mut txt =
+        record("r2", &z1, "box1.example.com", "host box1.example.com");
+    txt["type"] = json!("TXT");
+    for (body, result) in [
+        (
+            json!({
+                "name": "box1.example.com",
+                "type": "A",
+                "content": "198.51.100.7",
+                "ttl": 1,
+                "proxied": true,
+            }),
+            record("r1", &z1, "box1.example.com", "198.51.100.7"),
+        ),
+        (
+            json!({
+                "name": "box1.example.com",
+                "type": "TXT",
+                "content": "host box1.example.com",
+                "ttl": 3600,
+                "proxied": false,
+            }),
+            txt,
+        ),
+    ] {
+        Mock::given(method("POST"))
+            .and(path("/zones/z1/dns_records"))
+            .and(body_json(body))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "errors": [],
+                "messages": [],
+                "result": result,
+            })))
+            .expect(1)
+            .mount(&harness.server)
+            .await;
+    }
+    let config = harness.path("cddns/config.toml");
+    std::fs::create_dir_all(config.parent().unwrap()).unwrap();
+    std::fs::write(
+        &config,
+        "[ip]\n\
+        command = \"echo 198.51.100.7\"\n\
+        [[templates.new-host.records]]\n\
+        type = \"A\"\n\
+        proxied = true\n\
+        [[templates.new-host.records]]\n\
+        type = \"TXT\"\n\
+        content = \"host {name}\"\n\
+        ttl = 3600\n",
+    )
+    .unwrap();
+
+    // The zone is inferred from the name
+    let output = harness
+        .run(&[
+            "record",
+            "create",
+            "box1.example.com",
+            "--template",
+            "new-host",
+        ])
+        .await;
+    let logs = stdout(&output);
+    assert!(output.status.success(), "{logs}");
+    assert!(logs.contains("box1.example.com: r2 => host"), "{logs}");
+
+    let output = harness
+        .run(&["record", "create", "box2.example.com", "--template", "none"])
+        .await;
+    let missing = stdout(&output);
+    assert_eq!(output.status.code(), Some(3), "{missing}");
+    assert!(missing.contains("no template named 'none'"), "{missing}");
+}
+
+#[tokio::test]
+async fn record_delete_removes_from_inventory() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let mut aaaa = record("r2", &z1, "old.example.com", "2001:db8::1");
+    aaaa["type"] = json!("AAAA");
+    harness
+        .records(
+            "z1",
+            vec![
+                record("r1", &z1, "old.example.com", "192.0.2.1"),
+                aaaa,
+                record("r3", &z1, "www.example.com", "192.0.2.3"),
+            ],
+            1,
+            1,
+        )
+        .await;
+    for id in ["r1", "r2"] {
+        Mock::given(method("DELETE"))
+            .and(path(format!("/zones/z1/dns_records/{id}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "errors": [],
+                "messages": [],
+                "result": { "id": id },
+            })))
+            .expect(1)
+            .mount(&harness.server)
+            .await;
+    }
+    let inventory = harness
+        .inventory("z1:\n  - old.example.com\n  - r2\n  - www.example.com\n");
+
+    let output = harness
+        .command(&[
+            "record",
+            "delete",
+            "old.example.com",
+            "--yes",
+            "--inventory",
+        ])
+        .env("CDDNS_INVENTORY_PATH", &inventory)
+        .output()
+        .await
+        .unwrap();
+    let logs = stdout(&output);
+    assert!(output.status.success(), "{logs}");
+    assert!(logs.contains("deleted AAAA record"), "{logs}");
+    let saved = std::fs::read_to_string(&inventory).unwrap();
+    assert!(saved.contains("www.example.com"), "{saved}");
+    assert!(!saved.contains("old.example.com"), "{saved}");
+    assert!(!saved.contains("- r2"), "{saved}");
+
+    let output = harness.run(&["record", "delete", "gone.example.com"]).await;
+    let missing = stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{missing}");
+    assert!(missing.contains("record with id/name"), "{missing}");
+}
+
+#[tokio::test]
+async fn acme_sets_and_clears_challenges() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let name = "_acme-challenge.example.com";
+    let mut existing = record("r1", &z1, name, "\"old-token\"");
+    existing["type"] = json!("TXT");
+    harness.records("z1", vec![existing.clone()], 1, 1).await;
+    let mut created = record("r2", &z1, name, "new-token");
+    created["type"] = json!("TXT");
+    Mock::given(method("POST"))
+        .and(path("/zones/z1/dns_records"))
+        .and(body_json(json!({
+            "name": name,
+            "type": "TXT",
+            "content": "new-token",
+            "ttl": 1,
+            "proxied": false,
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": created,
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": { "id": "r1" },
+        })))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+
+    let output = harness
+        .run(&["acme", "set-txt", "--name", name, "--value", "new-token"])
+        .await;
+    assert!(output.status.success(), "{}", stdout(&output));
+
+    // Existing challenges are not created twice
+    let output = harness
+        .run(&["acme", "set-txt", "--name", name, "--value", "old-token"])
+        .await;
+    let logs = stdout(&output);
+    assert!(output.status.success(), "{logs}");
+    assert!(logs.contains("challenge record already exists"), "{logs}");
+
+    let output = harness
+        .run(&["acme", "clear-txt", "--name", name, "--value", "old-token"])
+        .await;
+    let logs = stdout(&output);
+    assert!(output.status.success(), "{logs}");
+    assert!(logs.contains("deleted challenge record"), "{logs}");
+}
+
+#[tokio::test]
+async fn check_compares_against_expected_ip() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - a.example.com\n");
+    let check = |ip: &'static str| {
+        [
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "check",
+            "--expect-ip",
+            ip,
+        ]
+    };
+
+    // No public IP is served, so it must not be resolved
+    let matched = harness.run(&check("192.0.2.1")).await;
+    assert!(matched.status.success(), "{}", stdout(&matched));
+
+    let mismatched = harness.run(&check("192.0.2.2")).await;
+    assert_eq!(mismatched.status.code(), Some(6), "{}", stdout(&mismatched));
+}
+
+#[tokio::test]
+async fn check_via_dns_falls_back_to_api_for_ids() {
+    let harness = Harness::new().await;
+    harness.ip("192.0.2.1").await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.2")],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - r1\n");
+
+    // IDs cannot be resolved, so records are listed through the API
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "check",
+            "--via",
+            "dns",
+        ])
+        .await;
+    assert_eq!(output.status.code(), Some(6), "{}", stdout(&output));
+    assert!(stdout(&output).contains("checking records through the API"));
+}
+
+#[tokio::test]
+async fn check_compares_proxied_records_by_origin() {
+    let harness = Harness::new().await;
+    harness.ip("192.0.2.1").await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let mut origin = record("r1", &z1, "a.example.com", "192.0.2.1");
+    origin["proxied"] = json!(true);
+    let mut edge = record("r2", &z1, "b.example.com", "104.16.0.1");
+    edge["proxied"] = json!(true);
+    harness.records("z1", vec![origin, edge], 1, 1).await;
+
+    // The origin value is compared, not the edge address it resolves to
+    let inventory = harness.inventory("z1:\n  - r1\n");
+    let output = harness
+        .run(&["inventory", "-p", inventory.to_str().unwrap(), "check"])
+        .await;
+    assert!(output.status.success(), "{}", stdout(&output));
+
+    let inventory = harness.inventory("z1:\n  - r2\n");
+    let output = harness
+        .run(&["inventory", "-p", inventory.to_str().unwrap(), "check"])
+        .await;
+    assert_eq!(output.status.code(), Some(6), "{}", stdout(&output));
+    assert!(stdout(&output).contains("Cloudflare edge address"));
+}
+
+#[tokio::test]
+async fn check_warns_about_long_ttl() {
+    let harness = Harness::new().await;
+    harness.ip("192.0.2.1").await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let mut day = record("r1", &z1, "a.example.com", "192.0.2.1");
+    day["ttl"] = json!(86400);
+    let mut short = record("r2", &z1, "b.example.com", "192.0.2.1");
+    short["ttl"] = json!(60);
+    harness.records("z1", vec![day, short], 1, 1).await;
+    let inventory = harness.inventory("z1:\n  - r1\n  - r2\n");
+
+    let output = harness
+        .run(&["inventory", "-p", inventory.to_str().unwrap(), "check"])
+        .await;
+    assert!(output.status.success(), "{}", stdout(&output));
+    let logs = stdout(&output);
+    assert!(logs.contains("'a.example.com' has a TTL of 86400 seconds"));
+    assert!(!logs.contains("'b.example.com'"));
+}
+
+#[tokio::test]
+async fn check_flags_suspicious_content() {
+    let harness = Harness::new().await;
+    harness.ip("192.0.2.1").await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![
+                record("r1", &z1, "a.example.com", "10.0.0.1"),
+                record("r2", &z1, "b.example.com", "192.0.2.2"),
+            ],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - r1\n  - r2\n");
+
+    let output = harness
+        .run(&["inventory", "-p", inventory.to_str().unwrap(), "check"])
+        .await;
+    assert_eq!(output.status.code(), Some(6), "{}", stdout(&output));
+    let logs = stdout(&output);
+    assert!(logs.contains("inventory contains 2 outdated records"));
+    assert!(logs.contains("inventory contains 2 suspicious records"));
+}
+
+#[tokio::test]
+async fn watch_exits_after_max_failures() {
+    let harness = Harness::new().await;
+    // No public IP is served, so every check fails
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - r1\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--watch-interval",
+            "1",
+            "--watch-max-failures",
+            "2",
+            "watch",
+        ])
+        .await;
+    assert!(!output.status.success(), "{}", stdout(&output));
+    assert!(stdout(&output).contains("too many consecutive failures"));
+}
+
+#[tokio::test]
+async fn watch_refreshes_groups_on_their_own_interval() {
+    let harness = Harness::new().await;
+    // No public IP is served, so every check fails
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![
+                record("r1", &z1, "a.example.com", "192.0.2.1"),
+                record("r2", &z1, "b.example.com", "192.0.2.1"),
+            ],
+            1,
+            1,
+        )
+        .await;
+    let config = harness.path("cddns/config.toml");
+    std::fs::create_dir_all(config.parent().unwrap()).unwrap();
+    std::fs::write(
+        &config,
+        "[inventory.groups.critical]\n\
+        records = [\"r1\"]\n\
+        interval = 1\n",
+    )
+    .unwrap();
+    let inventory = harness.inventory("z1:\n  - r1\n  - r2\n");
+
+    // Only the critical group is refreshed often enough to exit
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--watch-interval",
+            "3600000",
+            "--watch-max-failures",
+            "3",
+            "watch",
+        ])
+        .await;
+    assert!(!output.status.success(), "{}", stdout(&output));
+    assert!(stdout(&output).contains("too many consecutive failures"));
+}
+
+#[tokio::test]
+async fn watch_once_runs_a_single_iteration() {
+    let harness = Harness::new().await;
+    harness.ip("192.0.2.1").await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - r1\n");
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "watch",
+            "--once",
+        ])
+        .await;
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(stdout(&output).contains("inventory is up to date"));
+}
+
+#[tokio::test]
+async fn service_install_writes_systemd_unit() {
+    let harness = Harness::new().await;
+    let config = harness.path("cddns/config.toml");
+    let inventory = harness.path("cddns/inventory.yml");
+
+    let output = harness
+        .run(&[
+            "--config",
+            config.to_str().unwrap(),
+            "service",
+            "install",
+            "--systemd",
+            "--user",
+        ])
+        .await;
+    assert!(output.status.success(), "{}", stdout(&output));
+    let unit =
+        std::fs::read_to_string(harness.path("systemd/user/cddns.service"))
+            .unwrap();
+    assert!(unit.contains(&format!(
+        "--config \"{}\" inventory --path \"{}\" watch",
+        config.display(),
+        inventory.display()
+    )));
+    assert!(unit.contains("Restart=on-failure"));
+    assert!(unit.contains("WantedBy=default.target"));
+
+    // System units are hardened further
+    let output = harness
+        .run(&["service", "install", "--systemd", "--stdout"])
+        .await;
+    assert!(output.status.success(), "{}", stdout(&output));
+    let unit = stdout(&output);
+    assert!(unit.contains("ProtectSystem=strict"));
+    assert!(unit.contains("WantedBy=multi-user.target"));
+}
+
+#[tokio::test]
+async fn watch_is_ready_after_first_reconciliation() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - r1\n");
+    let addr = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    };
+    let probe = |path: &'static str| {
+        let addr = addr.clone();
+        async move {
+            let mut stream =
+                tokio::net::TcpStream::connect(&addr).await.ok()?;
+            let request = format!("GET {path} HTTP/1.1\r\nHost: cddns\r\n\r\n");
+            stream.write_all(request.as_bytes()).await.ok()?;
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.ok()?;
+            response.split_whitespace().nth(1).map(str::to_owned)
+        }
+    };
+
+    // The public IP is unavailable until the first probes are answered
+    let mut watch = harness
+        .command(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--watch-listen",
+            &addr,
+            "--watch-interval",
+            "100",
+            "watch",
+        ])
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    let mut live = None;
+    for _ in 0..50 {
+        live = probe("/livez").await;
+        if live.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(live.as_deref(), Some("200"));
+    assert_eq!(probe("/readyz").await.as_deref(), Some("503"));
+
+    harness.ip("192.0.2.1").await;
+    let mut ready = None;
+    for _ in 0..50 {
+        ready = probe("/readyz").await;
+        if ready.as_deref() == Some("200") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(ready.as_deref(), Some("200"));
+    watch.kill().await.unwrap();
+}
+
+#[tokio::test]
+async fn env_only_ignores_config_files() {
+    let harness = Harness::new().await;
+    harness.ip("192.0.2.1").await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - r1\n");
+    let config = harness.path("cddns/config.toml");
+    std::fs::create_dir_all(config.parent().unwrap()).unwrap();
+    std::fs::write(
+        &config,
+        format!("[inventory]\npath = \"{}\"\n", inventory.display()),
+    )
+    .unwrap();
+
+    // The inventory path of the config file is ignored
+    let output = harness.run(&["--env-only", "inventory", "check"]).await;
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+    assert!(stdout(&output).contains("set CDDNS_INVENTORY_PATH"));
+
+    let output = harness
+        .run(&[
+            "--env-only",
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "check",
+        ])
+        .await;
+    assert!(output.status.success(), "{}", stdout(&output));
+
+    // Misspelled settings are refused
+    let output = harness
+        .command(&["--env-only", "inventory", "check"])
+        .env("CDDNS_INVENTORY_PAHT", &inventory)
+        .output()
+        .await
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+    assert!(stdout(&output).contains("CDDNS_INVENTORY_PAHT"));
+}
+
+#[tokio::test]
+async fn watch_writes_textfile_metrics() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.ip("192.0.2.9").await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/zones/z1/dns_records/r1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": record("r1", &z1, "a.example.com", "192.0.2.9"),
+        })))
+        .mount(&harness.server)
+        .await;
+    let inventory = harness.inventory("z1:\n  - r1\n");
+    let textfile_dir = harness.path("textfile");
+    std::fs::create_dir_all(&textfile_dir).unwrap();
+
+    let output = harness
+        .run(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--watch-textfile-dir",
+            textfile_dir.to_str().unwrap(),
+            "watch",
+            "--once",
+        ])
+        .await;
+    assert!(output.status.success(), "{}", stdout(&output));
+    let metrics =
+        std::fs::read_to_string(textfile_dir.join("cddns.prom")).unwrap();
+    assert!(metrics.contains("\ncddns_watch_iterations_total 1\n"));
+    assert!(metrics.contains("\ncddns_records_updated_total 1\n"));
+    assert!(metrics.contains("\ncddns_records_outdated 0\n"));
+    assert_eq!(std::fs::read_dir(&textfile_dir).unwrap().count(), 1);
+}
+
+#[tokio::test]
+async fn watch_condenses_routine_logs() {
+    let harness = Harness::new().await;
+    harness.ip("192.0.2.1").await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1")],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - r1\n");
+
+    let mut watch = harness
+        .command(&[
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "--watch-interval",
+            "100",
+            "--watch-summary",
+            "2",
+            "watch",
+        ])
+        .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+    watch.start_kill().unwrap();
+    let output = watch.wait_with_output().await.unwrap();
+    let logs = stdout(&output);
+    assert!(logs.contains("watch summary"), "{logs}");
+    assert!(!logs.contains("checking records"), "{logs}");
+}
+
+#[tokio::test]
+async fn log_level_filters_logs() {
+    let harness = Harness::new().await;
+    harness.ip("192.0.2.1").await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.9")],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - r1\n");
+
+    let output = harness
+        .run(&[
+            "--log-level",
+            "warn",
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "check",
+        ])
+        .await;
+    let logs = stdout(&output);
+    assert!(!logs.contains("checking records"), "{logs}");
+    assert!(logs.contains("outdated"), "{logs}");
+}
+
+#[tokio::test]
+async fn quiet_prints_only_data() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.9")],
+            1,
+            1,
+        )
+        .await;
+
+    let output = harness.run(&["-q", "list", "records"]).await;
+    assert!(output.status.success());
+    let logs = stdout(&output);
+    assert!(!logs.contains("please wait"), "{logs}");
+    assert!(logs.contains("a.example.com"), "{logs}");
+}
+
+#[tokio::test]
+async fn color_follows_flag_and_env() {
+    let harness = Harness::new().await;
+    let inventory = harness.inventory("");
+    let args = ["inventory", "-p", inventory.to_str().unwrap(), "show"];
+
+    // Piped output isn't colored by default
+    let output = harness.run(&args).await;
+    let logs = stdout(&output);
+    assert!(logs.contains("inventory is empty"), "{logs}");
+    assert!(!logs.contains('\u{1b}'), "{logs}");
+
+    let output = harness
+        .command(&args)
+        .env("CLICOLOR_FORCE", "1")
+        .output()
+        .await
+        .unwrap();
+    assert!(stdout(&output).contains('\u{1b}'));
+
+    let output = harness
+        .command(&args)
+        .env("CLICOLOR_FORCE", "1")
+        .env("NO_COLOR", "1")
+        .output()
+        .await
+        .unwrap();
+    assert!(!stdout(&output).contains('\u{1b}'));
+
+    let output = harness
+        .run(&[
+            "--color",
+            "always",
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "show",
+        ])
+        .await;
+    assert!(stdout(&output).contains('\u{1b}'));
+}
+
+#[tokio::test]
+async fn timestamps_are_toggleable() {
+    let harness = Harness::new().await;
+    let inventory = harness.inventory("");
+    let inventory = inventory.to_str().unwrap();
+
+    // One-shot commands omit timestamps by default
+    let output = harness.run(&["inventory", "-p", inventory, "show"]).await;
+    let logs = stdout(&output);
+    assert!(logs.contains("\n WARN inventory is empty"), "{logs}");
+
+    let output = harness
+        .run(&["--timestamps", "true", "inventory", "-p", inventory, "show"])
+        .await;
+    let logs = stdout(&output);
+    assert!(logs.contains("Z  WARN inventory is empty"), "{logs}");
+}
+
+#[tokio::test]
+async fn list_records_shows_columns() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let mut r1 = record("r1", &z1, "a.example.com", "192.0.2.1");
+    r1["ttl"] = json!(300);
+    r1["proxied"] = json!(true);
+    r1["modified_on"] = json!("2024-05-06T07:08:09.123456Z");
+    let mut r2 = record("r2", &z1, "bb.example.com", "192.0.2.22");
+    r2["ttl"] = json!(1);
+    harness.records("z1", vec![r1, r2], 1, 1).await;
+
+    let output = harness.run(&["-q", "list", "records"]).await;
+    let stdout = stdout(&output);
+
+    assert!(output.status.success(), "{stdout}");
+    let lines = stdout.lines().collect::<Vec<&str>>();
+    assert_eq!(
+        lines[1..4],
+        [
+            "  NAME            TYPE  CONTENT     TTL   PROXIED  MODIFIED             ID",
+            "  a.example.com   A     192.0.2.1   300   yes      2024-05-06 07:08:09  r1",
+            "  bb.example.com  A     192.0.2.22  auto  no       -                    r2",
+        ],
+        "{stdout}"
+    );
+}
+
+#[tokio::test]
+async fn list_records_all_types() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let mut mx = record("r2", &z1, "example.com", "mail.example.com");
+    mx["type"] = json!("MX");
+    harness
+        .records(
+            "z1",
+            vec![record("r1", &z1, "a.example.com", "192.0.2.1"), mx],
+            1,
+            1,
+        )
+        .await;
+
+    let output = harness.run(&["-q", "list", "records"]).await;
+    let managed = stdout(&output);
+    assert!(managed.contains("total records: 1"), "{managed}");
+    assert!(!managed.contains("mail.example.com"), "{managed}");
+
+    let output = harness.run(&["-q", "list", "records", "--all-types"]).await;
+    let all = stdout(&output);
+    assert!(output.status.success(), "{all}");
+    assert!(all.contains("total records: 2"), "{all}");
+    assert!(all.contains("MX    mail.example.com"), "{all}");
+}
+
+#[tokio::test]
+async fn list_records_by_query() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let mut proxied = record("r3", &z1, "vpn.example.com", "192.0.2.3");
+    proxied["proxied"] = json!(true);
+    harness
+        .records(
+            "z1",
+            vec![
+                record("r1", &z1, "vpn.example.com", "192.0.2.1"),
+                record("r2", &z1, "www.example.com", "192.0.2.2"),
+                proxied,
+            ],
+            1,
+            1,
+        )
+        .await;
+
+    let query = r"type:A AND name~'^vpn\.' AND proxied:false";
+    let output = harness
+        .run(&["-q", "list", "--query", query, "records", "--ids"])
+        .await;
+    let listed = stdout(&output);
+    assert!(output.status.success(), "{listed}");
+    assert_eq!(listed, "r1\n");
+
+    let output = harness
+        .run(&["-q", "list", "--query", "type:A AND (name", "records"])
+        .await;
+    let invalid = stdout(&output);
+    assert_eq!(output.status.code(), Some(3), "{invalid}");
+    assert!(invalid.contains("invalid query"), "{invalid}");
+}
+
+#[tokio::test]
+async fn list_records_by_tag() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    let mut tagged = record("r1", &z1, "home.example.com", "192.0.2.1");
+    tagged["tags"] = json!(["ddns:home"]);
+    let mut other = record("r2", &z1, "www.example.com", "192.0.2.2");
+    other["tags"] = json!(["web"]);
+    harness
+        .records(
+            "z1",
+            vec![
+                tagged,
+                other,
+                record("r3", &z1, "vpn.example.com", "192.0.2.3"),
+            ],
+            1,
+            1,
+        )
+        .await;
+
+    let output = harness
+        .run(&["-q", "list", "--tag", "ddns", "records", "--ids"])
+        .await;
+    let listed = stdout(&output);
+    assert!(output.status.success(), "{listed}");
+    assert_eq!(listed, "r1\n");
+}
+
+#[tokio::test]
+async fn inventory_selects_records_by_query() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![
+                record("r1", &z1, "vpn.example.com", "192.0.2.1"),
+                record("r2", &z1, "www.example.com", "192.0.2.2"),
+            ],
+            1,
+            1,
+        )
+        .await;
+    let inventory = harness.inventory("z1:\n  - \"query:name~'^vpn'\"\n");
+    let check = |ip: &'static str| {
+        [
+            "inventory",
+            "-p",
+            inventory.to_str().unwrap(),
+            "check",
+            "--expect-ip",
+            ip,
+        ]
+    };
+
+    // Only the selected record is compared
+    let matched = harness.run(&check("192.0.2.1")).await;
+    assert!(matched.status.success(), "{}", stdout(&matched));
+
+    let mismatched = harness.run(&check("192.0.2.2")).await;
+    assert_eq!(mismatched.status.code(), Some(6), "{}", stdout(&mismatched));
+}
+
+#[tokio::test]
+async fn list_sorts_records_and_zones() {
+    let harness = Harness::new().await;
+    let (mut z1, mut z2) = (zone("z1", "a.com"), zone("z2", "b.com"));
+    z1["modified_on"] = json!("2024-02-01T00:00:00Z");
+    z2["modified_on"] = json!("2024-01-01T00:00:00Z");
+    harness.zones(vec![z1.clone(), z2.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![
+                record("r1", &z1, "x.a.com", "192.0.2.2"),
+                record("r2", &z1, "y.a.com", "192.0.2.3"),
+                record("r3", &z1, "z.a.com", "192.0.2.1"),
+            ],
+            1,
+            1,
+        )
+        .await;
+    harness.records("z2", vec![], 1, 1).await;
+
+    // Return the names in the order they were printed
+    let order = |output: &std::process::Output, names: &[&'static str]| {
+        let stdout = stdout(output);
+        let mut names = names.to_vec();
+        names.sort_by_key(|name| stdout.find(name));
+        names
+    };
+
+    let args = ["-q", "list", "records", "--sort", "content"];
+    let output = harness.run(&args).await;
+    let names = ["x.a.com", "y.a.com", "z.a.com"];
+    assert_eq!(order(&output, &names), ["z.a.com", "x.a.com", "y.a.com"]);
+
+    let output = harness.run(&[&args[..], &["--desc"]].concat()).await;
+    assert_eq!(order(&output, &names), ["y.a.com", "x.a.com", "z.a.com"]);
+
+    let output = harness
+        .run(&["-q", "list", "zones", "--sort", "modified"])
+        .await;
+    assert_eq!(order(&output, &["a.com", "b.com"]), ["b.com", "a.com"]);
+}
+
+#[tokio::test]
+async fn list_retrieves_a_single_page_of_records() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    Mock::given(method("GET"))
+        .and(path("/zones/z1/dns_records"))
+        .and(query_param("page", "2"))
+        .and(query_param("per_page", "3"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            common::envelope(
+                vec![record("r4", &z1, "d.example.com", "192.0.2.4")],
+                4,
+            ),
+        ))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+
+    let output = harness
+        .run(&["-q", "list", "records", "--page", "2", "--limit", "3"])
+        .await;
+    let listed = stdout(&output);
+    assert!(output.status.success(), "{listed}");
+    assert!(listed.contains("d.example.com"), "{listed}");
+}
+
+#[tokio::test]
+async fn list_prints_only_ids() {
+    let harness = Harness::new().await;
+    let (z1, z2) = (zone("z1", "example.com"), zone("z2", "example.dev"));
+    harness.zones(vec![z1.clone(), z2.clone()], 1, 1).await;
+    harness
+        .records(
+            "z1",
+            vec![
+                record("r1", &z1, "a.example.com", "192.0.2.1"),
+                record("r2", &z1, "b.example.com", "192.0.2.2"),
+            ],
+            1,
+            1,
+        )
+        .await;
+    harness.records("z2", vec![], 1, 1).await;
+
+    let output = harness.run(&["list", "zones", "--ids"]).await;
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "z1\nz2\n");
+
+    let output = harness.run(&["list", "records", "--ids"]).await;
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "r1\nr2\n");
+}
+
+#[tokio::test]
+async fn list_zones_shows_details() {
+    let harness = Harness::new().await;
+    let mut z1 = zone("z1", "example.com");
+    z1["account"] = json!({ "id": "a1", "name": "Example Org" });
+    z1["plan"] = json!({ "name": "Free Website" });
+    z1["name_servers"] =
+        json!(["ada.ns.cloudflare.com", "bob.ns.cloudflare.com"]);
+    let mut z2 = zone("z2", "example.dev");
+    z2["paused"] = json!(true);
+    harness.zones(vec![z1, z2], 1, 1).await;
+    for (id, status) in [("z1", "active"), ("z2", "disabled")] {
+        Mock::given(method("GET"))
+            .and(path(format!("/zones/{id}/dnssec")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "errors": [],
+                "messages": [],
+                "result": { "status": status },
+            })))
+            .mount(&harness.server)
+            .await;
+    }
+
+    let output = harness.run(&["-q", "list", "zones", "--details"]).await;
+    let stdout = stdout(&output);
+
+    assert!(output.status.success(), "{stdout}");
+    let lines = stdout.lines().collect::<Vec<&str>>();
+    assert_eq!(
+        lines,
+        [
+            "NAME         STATUS  ACCOUNT      PLAN          DNSSEC    NAMESERVERS                                  ID",
+            "example.com  active  Example Org  Free Website  active    ada.ns.cloudflare.com,bob.ns.cloudflare.com  z1",
+            "example.dev  paused  -            -             disabled  -                                            z2",
+        ],
+        "{stdout}"
+    );
+}
+
+#[tokio::test]
+async fn list_scopes_zones_to_an_account() {
+    let harness = Harness::new().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            common::envelope(
+                vec![
+                    json!({ "id": "a1", "name": "Example Org" }),
+                    json!({ "id": "a2", "name": "Other Org" }),
+                ],
+                1,
+            ),
+        ))
+        .mount(&harness.server)
+        .await;
+    let (mut z1, mut z2) =
+        (zone("z1", "example.com"), zone("z2", "example.dev"));
+    z1["account"] = json!({ "id": "a1", "name": "Example Org" });
+    z2["account"] = json!({ "id": "a2", "name": "Other Org" });
+    harness.zones(vec![z1, z2], 1, 1).await;
+
+    let output = harness.run(&["-q", "list", "accounts"]).await;
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "Example Org: a1\nOther Org: a2\n");
+
+    for account in ["a2", "Other Org"] {
+        let output = harness
+            .run(&["-q", "list", "--account", account, "zones"])
+            .await;
+        assert_eq!(stdout(&output), "example.dev: z2\n");
+    }
+}
+
+#[tokio::test]
+async fn list_records_of_several_zones() {
+    let harness = Harness::new().await;
+    let (z1, z2, z3) = (
+        zone("z1", "example.com"),
+        zone("z2", "example.dev"),
+        zone("z3", "example.org"),
+    );
+    harness
+        .zones(vec![z1.clone(), z2.clone(), z3.clone()], 1, 1)
+        .await;
+    for (id, zone, name) in [
+        ("z1", &z1, "a.example.com"),
+        ("z2", &z2, "a.example.dev"),
+        ("z3", &z3, "a.example.org"),
+    ] {
+        harness
+            .records(id, vec![record(id, zone, name, "192.0.2.1")], 1, 1)
+            .await;
+    }
+
+    let output = harness
+        .run(&["-q", "list", "records", "-z", "example.com", "-z", "z2"])
+        .await;
+    let listed = stdout(&output);
+    assert!(output.status.success(), "{listed}");
+    assert!(listed.contains("a.example.com"), "{listed}");
+    assert!(listed.contains("a.example.dev"), "{listed}");
+    assert!(!listed.contains("a.example.org"), "{listed}");
+
+    let output = harness
+        .run(&["list", "records", "-z", "z1", "-z", "example.net"])
+        .await;
+    let logs = stdout(&output);
+    assert!(!output.status.success(), "{logs}");
+    assert!(logs.contains("'example.net'"), "{logs}");
+}
+
+#[tokio::test]
+async fn list_pages_without_a_page_count() {
+    let harness = Harness::new().await;
+    let z1 = zone("z1", "example.com");
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    // Pages counted by `total_count` and `per_page`
+    for (page, name) in [(1, "a.example.com"), (2, "b.example.com")] {
+        Mock::given(method("GET"))
+            .and(path("/zones/z1/dns_records"))
+            .and(query_param("page", page.to_string()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "errors": [],
+                "messages": [],
+                "result": [record("r1", &z1, name, "192.0.2.1")],
+                "result_info": { "page": page, "per_page": 1, "total_count": 2 },
+            })))
+            .expect(1)
+            .mount(&harness.server)
+            .await;
+    }
+
+    let output = harness.run(&["-q", "list", "records"]).await;
+    let listed = stdout(&output);
+    assert!(output.status.success(), "{listed}");
+    assert!(listed.contains("a.example.com"), "{listed}");
+    assert!(listed.contains("b.example.com"), "{listed}");
+
+    // Pages retrieved until one is empty, without metadata
+    harness.server.reset().await;
+    harness.zones(vec![z1.clone()], 1, 1).await;
+    for (page, records) in [
+        (1, vec![record("r1", &z1, "a.example.com", "192.0.2.1")]),
+        (2, vec![record("r2", &z1, "b.example.com", "192.0.2.1")]),
+        (3, vec![]),
+    ] {
+        Mock::given(method("GET"))
+            .and(path("/zones/z1/dns_records"))
+            .and(query_param("page", page.to_string()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "errors": [],
+                "messages": [],
+                "result": records,
+            })))
+            .expect(1)
+            .mount(&harness.server)
+            .await;
+    }
+
+    let output = harness.run(&["list", "records"]).await;
+    let listed = stdout(&output);
+    assert!(output.status.success(), "{listed}");
+    assert!(listed.contains("b.example.com"), "{listed}");
+    assert!(listed.contains("no page count"), "{listed}");
+}
+
+#[tokio::test]
+async fn verify_waits_for_a_token_until_the_deadline() {
+    let harness = Harness::new().await;
+    Mock::given(method("GET"))
+        .and(path("/user/tokens/verify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [],
+            "result": { "status": "active" },
+        })))
+        .mount(&harness.server)
+        .await;
+
+    // The token is provided by a config file written after startup
+    let config = harness.path("secret.toml");
+    let config_arg = config.to_str().unwrap();
+    let mut command = harness.command(&[
+        "--config",
+        config_arg,
+        "verify",
+        "--wait",
+        "--timeout",
+        "30s",
+    ]);
+    let child = command.env_remove("CDDNS_VERIFY_TOKEN").output();
+    let write = async {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        std::fs::write(&config, format!("[verify]\ntoken = \"{TOKEN}\"\n"))
+            .unwrap();
+    };
+    let (output, ()) = tokio::join!(child, write);
+    let output = output.unwrap();
+    assert!(output.status.success(), "{}", stdout(&output));
+
+    // Slow attempts are cut short by the deadline
+    harness.server.reset().await;
+    Mock::given(method("GET"))
+        .and(path("/user/tokens/verify"))
+        .respond_with(
+            ResponseTemplate::new(200).set_delay(Duration::from_secs(60)),
+        )
+        .mount(&harness.server)
+        .await;
+    let started = std::time::Instant::now();
+    let output = harness.run(&["verify", "--wait", "--timeout", "1s"]).await;
+    assert!(!output.status.success(), "{}", stdout(&output));
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn verify_reports_only_the_result_for_scripts() {
+    let harness = Harness::new().await;
+    Mock::given(method("GET"))
+        .and(path("/user/tokens/verify"))
+        .respond_with(
+            ResponseTemplate::new(401)
+                .set_body_json(failure(1000, "Invalid API Token")),
+        )
+        .mount(&harness.server)
+        .await;
+
+    let output = harness.run(&["verify", "--quiet"]).await;
+    assert_eq!(output.status.code(), Some(4));
+    assert!(output.stdout.is_empty(), "{}", stdout(&output));
+    assert!(output.stderr.is_empty());
+
+    let output = harness.run(&["verify", "--quiet", "--json"]).await;
+    let result: serde_json::Value =
+        serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(result["valid"], false);
+    assert_eq!(result["error"]["code"], "AUTH_FAILED");
+
+    // Logs go to stderr, leaving stdout to the result
+    let output = harness.run(&["verify", "--json"]).await;
+    let result: serde_json::Value =
+        serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(result["error"]["code"], "AUTH_FAILED");
+    assert!(!output.stderr.is_empty());
+
+    harness.server.reset().await;
+    Mock::given(method("GET"))
+        .and(path("/user/tokens/verify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "errors": [],
+            "messages": [{ "code": 10000, "message": "This API Token is valid and active" }],
+            "result": { "status": "active" },
+        })))
+        .mount(&harness.server)
+        .await;
+
+    let output = harness.run(&["verify", "--json"]).await;
+    let result: serde_json::Value =
+        serde_json::from_str(&stdout(&output)).unwrap();
+    assert!(output.status.success(), "{result}");
+    assert_eq!(result["valid"], true);
+    assert_eq!(result["messages"][0], "This API Token is valid and active");
+}
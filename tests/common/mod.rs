@@ -0,0 +1,165 @@
+//! A harness running the cddns binary against a mock Cloudflare API.
+
+#![allow(dead_code)]
+
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::process::Output;
+use tempfile::TempDir;
+use tokio::process::Command;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// The token sent by the binary under test.
+pub const TOKEN: &str = "test-token";
+
+/// A mock Cloudflare API and an isolated home directory.
+pub struct Harness {
+    pub server: MockServer,
+    pub home: TempDir,
+}
+
+impl Harness {
+    pub async fn new() -> Self {
+        Self {
+            server: MockServer::start().await,
+            home: TempDir::new().expect("temporary home directory"),
+        }
+    }
+
+    /// Return the path of a file in the isolated home directory.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.home.path().join(name)
+    }
+
+    /// Write an inventory file, returning its path.
+    pub fn inventory(&self, contents: &str) -> PathBuf {
+        let path = self.path("inventory.yml");
+        std::fs::write(&path, contents).expect("inventory file");
+        path
+    }
+
+    /// Write an audit log, returning its path.
+    pub fn audit(&self, entries: &[Value]) -> PathBuf {
+        let path = self.path("cddns/audit.jsonl");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let lines: Vec<String> =
+            entries.iter().map(|e| format!("{e}\n")).collect();
+        std::fs::write(&path, lines.concat()).expect("audit log");
+        path
+    }
+
+    /// Serve the public IP address.
+    pub async fn ip(&self, ip: &str) {
+        Mock::given(method("GET"))
+            .and(path("/ip"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(ip))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Serve a page of zones.
+    pub async fn zones(&self, zones: Vec<Value>, page: u32, total_pages: u32) {
+        Mock::given(method("GET"))
+            .and(path("/zones"))
+            .and(query_param("page", page.to_string()))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(envelope(zones, total_pages)),
+            )
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Serve a page of records for a zone.
+    pub async fn records(
+        &self,
+        zone_id: &str,
+        records: Vec<Value>,
+        page: u32,
+        total_pages: u32,
+    ) {
+        Mock::given(method("GET"))
+            .and(path(format!("/zones/{zone_id}/dns_records")))
+            .and(query_param("page", page.to_string()))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(envelope(records, total_pages)),
+            )
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Run the binary with the given arguments against the mock API.
+    pub async fn run(&self, args: &[&str]) -> Output {
+        self.command(args).output().await.expect("cddns binary")
+    }
+
+    /// Return a command running the binary with the given arguments against
+    /// the mock API, for processes which outlive a single call. Its
+    /// directories are those of the isolated home on Linux.
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_cddns"));
+        command
+            .args(args)
+            .env_clear()
+            .env("HOME", self.home.path())
+            .env("XDG_CONFIG_HOME", self.home.path())
+            .env("XDG_CACHE_HOME", self.home.path())
+            .env("XDG_STATE_HOME", self.home.path())
+            .env("CDDNS_API_BASE_URL", self.server.uri())
+            .env("CDDNS_API_RETRY_BACKOFF", "1")
+            .env("CDDNS_VERIFY_TOKEN", TOKEN)
+            .env("CDDNS_IP_IPV4_URLS", format!("{}/ip", self.server.uri()));
+        command
+    }
+}
+
+/// A zone which cddns is permitted to edit.
+pub fn zone(id: &str, name: &str) -> Value {
+    json!({
+        "id": id,
+        "name": name,
+        "permissions": ["#zone:edit"],
+        "status": "active",
+    })
+}
+
+/// An A record.
+pub fn record(id: &str, zone: &Value, name: &str, content: &str) -> Value {
+    json!({
+        "id": id,
+        "zone_id": zone["id"],
+        "zone_name": zone["name"],
+        "name": name,
+        "type": "A",
+        "content": content,
+        "locked": false,
+    })
+}
+
+/// A successful Cloudflare response.
+pub fn envelope(result: Vec<Value>, total_pages: u32) -> Value {
+    json!({
+        "success": true,
+        "errors": [],
+        "messages": [],
+        "result": result,
+        "result_info": { "total_pages": total_pages },
+    })
+}
+
+/// An unsuccessful Cloudflare response.
+pub fn failure(code: i32, message: &str) -> Value {
+    json!({
+        "success": false,
+        "errors": [{ "code": code, "message": message }],
+        "messages": [],
+        "result": null,
+    })
+}
+
+/// Return the standard output of a run.
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}